@@ -0,0 +1,41 @@
+use crate::models::NewAlertRule;
+use crate::services::DataService;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct AlertRulesQuery {
+    symbol: Option<String>,
+}
+
+pub async fn create_alert_rule(
+    State(data_service): State<Arc<DataService>>,
+    Json(rule): Json<NewAlertRule>,
+) -> Response {
+    match data_service.add_alert_rule(rule) {
+        Ok(rule) => (StatusCode::CREATED, Json(rule)).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, format!("{:#}", err)).into_response(),
+    }
+}
+
+pub async fn list_alert_rules(
+    Query(query): Query<AlertRulesQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    Json(data_service.alert_rules(query.symbol.as_deref())).into_response()
+}
+
+pub async fn delete_alert_rule(Path(id): Path<Uuid>, State(data_service): State<Arc<DataService>>) -> Response {
+    if data_service.remove_alert_rule(id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (StatusCode::NOT_FOUND, "Unknown alert rule").into_response()
+    }
+}
@@ -0,0 +1,79 @@
+use crate::models::Transaction;
+use crate::services::DataService;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::mpsc::error::TrySendError;
+
+/// A single trade or a batch.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum IngestBody {
+    Batch(Vec<Transaction>),
+    Single(Box<Transaction>),
+}
+
+#[derive(Serialize)]
+struct RejectedTransaction {
+    index: usize,
+    error: String,
+}
+
+pub async fn ingest_transactions(
+    Path(source): Path<String>,
+    State(data_service): State<Arc<DataService>>,
+    Json(body): Json<IngestBody>,
+) -> Response {
+    let sender = match data_service.sources().push_sender(&source) {
+        Some(sender) => sender,
+        None => return (StatusCode::NOT_FOUND, "Unknown push source").into_response(),
+    };
+
+    let transactions = match body {
+        IngestBody::Batch(transactions) => transactions,
+        IngestBody::Single(transaction) => vec![*transaction],
+    };
+
+    let now = data_service.now();
+    let mut accepted = 0;
+    let mut rejected = Vec::new();
+    for (index, transaction) in transactions.into_iter().enumerate() {
+        if let Err(err) = transaction.validate(now) {
+            rejected.push(RejectedTransaction {
+                index,
+                error: format!("{:#}", err),
+            });
+            continue;
+        }
+        match sender.try_send(transaction) {
+            Ok(()) => accepted += 1,
+            // Trades already queued stay accepted; the caller resends from `retry_from`.
+            Err(TrySendError::Full(_)) => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({
+                        "accepted": accepted,
+                        "rejected": rejected,
+                        "retry_from": index,
+                        "error": "Source is full, retry later"
+                    })),
+                )
+                    .into_response();
+            }
+            Err(TrySendError::Closed(_)) => {
+                return (StatusCode::SERVICE_UNAVAILABLE, "Source is not running").into_response();
+            }
+        }
+    }
+
+    Json(serde_json::json!({ "accepted": accepted, "rejected": rejected })).into_response()
+}
+
+pub async fn list_sources(State(data_service): State<Arc<DataService>>) -> Response {
+    Json(data_service.sources().health()).into_response()
+}
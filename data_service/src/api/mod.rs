@@ -1,3 +1,15 @@
+mod alerts;
+mod ingest;
+mod replays;
 mod rest;
+mod webhooks;
 
-pub use rest::{get_klines, health_check};
\ No newline at end of file
+pub use alerts::{create_alert_rule, delete_alert_rule, list_alert_rules};
+pub use ingest::{ingest_transactions, list_sources};
+pub use replays::{list_replays, pause_replay, resume_replay, seek_replay};
+pub use rest::{
+    get_bars, get_footprints, get_klines, get_stats, get_symbol, get_ticker, get_top_holders,
+    get_volume_profile, get_wallet_positions, health_check, list_patterns, list_symbols,
+    list_venues, list_whales,
+};
+pub use webhooks::{delete_dead_letter, list_dead_letters, list_webhooks, retry_dead_letter};
@@ -0,0 +1,81 @@
+use crate::services::{DataService, ReplayControl};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Serialize)]
+struct ReplayStatus {
+    name: String,
+    paused: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SeekBody {
+    /// Recorded time to continue from.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Looks a replay up by source name, with or without the `replay:` prefix.
+fn find_replay(data_service: &DataService, name: &str) -> Option<ReplayControl> {
+    let sources = data_service.sources();
+    sources
+        .replay_control(name)
+        .or_else(|| sources.replay_control(&format!("replay:{}", name)))
+}
+
+fn unknown_replay() -> Response {
+    (StatusCode::NOT_FOUND, "Unknown replay").into_response()
+}
+
+pub async fn list_replays(State(data_service): State<Arc<DataService>>) -> Response {
+    let sources = data_service.sources();
+    let replays: Vec<ReplayStatus> = sources
+        .replay_names()
+        .into_iter()
+        .filter_map(|name| {
+            let paused = sources.replay_control(&name)?.is_paused();
+            Some(ReplayStatus { name, paused })
+        })
+        .collect();
+    Json(replays).into_response()
+}
+
+pub async fn pause_replay(Path(name): Path<String>, State(data_service): State<Arc<DataService>>) -> Response {
+    match find_replay(&data_service, &name) {
+        Some(control) => {
+            control.pause();
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => unknown_replay(),
+    }
+}
+
+pub async fn resume_replay(Path(name): Path<String>, State(data_service): State<Arc<DataService>>) -> Response {
+    match find_replay(&data_service, &name) {
+        Some(control) => {
+            control.resume();
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => unknown_replay(),
+    }
+}
+
+pub async fn seek_replay(
+    Path(name): Path<String>,
+    State(data_service): State<Arc<DataService>>,
+    Json(body): Json<SeekBody>,
+) -> Response {
+    match find_replay(&data_service, &name) {
+        Some(control) => {
+            control.seek(body.timestamp);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => unknown_replay(),
+    }
+}
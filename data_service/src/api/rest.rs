@@ -1,18 +1,45 @@
-use crate::models::KLineInterval;
-use crate::services::DataService;
+use crate::models::{
+    Bar, BarType, CandlePattern, ChartType, KLine, KLineInterval, MarketCapKLine, PriceUnit,
+    TraderMetrics,
+};
+use crate::services::{
+    heikin_ashi_series, renko_bricks, validate_box_size, DataService, MAX_STATS_WINDOW,
+};
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    Json,
     response::{IntoResponse, Response},
+    Json,
 };
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 #[derive(Deserialize)]
 pub struct KLineQuery {
-    interval: String,
+    /// Required except for Renko bricks.
+    interval: Option<String>,
     limit: Option<usize>,
+    /// Defaults to the service's default venue.
+    venue: Option<String>,
+    #[serde(default)]
+    unit: PriceUnit,
+    /// Adds each candle's wallet metrics under `traders`.
+    #[serde(default)]
+    traders: bool,
+    #[serde(default)]
+    chart_type: ChartType,
+    /// Brick size for Renko charts, in price units.
+    box_size: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+struct CandleWithTraders<T> {
+    #[serde(flatten)]
+    candle: T,
+    traders: Option<TraderMetrics>,
 }
 
 pub async fn get_klines(
@@ -20,23 +47,257 @@ pub async fn get_klines(
     Query(query): Query<KLineQuery>,
     State(data_service): State<Arc<DataService>>,
 ) -> Response {
-    let interval = match KLineInterval::from_str(&query.interval) {
-        Some(interval) => interval,
-        None => {
-            return (StatusCode::BAD_REQUEST, "Invalid interval").into_response();
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let venue = query.venue.as_deref().unwrap_or(data_service.default_venue());
+
+    if query.chart_type == ChartType::Renko {
+        if query.traders {
+            return (StatusCode::BAD_REQUEST, "Renko bricks have no wallet metrics").into_response();
+        }
+        let box_size = query.box_size.unwrap_or_default();
+        let trades = data_service.recent_trades(venue, &symbol);
+        if let Err(err) = validate_box_size(box_size, &trades) {
+            return (StatusCode::BAD_REQUEST, format!("{:#}", err)).into_response();
         }
+        let bricks = renko_bricks(venue, &symbol, box_size, &trades, limit);
+        return match query.unit {
+            PriceUnit::Price => Json(bricks).into_response(),
+            PriceUnit::Mcap => match data_service.symbol_metadata(&symbol) {
+                Some(metadata) => {
+                    let bricks: Vec<MarketCapKLine<Bar>> =
+                        bricks.iter().map(|brick| MarketCapKLine::new(brick, &metadata)).collect();
+                    Json(bricks).into_response()
+                }
+                None => (StatusCode::BAD_REQUEST, "No supply metadata for symbol").into_response(),
+            },
+        };
+    }
+
+    let Some(interval) = query.interval.as_deref().and_then(KLineInterval::from_str) else {
+        return (StatusCode::BAD_REQUEST, "Invalid interval").into_response();
     };
+    let klines = match query.chart_type {
+        // Heikin-Ashi candles depend on all candles before them, so start from the oldest
+        ChartType::HeikinAshi => {
+            let mut candles = heikin_ashi_series(&data_service.get_venue_klines(venue, &symbol, interval, usize::MAX));
+            candles.drain(..candles.len().saturating_sub(limit));
+            candles
+        }
+        _ => data_service.get_venue_klines(venue, &symbol, interval, limit),
+    };
+    let traders = query
+        .traders
+        .then(|| data_service.get_venue_trader_metrics(venue, &symbol, interval, limit));
+
+    match query.unit {
+        PriceUnit::Price => candles_response(&klines, traders, KLine::clone),
+        PriceUnit::Mcap => match data_service.symbol_metadata(&symbol) {
+            Some(metadata) => candles_response(&klines, traders, |kline| MarketCapKLine::new(kline, &metadata)),
+            None => (StatusCode::BAD_REQUEST, "No supply metadata for symbol").into_response(),
+        },
+    }
+}
+
+/// Serializes candles, joined with their wallet metrics by `open_time` when requested.
+fn candles_response<T: Serialize>(
+    klines: &[KLine],
+    traders: Option<Vec<TraderMetrics>>,
+    candle: impl Fn(&KLine) -> T,
+) -> Response {
+    let Some(traders) = traders else {
+        return Json(klines.iter().map(candle).collect::<Vec<T>>()).into_response();
+    };
+    let mut traders: HashMap<_, _> = traders.into_iter().map(|metrics| (metrics.open_time, metrics)).collect();
+    let candles: Vec<CandleWithTraders<T>> = klines
+        .iter()
+        .map(|kline| CandleWithTraders {
+            candle: candle(kline),
+            traders: traders.remove(&kline.open_time),
+        })
+        .collect();
+    Json(candles).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct VolumeProfileQuery {
+    /// Defaults to 24 hours before `to`.
+    from: Option<DateTime<Utc>>,
+    /// Defaults to now.
+    to: Option<DateTime<Utc>>,
+    buckets: Option<usize>,
+    venue: Option<String>,
+    /// Percentage of volume in the value area.
+    value_area: Option<Decimal>,
+}
 
+pub async fn get_volume_profile(
+    Path(symbol): Path<String>,
+    Query(query): Query<VolumeProfileQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    let to = query.to.unwrap_or_else(|| data_service.now());
+    let from = query.from.unwrap_or(to - chrono::Duration::hours(24));
+    if from >= to {
+        return (StatusCode::BAD_REQUEST, "from must be before to").into_response();
+    }
+    let value_area = query.value_area.unwrap_or(Decimal::new(70, 0));
+    if value_area <= Decimal::ZERO || value_area > Decimal::ONE_HUNDRED {
+        return (StatusCode::BAD_REQUEST, "value_area must be a percentage above 0").into_response();
+    }
+
+    let buckets = query.buckets.unwrap_or(50).clamp(1, 1000);
+    let venue = query.venue.as_deref().unwrap_or(data_service.default_venue());
+    let profile = data_service.volume_profile(venue, &symbol, from, to, buckets, value_area / Decimal::ONE_HUNDRED);
+    Json(profile).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct BarQuery {
+    #[serde(rename = "type")]
+    bar_type: BarType,
+    threshold: Decimal,
+    limit: Option<usize>,
+    venue: Option<String>,
+}
+
+/// Closed tick, volume or dollar bars, for thresholds configured under `bars`.
+pub async fn get_bars(
+    Path(symbol): Path<String>,
+    Query(query): Query<BarQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    if !data_service.bars_enabled(&symbol, query.bar_type, query.threshold) {
+        return (StatusCode::NOT_FOUND, "No bars configured for symbol, type and threshold").into_response();
+    }
     let limit = query.limit.unwrap_or(100).min(1000);
-    let klines = data_service.get_klines(&symbol, interval, limit);
+    let venue = query.venue.as_deref().unwrap_or(data_service.default_venue());
+    Json(data_service.get_venue_bars(venue, &symbol, query.bar_type, query.threshold, limit)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct FootprintQuery {
+    interval: String,
+    limit: Option<usize>,
+    venue: Option<String>,
+}
+
+/// Closed footprint candles, for symbols and intervals that have footprints configured.
+pub async fn get_footprints(
+    Path(symbol): Path<String>,
+    Query(query): Query<FootprintQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    let Some(interval) = KLineInterval::from_str(&query.interval) else {
+        return (StatusCode::BAD_REQUEST, "Invalid interval").into_response();
+    };
+    if !data_service.footprints_enabled(&symbol, interval) {
+        return (StatusCode::NOT_FOUND, "No footprint configured for symbol and interval").into_response();
+    }
+    let limit = query.limit.unwrap_or(100).min(200);
+    let venue = query.venue.as_deref().unwrap_or(data_service.default_venue());
+    Json(data_service.get_venue_footprints(venue, &symbol, interval, limit)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    interval: String,
+    /// Candles to compute over; defaults to the first configured window.
+    window: Option<usize>,
+    venue: Option<String>,
+}
+
+pub async fn get_stats(
+    Path(symbol): Path<String>,
+    Query(query): Query<StatsQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    let Some(interval) = KLineInterval::from_str(&query.interval) else {
+        return (StatusCode::BAD_REQUEST, "Invalid interval").into_response();
+    };
+    let window = query.window.or(data_service.default_stats_window()).unwrap_or(20);
+    if !(2..=MAX_STATS_WINDOW).contains(&window) {
+        return (StatusCode::BAD_REQUEST, "window must be between 2 and 1000 candles").into_response();
+    }
+    let venue = query.venue.as_deref().unwrap_or(data_service.default_venue());
+    match data_service.return_stats(venue, &symbol, interval, window) {
+        Some(stats) => Json(stats).into_response(),
+        None => (StatusCode::NOT_FOUND, "No closed candles for symbol and interval").into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HoldersQuery {
+    limit: Option<usize>,
+}
+
+pub async fn get_wallet_positions(Path(wallet): Path<String>, State(data_service): State<Arc<DataService>>) -> Response {
+    Json(data_service.wallet_positions(&wallet)).into_response()
+}
+
+pub async fn get_top_holders(
+    Path(symbol): Path<String>,
+    Query(query): Query<HoldersQuery>,
+    State(data_service): State<Arc<DataService>>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).min(1000);
+    Json(data_service.top_holders(&symbol, limit)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct WhalesQuery {
+    symbol: Option<String>,
+    limit: Option<usize>,
+}
+
+pub async fn list_whales(Query(query): Query<WhalesQuery>, State(data_service): State<Arc<DataService>>) -> Response {
+    let limit = query.limit.unwrap_or(100).min(1000);
+    Json(data_service.recent_whales(query.symbol.as_deref(), limit)).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct PatternsQuery {
+    symbol: Option<String>,
+    interval: Option<String>,
+    pattern: Option<CandlePattern>,
+    limit: Option<usize>,
+}
+
+pub async fn list_patterns(Query(query): Query<PatternsQuery>, State(data_service): State<Arc<DataService>>) -> Response {
+    let interval = match query.interval.as_deref().map(KLineInterval::from_str) {
+        Some(None) => return (StatusCode::BAD_REQUEST, "Invalid interval").into_response(),
+        Some(interval) => interval,
+        None => None,
+    };
+    let limit = query.limit.unwrap_or(100).min(1000);
+    Json(data_service.recent_patterns(query.symbol.as_deref(), interval, query.pattern, limit)).into_response()
+}
+
+pub async fn list_symbols(State(data_service): State<Arc<DataService>>) -> Response {
+    Json(data_service.all_symbol_metadata()).into_response()
+}
+
+pub async fn get_symbol(Path(symbol): Path<String>, State(data_service): State<Arc<DataService>>) -> Response {
+    match data_service.symbol_metadata(&symbol) {
+        Some(metadata) => Json(metadata).into_response(),
+        None => (StatusCode::NOT_FOUND, "Unknown symbol").into_response(),
+    }
+}
+
+pub async fn get_ticker(Path(symbol): Path<String>, State(data_service): State<Arc<DataService>>) -> Response {
+    match data_service.ticker(&symbol) {
+        Some(ticker) => Json(ticker).into_response(),
+        None => (StatusCode::NOT_FOUND, "Unknown index symbol").into_response(),
+    }
+}
 
-    Json(klines).into_response()
+pub async fn list_venues(State(data_service): State<Arc<DataService>>) -> Response {
+    Json(data_service.venues()).into_response()
 }
 
-pub async fn health_check() -> Response {
+pub async fn health_check(State(data_service): State<Arc<DataService>>) -> Response {
     Json(serde_json::json!({
         "status": "healthy",
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": data_service.now().to_rfc3339()
     }))
     .into_response()
 } 
\ No newline at end of file
@@ -0,0 +1,51 @@
+use crate::services::DataService;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn not_configured() -> Response {
+    (StatusCode::NOT_FOUND, "Webhooks are not configured").into_response()
+}
+
+/// Delivery counters per webhook.
+pub async fn list_webhooks(State(data_service): State<Arc<DataService>>) -> Response {
+    match data_service.webhooks() {
+        Some(dispatcher) => Json(dispatcher.statuses()).into_response(),
+        None => not_configured(),
+    }
+}
+
+pub async fn list_dead_letters(State(data_service): State<Arc<DataService>>) -> Response {
+    match data_service.webhooks() {
+        Some(dispatcher) => Json(dispatcher.dead_letters()).into_response(),
+        None => not_configured(),
+    }
+}
+
+/// Queues a dead letter for delivery again with a fresh set of attempts.
+pub async fn retry_dead_letter(Path(id): Path<Uuid>, State(data_service): State<Arc<DataService>>) -> Response {
+    let Some(dispatcher) = data_service.webhooks() else {
+        return not_configured();
+    };
+    match dispatcher.retry_dead_letter(id).await {
+        Ok(true) => StatusCode::ACCEPTED.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Unknown dead letter").into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)).into_response(),
+    }
+}
+
+pub async fn delete_dead_letter(Path(id): Path<Uuid>, State(data_service): State<Arc<DataService>>) -> Response {
+    let Some(dispatcher) = data_service.webhooks() else {
+        return not_configured();
+    };
+    match dispatcher.delete_dead_letter(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Unknown dead letter").into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err)).into_response(),
+    }
+}
@@ -0,0 +1,338 @@
+use crate::models::SymbolMetadata;
+use crate::services::{
+    default_max_line_bytes, BarConfig, Clock, ConsolidatedConfig, FootprintConfig, GeneratorConfig,
+    HttpPushSource, MarketFactorConfig, MockDataConfig, MockSymbolConfig, PatternConfig,
+    ReplayConfig, SourceRegistry, StatsConfig, SymbolScenario, SyntheticConfig, TcpIngestConfig,
+    TcpNdjsonSource, TransactionSource, UpstreamWsConfig, UpstreamWsSource, WebhooksConfig,
+    WhaleConfig,
+};
+use anyhow::{bail, Context, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::{env, path::Path};
+
+/// Service configuration loaded at startup from the JSON file named by `CONFIG_FILE`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ServiceConfig {
+    /// Venue for trades and requests that don't name one.
+    pub default_venue: Option<String>,
+    pub mock: MockConfig,
+    /// Scripted mock symbols, each driven through a timeline of market regimes.
+    pub scenarios: Vec<SymbolScenario>,
+    /// Recorded trade files to replay through the pipeline.
+    pub replays: Vec<ReplayConfig>,
+    /// External trade feeds.
+    pub sources: Vec<SourceConfig>,
+    /// Listener for producers pushing newline-delimited trades over TCP.
+    pub tcp_ingest: Option<TcpIngestConfig>,
+    /// Symbols to consolidate across venues.
+    pub consolidated: Vec<ConsolidatedConfig>,
+    /// Symbols derived from the product or ratio of two others.
+    pub synthetic: Vec<SyntheticConfig>,
+    /// Supply and other metadata per symbol.
+    pub symbols: Vec<SymbolMetadata>,
+    /// Per-symbol thresholds for the whale trade stream.
+    pub whales: Vec<WhaleConfig>,
+    /// Tick, volume and dollar bar thresholds per symbol.
+    pub bars: Vec<BarConfig>,
+    /// Thresholds for candlestick pattern detection.
+    pub patterns: PatternConfig,
+    /// Windows kept up to date for the stats endpoint.
+    pub stats: StatsConfig,
+    /// Symbols to build footprint candles for.
+    pub footprint: Vec<FootprintConfig>,
+    /// HTTP subscribers for closed candles, transactions and alerts.
+    pub webhooks: Option<WebhooksConfig>,
+}
+
+impl ServiceConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let config: ServiceConfig = serde_json::from_str(&json).context("Failed to parse config file")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads `CONFIG_FILE` if it is set, otherwise the built-in defaults.
+    pub fn from_env() -> Result<Self> {
+        match env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for symbol in &self.mock.symbols {
+            if !seen.insert((symbol.venue.as_deref(), symbol.symbol.as_str())) {
+                bail!("Mock symbol {} is configured more than once", symbol.symbol);
+            }
+            if symbol.interval_ms == 0 {
+                bail!("Mock symbol {} needs a positive interval_ms", symbol.symbol);
+            }
+            if symbol.beta != 0.0 && !matches!(symbol.generator, GeneratorConfig::RandomWalk(_)) {
+                bail!("Mock symbol {} sets beta, which only random_walk generators support", symbol.symbol);
+            }
+            match &symbol.generator {
+                GeneratorConfig::RandomWalk(config) => config
+                    .validate()
+                    .with_context(|| format!("Invalid random_walk generator for {}", symbol.symbol))?,
+                GeneratorConfig::AmmPool(config) => config
+                    .validate()
+                    .with_context(|| format!("Invalid amm_pool generator for {}", symbol.symbol))?,
+                GeneratorConfig::BondingCurve(config) => config
+                    .validate()
+                    .with_context(|| format!("Invalid bonding_curve generator for {}", symbol.symbol))?,
+            }
+            if let Some(faults) = &symbol.faults {
+                faults
+                    .validate()
+                    .with_context(|| format!("Invalid faults for {}", symbol.symbol))?;
+            }
+        }
+
+        for replay in &self.replays {
+            replay.validate()?;
+        }
+        for scenario in &self.scenarios {
+            scenario.validate()?;
+        }
+
+        let mut names = HashSet::new();
+        for source in &self.sources {
+            if !names.insert(source.name()) {
+                bail!("Source {} is configured more than once", source.name());
+            }
+            if let SourceConfig::TcpNdjson { max_line_bytes: 0, .. } = source {
+                bail!("Source {} needs a positive max_line_bytes", source.name());
+            }
+        }
+        if let Some(tcp_ingest) = &self.tcp_ingest {
+            if names.contains(tcp_ingest.name.as_str()) {
+                bail!("Source {} is configured more than once", tcp_ingest.name);
+            }
+            if tcp_ingest.max_line_bytes == 0 {
+                bail!("tcp_ingest needs a positive max_line_bytes");
+            }
+        }
+
+        let mut consolidated = HashSet::new();
+        for config in &self.consolidated {
+            if !consolidated.insert(config.symbol.as_str()) {
+                bail!("Symbol {} is consolidated more than once", config.symbol);
+            }
+            if config.max_deviation < 0.0 {
+                bail!("Consolidated symbol {} needs a non-negative max_deviation", config.symbol);
+            }
+        }
+        SyntheticConfig::validate_all(&self.synthetic)?;
+        WhaleConfig::validate_all(&self.whales)?;
+        FootprintConfig::validate_all(&self.footprint)?;
+        BarConfig::validate_all(&self.bars)?;
+        self.patterns.validate()?;
+        self.stats.validate()?;
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.validate()?;
+        }
+
+        let mut described = HashSet::new();
+        for metadata in &self.symbols {
+            if !described.insert(metadata.symbol.as_str()) {
+                bail!("Metadata for {} is configured more than once", metadata.symbol);
+            }
+            if metadata.circulating_supply <= Decimal::ZERO {
+                bail!("Symbol {} needs a positive circulating_supply", metadata.symbol);
+            }
+            if metadata.total_supply() < metadata.circulating_supply {
+                bail!("Symbol {} has a total_supply below its circulating_supply", metadata.symbol);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceConfig {
+    /// Accepts trades on `POST /api/v1/ingest/{name}`.
+    HttpPush {
+        name: String,
+        #[serde(default = "default_push_capacity")]
+        capacity: usize,
+    },
+    /// Dials a TCP producer writing newline-delimited transaction JSON.
+    TcpNdjson {
+        name: String,
+        addr: String,
+        /// Longer lines drop the connection, which is then redialed.
+        #[serde(default = "default_max_line_bytes")]
+        max_line_bytes: usize,
+    },
+    /// Dials an upstream WebSocket trade feed and normalizes its messages.
+    UpstreamWs {
+        name: String,
+        #[serde(flatten)]
+        config: Box<UpstreamWsConfig>,
+    },
+}
+
+fn default_push_capacity() -> usize {
+    10_000
+}
+
+impl SourceConfig {
+    pub fn name(&self) -> &str {
+        match self {
+            SourceConfig::HttpPush { name, .. }
+            | SourceConfig::TcpNdjson { name, .. }
+            | SourceConfig::UpstreamWs { name, .. } => name,
+        }
+    }
+
+    pub fn build(&self, registry: &SourceRegistry, clock: Arc<dyn Clock>) -> Arc<dyn TransactionSource> {
+        match self {
+            SourceConfig::HttpPush { name, capacity } => Arc::new(HttpPushSource::register(name, *capacity, registry)),
+            SourceConfig::TcpNdjson {
+                name,
+                addr,
+                max_line_bytes,
+            } => Arc::new(TcpNdjsonSource::new(name, addr).with_max_line_bytes(*max_line_bytes)),
+            SourceConfig::UpstreamWs { name, config } => {
+                Arc::new(UpstreamWsSource::new(name, (**config).clone()).with_clock(clock))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MockConfig {
+    /// Base seed for all generators; each symbol is offset by its position in the list.
+    pub seed: Option<u64>,
+    /// Shared factor that correlates random-walk symbols with a non-zero `beta`.
+    pub market_factor: Option<MarketFactorConfig>,
+    pub symbols: Vec<MockSymbolConfig>,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            market_factor: None,
+            symbols: vec![MockSymbolConfig {
+                symbol: "DOGE".to_string(),
+                venue: None,
+                interval_ms: 100,
+                beta: 0.0,
+                generator: GeneratorConfig::RandomWalk(MockDataConfig::default()),
+                faults: None,
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{AmmPoolConfig, BondingCurveConfig, FaultConfig};
+
+    #[test]
+    fn test_parse_mock_symbols() {
+        let config: ServiceConfig = serde_json::from_str(
+            r#"{
+                "mock": {
+                    "seed": 7,
+                    "market_factor": { "volatility": 0.004 },
+                    "symbols": [
+                        { "symbol": "DOGE", "beta": 1.2, "generator": { "type": "random_walk", "base_price": "0.15" } },
+                        { "symbol": "PEPE", "interval_ms": 250, "generator": { "type": "amm_pool", "fee_rate": "0.01" },
+                          "faults": { "duplicate_probability": 0.01, "gap_probability": 0.001 } },
+                        { "symbol": "MOON", "generator": { "type": "bonding_curve", "shape": { "type": "linear", "base_price": 0.0001, "slope": 0.000001 } } }
+                    ]
+                },
+                "scenarios": [
+                    { "symbol": "RUG", "base_price": "1.0", "phases": [{ "name": "rug", "duration_secs": 5, "jump_probability": 0.1, "jump_size": -0.9 }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        assert_eq!(config.mock.symbols.len(), 3);
+        assert_eq!(config.mock.symbols[0].interval_ms, 100);
+        assert!(matches!(config.mock.symbols[2].generator, GeneratorConfig::BondingCurve(_)));
+        assert_eq!(config.mock.symbols[1].faults.as_ref().unwrap().duplicate_probability, 0.01);
+        assert_eq!(config.scenarios[0].phases[0].name, "rug");
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        let config: ServiceConfig = serde_json::from_str(
+            r#"{
+                "sources": [
+                    { "type": "http_push", "name": "partner" },
+                    { "type": "tcp_ndjson", "name": "firehose", "addr": "127.0.0.1:9000" },
+                    { "type": "upstream_ws", "name": "mirror", "url": "ws://127.0.0.1:8081/ws/transactions/DOGE" },
+                    { "type": "upstream_ws", "name": "binance", "url": "wss://stream.example.com/ws",
+                      "subscribe": [{ "method": "SUBSCRIBE", "params": ["dogeusdt@trade"], "id": 1 }],
+                      "mapping": { "symbol": "/s", "price": "/p", "volume": "/q", "timestamp": "/T", "side": "/m", "true_side": "sell" } }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_ok());
+        assert!(matches!(config.sources[0], SourceConfig::HttpPush { capacity: 10_000, .. }));
+        assert_eq!(config.sources[2].name(), "mirror");
+        match &config.sources[3] {
+            SourceConfig::UpstreamWs { config, .. } => {
+                assert_eq!(config.subscribe.len(), 1);
+                assert_eq!(config.mapping.as_ref().unwrap().price, "/p");
+            }
+            other => panic!("unexpected source {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_generators() {
+        let mut config = ServiceConfig::default();
+        config.mock.symbols[0].generator = GeneratorConfig::RandomWalk(MockDataConfig {
+            volatility: -0.01,
+            ..MockDataConfig::default()
+        });
+        assert!(config.validate().is_err());
+
+        let mut config = ServiceConfig::default();
+        config.mock.symbols[0].generator = GeneratorConfig::AmmPool(AmmPoolConfig {
+            buy_probability: 1.5,
+            ..AmmPoolConfig::default()
+        });
+        assert!(config.validate().is_err());
+
+        let mut config = ServiceConfig::default();
+        config.mock.symbols[0].generator = GeneratorConfig::BondingCurve(BondingCurveConfig {
+            buy_probability: -0.1,
+            ..BondingCurveConfig::default()
+        });
+        assert!(config.validate().is_err());
+
+        let mut config = ServiceConfig::default();
+        config.mock.symbols[0].faults = Some(FaultConfig {
+            duplicate_probability: 2.0,
+            ..FaultConfig::default()
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_rejects_duplicate_symbols() {
+        let mut config = ServiceConfig::default();
+        config.mock.symbols.push(config.mock.symbols[0].clone());
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,3 +1,5 @@
 pub mod api;
+pub mod config;
 pub mod models;
-pub mod services;
\ No newline at end of file
+pub mod services;
+pub mod websocket;
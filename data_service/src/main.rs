@@ -1,18 +1,16 @@
 use anyhow::{Context, Result};
-use axum::routing::{get, Router};
-use futures::pin_mut;
+use axum::routing::{delete, get, post, Router};
 use std::{env, net::SocketAddr, sync::Arc, time::Duration};
-use tokio_stream::StreamExt;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod api;
-mod models;
-mod services;
-mod websocket;
-
-use services::{DataService, MockDataGenerator};
+use data_service::config::ServiceConfig;
+use data_service::services::{
+    DataService, MarketFactor, MockSource, ReplaySource, Scenario, ScenarioGenerator, Supervisor,
+    TcpIngestSource, WebhookDispatcher,
+};
+use data_service::{api, websocket};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -31,29 +29,86 @@ async fn main() -> Result<()> {
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
 
+    let config = ServiceConfig::from_env()?;
+
+    // Optional seed so mock runs are reproducible; each generator gets its own offset.
+    let seed = match env::var("MOCK_SEED") {
+        Ok(seed) => Some(seed.parse::<u64>().context("Failed to parse MOCK_SEED environment variable")?),
+        Err(_) => config.mock.seed,
+    };
+
     // Initialize services
-    let data_service = Arc::new(DataService::new());
-    let mock_generator = MockDataGenerator::default();
-
-    // Start mock data generation
-    let kline_service_clone = data_service.clone();
-    tokio::spawn(async move {
-        let stream = mock_generator.generate_transaction_stream("DOGE".to_string(), 100);
-        pin_mut!(stream);
-
-        while let Some(result) = stream.next().await {
-            match result {
-                Ok(transaction) => {
-                    if let Err(err) = kline_service_clone.process_transaction(&transaction) {
-                        tracing::error!("Failed to process transaction: {:#}", err);
-                    }
-                }
-                Err(err) => {
-                    tracing::error!("Failed to generate transaction: {:#}", err);
-                }
-            }
+    let mut data_service = DataService::new()
+        .with_consolidated(config.consolidated.clone())
+        .with_synthetics(config.synthetic.clone())
+        .with_symbol_metadata(config.symbols.clone())
+        .with_whales(config.whales.clone())
+        .with_footprints(config.footprint.clone())
+        .with_bars(config.bars.clone())
+        .with_patterns(config.patterns.clone())
+        .with_stats(config.stats.clone());
+    if let Some(venue) = &config.default_venue {
+        data_service = data_service.with_default_venue(venue.clone());
+    }
+    let data_service = Arc::new(data_service);
+    let supervisor = Supervisor::new(data_service.clone());
+    let market_factor = config
+        .mock
+        .market_factor
+        .clone()
+        .map(|factor_config| MarketFactor::new(factor_config, seed));
+
+    // Start webhook delivery, if configured
+    if let Some(webhooks_config) = config.webhooks.clone() {
+        tracing::info!("Delivering to {} webhooks", webhooks_config.subscriptions.len());
+        let dispatcher = WebhookDispatcher::start(webhooks_config, &data_service).await?;
+        data_service.set_webhooks(dispatcher);
+    }
+
+    // Start one supervised generator per configured mock symbol
+    for (index, symbol_config) in config.mock.symbols.into_iter().enumerate() {
+        tracing::info!("Starting mock generator for {}", symbol_config.symbol);
+        let symbol_seed = seed.map(|seed| seed.wrapping_add(index as u64));
+        let source = MockSource::new(symbol_config, symbol_seed, market_factor.clone()).register(data_service.sources());
+        supervisor.spawn(Arc::new(source));
+    }
+
+    // Start file replays, if any
+    for replay_config in config.replays {
+        let path = replay_config.path.display().to_string();
+        let replay = ReplaySource::open(replay_config)?.register(data_service.sources());
+        tracing::info!("Replaying {} records from {}", replay.len(), path);
+        supervisor.spawn(Arc::new(replay));
+    }
+
+    // Start external feeds, if any
+    for source_config in &config.sources {
+        tracing::info!("Starting source {}", source_config.name());
+        supervisor.spawn(source_config.build(data_service.sources(), data_service.clock()));
+    }
+
+    // Start the TCP ingest listener, if configured
+    if let Some(tcp_config) = config.tcp_ingest {
+        let source = TcpIngestSource::bind(tcp_config, data_service.clone()).await?;
+        tracing::info!("Accepting TCP ingest connections at {}", source.local_addr());
+        supervisor.spawn(Arc::new(source));
+    }
+
+    // Start scripted scenarios from the config and SCENARIO_FILE, if any
+    let mut scenarios = config.scenarios;
+    if let Ok(path) = env::var("SCENARIO_FILE") {
+        tracing::info!("Loading scenarios from {}", path);
+        scenarios.extend(Scenario::from_file(&path)?.symbols);
+    }
+    for (index, symbol_scenario) in scenarios.into_iter().enumerate() {
+        tracing::info!("Running scenario for {}", symbol_scenario.symbol);
+        let scenario_seed = seed.map(|seed| seed.wrapping_add(1000 + index as u64));
+        let mut scenario_generator = ScenarioGenerator::new(symbol_scenario);
+        if let Some(seed) = scenario_seed {
+            scenario_generator = scenario_generator.with_seed(seed);
         }
-    });
+        supervisor.spawn(Arc::new(scenario_generator));
+    }
 
     // Configure CORS
     let cors = CorsLayer::new()
@@ -66,8 +121,44 @@ async fn main() -> Result<()> {
     let router = Router::new()
         .route("/health", get(api::health_check))
         .route("/api/v1/klines/{symbol}", get(api::get_klines))
+        .route("/api/v1/volume-profile/{symbol}", get(api::get_volume_profile))
+        .route("/api/v1/venues", get(api::list_venues))
+        .route("/api/v1/symbols", get(api::list_symbols))
+        .route("/api/v1/symbols/{symbol}", get(api::get_symbol))
+        .route("/api/v1/symbols/{symbol}/holders", get(api::get_top_holders))
+        .route("/api/v1/wallets/{wallet}/positions", get(api::get_wallet_positions))
+        .route("/api/v1/bars/{symbol}", get(api::get_bars))
+        .route("/api/v1/footprint/{symbol}", get(api::get_footprints))
+        .route("/api/v1/stats/{symbol}", get(api::get_stats))
+        .route("/api/v1/ticker/{symbol}", get(api::get_ticker))
+        .route("/api/v1/whales", get(api::list_whales))
+        .route("/api/v1/patterns", get(api::list_patterns))
+        .route("/api/v1/alerts", get(api::list_alert_rules).post(api::create_alert_rule))
+        .route("/api/v1/alerts/{id}", delete(api::delete_alert_rule))
+        .route("/api/v1/admin/webhooks", get(api::list_webhooks))
+        .route("/api/v1/admin/webhooks/dead-letters", get(api::list_dead_letters))
+        .route("/api/v1/admin/webhooks/dead-letters/{id}", delete(api::delete_dead_letter))
+        .route("/api/v1/admin/webhooks/dead-letters/{id}/retry", post(api::retry_dead_letter))
+        .route("/api/v1/admin/replays", get(api::list_replays))
+        .route("/api/v1/admin/replays/{name}/pause", post(api::pause_replay))
+        .route("/api/v1/admin/replays/{name}/resume", post(api::resume_replay))
+        .route("/api/v1/admin/replays/{name}/seek", post(api::seek_replay))
+        .route("/api/v1/sources", get(api::list_sources))
+        .route("/api/v1/ingest/{source}", post(api::ingest_transactions))
         .route("/ws/klines/{symbol}/{interval}", get(websocket::ws_kline_handler))
+        .route("/ws/klines/{venue}/{symbol}/{interval}", get(websocket::ws_venue_kline_handler))
         .route("/ws/transactions/{symbol}", get(websocket::ws_transaction_handler))
+        .route("/ws/transactions/{venue}/{symbol}", get(websocket::ws_venue_transaction_handler))
+        .route("/ws/ticker/{symbol}", get(websocket::ws_ticker_handler))
+        .route("/ws/alerts", get(websocket::ws_alert_handler))
+        .route("/ws/whales", get(websocket::ws_whale_handler))
+        .route("/ws/patterns", get(websocket::ws_pattern_handler))
+        .route("/ws/metrics/{symbol}/{interval}", get(websocket::ws_metrics_handler))
+        .route("/ws/metrics/{venue}/{symbol}/{interval}", get(websocket::ws_venue_metrics_handler))
+        .route("/ws/bars/{symbol}/{bar_type}/{threshold}", get(websocket::ws_bar_handler))
+        .route("/ws/bars/{venue}/{symbol}/{bar_type}/{threshold}", get(websocket::ws_venue_bar_handler))
+        .route("/ws/footprint/{symbol}/{interval}", get(websocket::ws_footprint_handler))
+        .route("/ws/footprint/{venue}/{symbol}/{interval}", get(websocket::ws_venue_footprint_handler))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(data_service);
@@ -0,0 +1,138 @@
+use super::KLineInterval;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Candles a candle-based rule can look back over; the service keeps no more history.
+const MAX_LOOKBACK: usize = 1000;
+/// Longest window a percent change rule can measure over, one day.
+const MAX_WINDOW_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossDirection {
+    Up,
+    Down,
+    #[default]
+    Any,
+}
+
+/// What an alert rule watches for. Rules fire on the transition into the condition,
+/// not on every trade while it holds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The trade price crosses `level`.
+    PriceCross {
+        level: Decimal,
+        #[serde(default)]
+        direction: CrossDirection,
+    },
+    /// The price moves by at least `percent`, up or down, within `window_secs`.
+    PercentChange { percent: Decimal, window_secs: u64 },
+    /// A closed candle's volume is at least `multiplier` times the average of the
+    /// `lookback` candles before it.
+    VolumeSpike {
+        interval: KLineInterval,
+        multiplier: Decimal,
+        #[serde(default = "default_lookback")]
+        lookback: usize,
+    },
+    /// The open candle trades above the highs of the `lookback` candles before it.
+    NewHigh {
+        interval: KLineInterval,
+        #[serde(default = "default_lookback")]
+        lookback: usize,
+    },
+    /// The open candle trades below the lows of the `lookback` candles before it.
+    NewLow {
+        interval: KLineInterval,
+        #[serde(default = "default_lookback")]
+        lookback: usize,
+    },
+}
+
+fn default_lookback() -> usize {
+    20
+}
+
+impl AlertCondition {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            AlertCondition::PriceCross { level, .. } if *level <= Decimal::ZERO => {
+                bail!("Price cross level must be positive")
+            }
+            AlertCondition::PercentChange { percent, .. } if *percent <= Decimal::ZERO => {
+                bail!("Percent change needs a positive percent")
+            }
+            AlertCondition::PercentChange { window_secs, .. } if !(1..=MAX_WINDOW_SECS).contains(window_secs) => {
+                bail!("Percent change window_secs must be between 1 and {}", MAX_WINDOW_SECS)
+            }
+            AlertCondition::VolumeSpike { multiplier, .. } if *multiplier <= Decimal::ZERO => {
+                bail!("Volume spike multiplier must be positive")
+            }
+            _ => {}
+        }
+        if let Some(lookback) = self.lookback() {
+            if lookback == 0 || lookback > MAX_LOOKBACK {
+                bail!("lookback must be between 1 and {}", MAX_LOOKBACK);
+            }
+        }
+        Ok(())
+    }
+
+    /// The candle interval a candle-based rule is evaluated on.
+    pub fn interval(&self) -> Option<KLineInterval> {
+        match self {
+            AlertCondition::VolumeSpike { interval, .. }
+            | AlertCondition::NewHigh { interval, .. }
+            | AlertCondition::NewLow { interval, .. } => Some(*interval),
+            AlertCondition::PriceCross { .. } | AlertCondition::PercentChange { .. } => None,
+        }
+    }
+
+    fn lookback(&self) -> Option<usize> {
+        match self {
+            AlertCondition::VolumeSpike { lookback, .. }
+            | AlertCondition::NewHigh { lookback, .. }
+            | AlertCondition::NewLow { lookback, .. } => Some(*lookback),
+            AlertCondition::PriceCross { .. } | AlertCondition::PercentChange { .. } => None,
+        }
+    }
+}
+
+/// Body of a rule registration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAlertRule {
+    pub symbol: String,
+    /// The default venue when omitted.
+    #[serde(default)]
+    pub venue: Option<String>,
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertRule {
+    pub id: Uuid,
+    pub symbol: String,
+    pub venue: String,
+    #[serde(flatten)]
+    pub condition: AlertCondition,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A rule firing.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub rule_id: Uuid,
+    pub symbol: String,
+    pub venue: String,
+    pub condition: AlertCondition,
+    /// The price that fired the rule.
+    pub price: Decimal,
+    pub message: String,
+    pub triggered_at: DateTime<Utc>,
+}
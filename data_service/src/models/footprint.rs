@@ -0,0 +1,26 @@
+use super::KLine;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Volume traded at one price level of a footprint candle.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FootprintLevel {
+    /// Lower bound of the level, a multiple of the tick size.
+    pub price: Decimal,
+    /// Volume of sells hitting the bid.
+    pub bid_volume: Decimal,
+    /// Volume of buys lifting the ask.
+    pub ask_volume: Decimal,
+    /// `ask_volume - bid_volume`.
+    pub delta: Decimal,
+}
+
+/// A candle broken down into bid and ask volume per price level.
+#[derive(Debug, Clone, Serialize)]
+pub struct FootprintCandle {
+    #[serde(flatten)]
+    pub kline: KLine,
+    pub tick_size: Decimal,
+    /// Lowest price first.
+    pub levels: Vec<FootprintLevel>,
+}
@@ -1,21 +1,166 @@
+use super::{SymbolMetadata, TradeSide};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KLine {
+    pub venue: String,
     pub symbol: String,
     pub interval: KLineInterval,
     pub open_time: DateTime<Utc>,
     pub close_time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub ohlcv: Ohlcv,
+    pub is_closed: bool,
+}
+
+/// Prices and volumes of a candle or bar, built up trade by trade.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ohlcv {
     pub open: Decimal,
     pub high: Decimal,
     pub low: Decimal,
     pub close: Decimal,
     pub volume: Decimal,
+    /// Traded value, `price * volume` summed over the candle's trades.
+    #[serde(default)]
+    pub quote_volume: Decimal,
+    /// Volume-weighted average price; the open price until volume trades.
+    #[serde(default)]
+    pub vwap: Decimal,
+    /// Volume of trades initiated by buyers.
+    #[serde(default)]
+    pub buy_volume: Decimal,
+    #[serde(default)]
+    pub sell_volume: Decimal,
+    /// `buy_volume - sell_volume`.
+    #[serde(default)]
+    pub delta: Decimal,
+    /// Running sum of `delta` over the series, up to and including this candle.
+    #[serde(default)]
+    pub cumulative_delta: Decimal,
+}
+
+/// What candle prices are denominated in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceUnit {
+    #[default]
+    Price,
+    /// Market cap: price times circulating supply.
+    Mcap,
+}
+
+/// A bar that closes on activity rather than the clock, or a Renko brick. It shares the
+/// K-line's [`Ohlcv`], with `bar_type` and `threshold` in place of `interval`;
+/// `close_time` follows the latest trade until the bar closes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bar {
+    pub venue: String,
+    pub symbol: String,
+    pub bar_type: BarType,
+    /// Trades, volume or quote value that closes the bar; the brick size for Renko.
+    pub threshold: Decimal,
+    pub open_time: DateTime<Utc>,
+    pub close_time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub ohlcv: Ohlcv,
     pub is_closed: bool,
 }
 
+/// Candles and bars, as far as price conversions are concerned.
+pub trait Ohlc: Clone {
+    fn ohlcv(&self) -> &Ohlcv;
+    fn ohlcv_mut(&mut self) -> &mut Ohlcv;
+
+    /// Open, high, low and close.
+    fn ohlc(&self) -> (Decimal, Decimal, Decimal, Decimal) {
+        let ohlcv = self.ohlcv();
+        (ohlcv.open, ohlcv.high, ohlcv.low, ohlcv.close)
+    }
+
+    /// A copy with `f` applied to the open, high, low, close and VWAP.
+    fn map_prices(&self, f: impl Fn(Decimal) -> Decimal) -> Self {
+        let mut mapped = self.clone();
+        let ohlcv = mapped.ohlcv_mut();
+        for price in [&mut ohlcv.open, &mut ohlcv.high, &mut ohlcv.low, &mut ohlcv.close, &mut ohlcv.vwap] {
+            *price = f(*price);
+        }
+        mapped
+    }
+}
+
+/// A candle denominated in market cap, with fully diluted valuation alongside.
+///
+/// `open` through `close` and `vwap` are multiplied by the circulating supply; the
+/// `fdv_*` fields by the total supply. Volumes stay in token and quote units.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketCapKLine<T = KLine> {
+    #[serde(flatten)]
+    pub kline: T,
+    pub fdv_open: Decimal,
+    pub fdv_high: Decimal,
+    pub fdv_low: Decimal,
+    pub fdv_close: Decimal,
+    pub circulating_supply: Decimal,
+    pub total_supply: Decimal,
+}
+
+impl<T: Ohlc> MarketCapKLine<T> {
+    pub fn new(kline: &T, metadata: &SymbolMetadata) -> Self {
+        let fdv = |price| metadata.fully_diluted_valuation(price);
+        let (open, high, low, close) = kline.ohlc();
+        Self {
+            fdv_open: fdv(open),
+            fdv_high: fdv(high),
+            fdv_low: fdv(low),
+            fdv_close: fdv(close),
+            circulating_supply: metadata.circulating_supply,
+            total_supply: metadata.total_supply(),
+            kline: kline.map_prices(|price| metadata.market_cap(price)),
+        }
+    }
+}
+
+/// What closes a bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarType {
+    /// Closes after `threshold` trades.
+    Tick,
+    /// Closes once `threshold` units have traded.
+    Volume,
+    /// Closes once `threshold` of quote value has traded.
+    Dollar,
+    /// A Renko brick `threshold` high.
+    Renko,
+}
+
+/// How candles are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartType {
+    #[default]
+    Candles,
+    HeikinAshi,
+    /// Bricks of a fixed price size, built from trades rather than candles.
+    Renko,
+}
+
+impl BarType {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "tick" => Some(Self::Tick),
+            "volume" => Some(Self::Volume),
+            "dollar" => Some(Self::Dollar),
+            "renko" => Some(Self::Renko),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum KLineInterval {
     #[serde(rename = "1s")]
@@ -41,6 +186,7 @@ impl KLineInterval {
         }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "1s" => Some(Self::OneSecond),
@@ -53,31 +199,184 @@ impl KLineInterval {
     }
 }
 
-impl KLine {
-    pub fn new(symbol: String, interval: KLineInterval, open_time: DateTime<Utc>, price: Decimal) -> Self {
-        let close_time = open_time + chrono::Duration::seconds(interval.as_seconds());
+impl Ohlcv {
+    /// A candle opening at `price`, before any volume has traded.
+    pub fn new(price: Decimal) -> Self {
         Self {
-            symbol,
-            interval,
-            open_time,
-            close_time,
             open: price,
             high: price,
             low: price,
             close: price,
             volume: Decimal::ZERO,
-            is_closed: false,
+            quote_volume: Decimal::ZERO,
+            vwap: price,
+            buy_volume: Decimal::ZERO,
+            sell_volume: Decimal::ZERO,
+            delta: Decimal::ZERO,
+            cumulative_delta: Decimal::ZERO,
         }
     }
 
-    pub fn update(&mut self, price: Decimal, volume: Decimal) {
+    pub fn update(&mut self, price: Decimal, volume: Decimal, side: TradeSide) {
         self.high = self.high.max(price);
         self.low = self.low.min(price);
         self.close = price;
         self.volume += volume;
+        self.quote_volume += price * volume;
+        if !self.volume.is_zero() {
+            self.vwap = self.quote_volume / self.volume;
+        }
+
+        let signed = match side {
+            TradeSide::Buy => {
+                self.buy_volume += volume;
+                volume
+            }
+            TradeSide::Sell => {
+                self.sell_volume += volume;
+                -volume
+            }
+        };
+        self.delta += signed;
+        self.cumulative_delta += signed;
+    }
+}
+
+impl KLine {
+    pub fn new(
+        venue: String,
+        symbol: String,
+        interval: KLineInterval,
+        open_time: DateTime<Utc>,
+        price: Decimal,
+    ) -> Self {
+        let close_time = open_time + chrono::Duration::seconds(interval.as_seconds());
+        Self {
+            venue,
+            symbol,
+            interval,
+            open_time,
+            close_time,
+            ohlcv: Ohlcv::new(price),
+            is_closed: false,
+        }
+    }
+
+    pub fn update(&mut self, price: Decimal, volume: Decimal, side: TradeSide) {
+        self.ohlcv.update(price, volume, side);
     }
 
     pub fn close(&mut self) {
         self.is_closed = true;
     }
-} 
\ No newline at end of file
+}
+
+impl Ohlc for KLine {
+    fn ohlcv(&self) -> &Ohlcv {
+        &self.ohlcv
+    }
+
+    fn ohlcv_mut(&mut self) -> &mut Ohlcv {
+        &mut self.ohlcv
+    }
+}
+
+impl Bar {
+    /// Opens a bar at the trade that starts it.
+    pub fn new(
+        venue: String,
+        symbol: String,
+        bar_type: BarType,
+        threshold: Decimal,
+        open_time: DateTime<Utc>,
+        price: Decimal,
+    ) -> Self {
+        Self {
+            venue,
+            symbol,
+            bar_type,
+            threshold,
+            open_time,
+            close_time: open_time,
+            ohlcv: Ohlcv::new(price),
+            is_closed: false,
+        }
+    }
+
+    /// Adds a trade and moves `close_time` to it.
+    pub fn update(&mut self, price: Decimal, volume: Decimal, side: TradeSide, at: DateTime<Utc>) {
+        self.ohlcv.update(price, volume, side);
+        self.close_time = at;
+    }
+
+    pub fn close(&mut self) {
+        self.is_closed = true;
+    }
+}
+
+impl Ohlc for Bar {
+    fn ohlcv(&self) -> &Ohlcv {
+        &self.ohlcv
+    }
+
+    fn ohlcv_mut(&mut self) -> &mut Ohlcv {
+        &mut self.ohlcv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_market_cap_kline() {
+        let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut kline = KLine::new(
+            "default".to_string(),
+            "DOGE".to_string(),
+            KLineInterval::OneMinute,
+            open_time,
+            Decimal::new(2, 1),
+        );
+        kline.update(Decimal::new(3, 1), Decimal::TEN, TradeSide::Buy);
+        let metadata = SymbolMetadata {
+            symbol: "DOGE".to_string(),
+            name: None,
+            circulating_supply: Decimal::new(1_000, 0),
+            total_supply: Some(Decimal::new(4_000, 0)),
+        };
+
+        let mcap = MarketCapKLine::new(&kline, &metadata);
+        assert_eq!(mcap.kline.ohlcv.open, Decimal::new(200, 0));
+        assert_eq!(mcap.kline.ohlcv.high, Decimal::new(300, 0));
+        assert_eq!(mcap.fdv_close, Decimal::new(1_200, 0));
+        assert_eq!(mcap.kline.ohlcv.volume, Decimal::TEN);
+
+        let json = serde_json::to_value(&mcap).unwrap();
+        assert_eq!(json["symbol"], "DOGE");
+        assert_eq!(json["close"], "300.0");
+        assert_eq!(json["fdv_low"], "800.0");
+    }
+
+    #[test]
+    fn test_bar_has_kline_shape() {
+        let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let price = Decimal::new(2, 1);
+        let mut kline = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, open_time, price);
+        let mut bar = Bar::new("default".to_string(), "DOGE".to_string(), BarType::Tick, Decimal::TWO, open_time, price);
+        for (price, side) in [(Decimal::new(3, 1), TradeSide::Buy), (Decimal::new(1, 1), TradeSide::Sell)] {
+            kline.update(price, Decimal::TEN, side);
+            bar.update(price, Decimal::TEN, side, open_time);
+        }
+
+        let kline = serde_json::to_value(&kline).unwrap();
+        let bar = serde_json::to_value(&bar).unwrap();
+        for field in ["open", "high", "low", "close", "volume", "quote_volume", "vwap", "delta", "cumulative_delta"] {
+            assert_eq!(bar[field], kline[field], "{}", field);
+        }
+        assert_eq!(bar["vwap"], "0.2");
+        assert_eq!(bar["bar_type"], "tick");
+        assert!(bar.get("ohlcv").is_none());
+    }
+}
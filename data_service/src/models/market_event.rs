@@ -0,0 +1,60 @@
+use super::Transaction;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum MarketEvent {
+    Trade(Transaction),
+    Liquidity(LiquidityEvent),
+    Graduated(GraduationEvent),
+}
+
+impl MarketEvent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketEvent::Trade(transaction) => &transaction.symbol,
+            MarketEvent::Liquidity(event) => &event.symbol,
+            MarketEvent::Graduated(event) => &event.symbol,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidityEvent {
+    pub id: Uuid,
+    pub symbol: String,
+    pub action: LiquidityAction,
+    pub token_amount: Decimal,
+    pub quote_amount: Decimal,
+    /// Pool reserves after the change was applied.
+    pub token_reserve: Decimal,
+    pub quote_reserve: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LiquidityAction {
+    #[serde(rename = "add")]
+    Add,
+    #[serde(rename = "remove")]
+    Remove,
+}
+
+/// Emitted once when a bonding-curve token reaches its graduation market cap and
+/// its liquidity migrates into a pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraduationEvent {
+    pub id: Uuid,
+    pub symbol: String,
+    pub price: Decimal,
+    pub market_cap: Decimal,
+    /// Tokens sold from the curve at graduation.
+    pub supply: Decimal,
+    /// Pool reserves seeded from the curve.
+    pub token_reserve: Decimal,
+    pub quote_reserve: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
@@ -1,5 +1,31 @@
+mod alert;
+mod footprint;
 mod kline;
+mod market_event;
+mod pattern;
+mod position;
+mod stats;
+mod symbol;
+mod ticker;
+mod trader_metrics;
 mod transaction;
+mod venue;
+mod volume_profile;
+mod whale;
 
-pub use kline::{KLine, KLineInterval};
-pub use transaction::{Transaction, TradeSide};
+pub use alert::{Alert, AlertCondition, AlertRule, CrossDirection, NewAlertRule};
+pub use footprint::{FootprintCandle, FootprintLevel};
+pub use kline::{
+    Bar, BarType, ChartType, KLine, KLineInterval, MarketCapKLine, Ohlc, Ohlcv, PriceUnit,
+};
+pub use market_event::{GraduationEvent, LiquidityAction, LiquidityEvent, MarketEvent};
+pub use pattern::{CandlePattern, PatternBias, PatternEvent};
+pub use position::Position;
+pub use stats::ReturnStats;
+pub use symbol::SymbolMetadata;
+pub use ticker::{ConstituentPrice, ConstituentStatus, IndexTicker};
+pub use trader_metrics::TraderMetrics;
+pub use transaction::{TradeSide, Transaction};
+pub use venue::{VenueInfo, DEFAULT_VENUE};
+pub use volume_profile::{ProfileSource, VolumeBucket, VolumeProfile};
+pub use whale::WhaleTrade;
@@ -0,0 +1,58 @@
+use super::KLineInterval;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandlePattern {
+    /// Opens and closes at nearly the same price.
+    Doji,
+    /// Small body at the top of a long lower shadow.
+    Hammer,
+    /// Small body at the bottom of a long upper shadow.
+    ShootingStar,
+    /// An up candle whose body covers the body of the down candle before it.
+    BullishEngulfing,
+    BearishEngulfing,
+    /// A long down candle, a small-bodied one, then an up candle closing past the middle
+    /// of the first.
+    MorningStar,
+    EveningStar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternBias {
+    Bullish,
+    Bearish,
+    /// Indecision; depends on the trend it appears in.
+    Neutral,
+}
+
+impl CandlePattern {
+    pub fn bias(&self) -> PatternBias {
+        match self {
+            CandlePattern::Doji => PatternBias::Neutral,
+            CandlePattern::Hammer | CandlePattern::BullishEngulfing | CandlePattern::MorningStar => PatternBias::Bullish,
+            CandlePattern::ShootingStar | CandlePattern::BearishEngulfing | CandlePattern::EveningStar => {
+                PatternBias::Bearish
+            }
+        }
+    }
+}
+
+/// A pattern completed by a closed candle.
+#[derive(Debug, Clone, Serialize)]
+pub struct PatternEvent {
+    pub venue: String,
+    pub symbol: String,
+    pub interval: KLineInterval,
+    pub pattern: CandlePattern,
+    pub bias: PatternBias,
+    /// Open time of the pattern's first candle.
+    pub open_time: DateTime<Utc>,
+    /// Close time of the candle that completed the pattern.
+    pub close_time: DateTime<Utc>,
+    pub close: Decimal,
+}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// A wallet's holding in one symbol, with profit and loss in the quote currency.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Position {
+    pub wallet: String,
+    pub symbol: String,
+    /// Net tokens held; negative when the wallet sold more than it was seen buying.
+    pub balance: Decimal,
+    /// Average price of the open balance; zero when flat.
+    pub average_entry_price: Decimal,
+    pub realized_pnl: Decimal,
+    /// `(last_price - average_entry_price) * balance`.
+    pub unrealized_pnl: Decimal,
+    /// Last traded price of the symbol.
+    pub last_price: Decimal,
+    pub trades: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_trade_at: DateTime<Utc>,
+}
@@ -0,0 +1,29 @@
+use super::KLineInterval;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Realized volatility and log-return statistics over the last `window` closed candles.
+///
+/// Volatilities are annualized over a 365-day year; return statistics are per candle.
+/// Each figure is `None` until there are enough candles for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReturnStats {
+    pub venue: String,
+    pub symbol: String,
+    pub interval: KLineInterval,
+    pub window: usize,
+    /// Candles the figures were computed from; below `window` early in a series.
+    pub candles: usize,
+    /// Open time of the first candle used.
+    pub from: Option<DateTime<Utc>>,
+    /// Close time of the last candle used.
+    pub to: Option<DateTime<Utc>>,
+    pub close_to_close: Option<f64>,
+    pub parkinson: Option<f64>,
+    pub garman_klass: Option<f64>,
+    pub mean_return: Option<f64>,
+    pub stdev_return: Option<f64>,
+    pub skew: Option<f64>,
+    /// Largest fall from a running peak close, as a share of the peak.
+    pub max_drawdown: Option<f64>,
+}
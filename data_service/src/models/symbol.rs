@@ -0,0 +1,28 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Static facts about a token, used for market-cap denominated data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolMetadata {
+    pub symbol: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub circulating_supply: Decimal,
+    /// Maximum supply, for fully diluted valuation. Defaults to the circulating supply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<Decimal>,
+}
+
+impl SymbolMetadata {
+    pub fn total_supply(&self) -> Decimal {
+        self.total_supply.unwrap_or(self.circulating_supply)
+    }
+
+    pub fn market_cap(&self, price: Decimal) -> Decimal {
+        price * self.circulating_supply
+    }
+
+    pub fn fully_diluted_valuation(&self, price: Decimal) -> Decimal {
+        price * self.total_supply()
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstituentStatus {
+    Included,
+    /// Too far from the median of the other venues.
+    Outlier,
+    /// No trade within the staleness window.
+    Stale,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConstituentPrice {
+    pub venue: String,
+    pub price: Decimal,
+    pub last_trade_at: DateTime<Utc>,
+    pub status: ConstituentStatus,
+}
+
+/// Cross-venue reference price for a symbol.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexTicker {
+    /// The index pseudo-symbol, e.g. `DOGE.IDX`.
+    pub symbol: String,
+    /// The symbol being indexed.
+    pub underlying: String,
+    /// `None` while every venue is stale.
+    pub price: Option<Decimal>,
+    pub constituents: Vec<ConstituentPrice>,
+    pub timestamp: DateTime<Utc>,
+}
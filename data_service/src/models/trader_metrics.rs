@@ -0,0 +1,22 @@
+use super::KLineInterval;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Wallet activity over one candle, a companion to the `KLine` with the same key and
+/// `open_time`. Only trades that carry maker or taker wallets contribute.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraderMetrics {
+    pub venue: String,
+    pub symbol: String,
+    pub interval: KLineInterval,
+    pub open_time: DateTime<Utc>,
+    pub close_time: DateTime<Utc>,
+    /// Distinct wallets that received tokens: takers of buys and makers of sells.
+    pub unique_buyers: u64,
+    pub unique_sellers: u64,
+    /// Wallets that traded the symbol for the first time on any venue.
+    pub new_wallets: u64,
+    /// Wallets holding the symbol, across venues, after the candle's last trade.
+    pub holders: u64,
+    pub is_closed: bool,
+}
@@ -1,16 +1,33 @@
+use anyhow::{bail, Result};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// How far ahead of the service clock an ingested trade may be stamped.
+const MAX_FUTURE_SKEW_SECS: i64 = 60;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
+    #[serde(default = "Uuid::new_v4")]
     pub id: Uuid,
     pub symbol: String,
     pub price: Decimal,
     pub volume: Decimal,
     pub timestamp: DateTime<Utc>,
     pub side: TradeSide,
+    /// Where the trade happened. Trades without one are booked to the service's default venue.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub venue: Option<String>,
+    /// Wallet whose resting order was filled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maker: Option<String>,
+    /// Wallet that initiated the trade; `side` is from its point of view.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taker: Option<String>,
+    /// Name of the ingestion source the trade arrived through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -23,17 +40,62 @@ pub enum TradeSide {
 
 impl Transaction {
     pub fn new(symbol: String, price: Decimal, volume: Decimal, side: TradeSide) -> Self {
+        Self::new_at(Uuid::new_v4(), symbol, price, volume, side, Utc::now())
+    }
+
+    /// Builds a transaction with an explicit id and timestamp, for sources that
+    /// simulate or replay time.
+    pub fn new_at(
+        id: Uuid,
+        symbol: String,
+        price: Decimal,
+        volume: Decimal,
+        side: TradeSide,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id,
             symbol,
             price,
             volume,
-            timestamp: Utc::now(),
+            timestamp,
             side,
+            venue: None,
+            maker: None,
+            taker: None,
+            source: None,
         }
     }
 
+    pub fn with_venue(mut self, venue: impl Into<String>) -> Self {
+        self.venue = Some(venue.into());
+        self
+    }
+
+    pub fn with_wallets(mut self, maker: Option<String>, taker: Option<String>) -> Self {
+        self.maker = maker;
+        self.taker = taker;
+        self
+    }
+
     pub fn total_value(&self) -> Decimal {
         self.price * self.volume
     }
-} 
\ No newline at end of file
+
+    /// Rejects trades no ingestion path should accept.
+    pub fn validate(&self, now: DateTime<Utc>) -> Result<()> {
+        if self.symbol.trim().is_empty() {
+            bail!("Transaction {} has an empty symbol", self.id);
+        }
+        if self.price <= Decimal::ZERO {
+            bail!("Transaction {} has a non-positive price", self.id);
+        }
+        if self.volume < Decimal::ZERO {
+            bail!("Transaction {} has a negative volume", self.id);
+        }
+        if self.timestamp > now + chrono::Duration::seconds(MAX_FUTURE_SKEW_SECS) {
+            bail!("Transaction {} is stamped too far in the future", self.id);
+        }
+        Ok(())
+    }
+}
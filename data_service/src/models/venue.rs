@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+/// Venue assumed for trades and requests that don't name one.
+pub const DEFAULT_VENUE: &str = "default";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VenueInfo {
+    pub venue: String,
+    pub symbols: Vec<String>,
+    /// Whether requests without a venue are served from this one.
+    pub is_default: bool,
+}
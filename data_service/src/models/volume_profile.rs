@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Where a volume profile's volume came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileSource {
+    Trades,
+    /// Candles, spreading each candle's buy and sell volume evenly over its high-low range.
+    Candles,
+    /// Candles for the part of the range older than the stored trades.
+    Mixed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VolumeBucket {
+    pub price_low: Decimal,
+    pub price_high: Decimal,
+    pub volume: Decimal,
+    /// Volume from buys.
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+}
+
+/// Traded volume by price over a time range.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VolumeProfile {
+    pub venue: String,
+    pub symbol: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub source: ProfileSource,
+    pub total_volume: Decimal,
+    /// Midpoint of the bucket with the most volume.
+    pub point_of_control: Option<Decimal>,
+    /// Bounds of the buckets around the point of control holding the value-area share
+    /// of volume.
+    pub value_area_low: Option<Decimal>,
+    pub value_area_high: Option<Decimal>,
+    /// Lowest price first.
+    pub buckets: Vec<VolumeBucket>,
+}
@@ -0,0 +1,14 @@
+use super::Transaction;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// A trade whose quote value crossed its symbol's whale threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct WhaleTrade {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    /// `price * volume`.
+    pub value: Decimal,
+    /// The threshold in force when the trade arrived.
+    pub threshold: Decimal,
+}
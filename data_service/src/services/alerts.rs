@@ -0,0 +1,356 @@
+use crate::models::{
+    Alert, AlertCondition, AlertRule, CrossDirection, KLine, KLineInterval, Transaction,
+};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+const MAX_ALERT_RULES: usize = 10_000;
+/// Rules allowed on one venue and symbol, whose trades evaluate all of them.
+const MAX_ALERT_RULES_PER_SYMBOL: usize = 100;
+/// Trades a percent change rule keeps; on a busier tape the window starts at the oldest
+/// one kept.
+const MAX_WINDOW_TRADES: usize = 10_000;
+
+/// A rule with what it remembers between evaluations.
+struct RuleState {
+    rule: AlertRule,
+    last_price: Option<Decimal>,
+    /// Trades within a percent-change window, oldest first.
+    window: VecDeque<(DateTime<Utc>, Decimal)>,
+    /// Whether a percent-change rule may fire; cleared when it fires and set again once
+    /// the move falls back under the threshold.
+    armed: bool,
+    /// Open time of the last candle that fired, so each candle fires at most once.
+    fired_candle: Option<DateTime<Utc>>,
+}
+
+impl RuleState {
+    fn alert(&self, price: Decimal, message: String, triggered_at: DateTime<Utc>) -> Alert {
+        Alert {
+            rule_id: self.rule.id,
+            symbol: self.rule.symbol.clone(),
+            venue: self.rule.venue.clone(),
+            condition: self.rule.condition.clone(),
+            price,
+            message,
+            triggered_at,
+        }
+    }
+
+    fn on_trade(&mut self, transaction: &Transaction) -> Option<Alert> {
+        let price = transaction.price;
+        let previous = self.last_price.replace(price);
+        let symbol = &self.rule.symbol;
+        match &self.rule.condition {
+            AlertCondition::PriceCross { level, direction } => {
+                let previous = previous?;
+                let up = previous < *level && price >= *level;
+                let down = previous > *level && price <= *level;
+                let crossed = match direction {
+                    CrossDirection::Up => up,
+                    CrossDirection::Down => down,
+                    CrossDirection::Any => up || down,
+                };
+                let message = format!("{} crossed {} {} at {}", symbol, if up { "above" } else { "below" }, level, price);
+                crossed.then(|| self.alert(price, message, transaction.timestamp))
+            }
+            AlertCondition::PercentChange { percent, window_secs } => {
+                let window_start = i64::try_from(*window_secs)
+                    .ok()
+                    .and_then(chrono::Duration::try_seconds)
+                    .and_then(|window| transaction.timestamp.checked_sub_signed(window));
+                self.window.push_back((transaction.timestamp, price));
+                while self.window.len() > MAX_WINDOW_TRADES
+                    || self.window.front().is_some_and(|(at, _)| window_start.is_some_and(|start| *at < start))
+                {
+                    self.window.pop_front();
+                }
+                let reference = self.window.front()?.1;
+                if reference.is_zero() {
+                    return None;
+                }
+                let change = (price - reference) / reference * Decimal::ONE_HUNDRED;
+                if change.abs() < *percent {
+                    self.armed = true;
+                    return None;
+                }
+                if !std::mem::replace(&mut self.armed, false) {
+                    return None;
+                }
+                let message = format!("{} moved {}% in {}s to {}", symbol, change.round_dp(2).normalize(), window_secs, price);
+                Some(self.alert(price, message, transaction.timestamp))
+            }
+            _ => None,
+        }
+    }
+
+    fn on_kline(&mut self, kline: &KLine, previous: &[KLine], at: DateTime<Utc>) -> Option<Alert> {
+        if self.fired_candle == Some(kline.open_time) {
+            return None;
+        }
+        let symbol = &self.rule.symbol;
+        let (fired, message) = match &self.rule.condition {
+            AlertCondition::VolumeSpike { multiplier, lookback, .. } if kline.is_closed => {
+                let previous = full_lookback(previous, *lookback)?;
+                let total: Decimal = previous.iter().map(|kline| kline.ohlcv.volume).sum();
+                let average = total / Decimal::from(previous.len());
+                let volume = kline.ohlcv.volume;
+                let fired = !average.is_zero() && volume >= average * multiplier;
+                let message = format!("{} traded {} in one candle, {}x the average", symbol, volume, multiplier);
+                (fired, message)
+            }
+            AlertCondition::NewHigh { lookback, .. } if !kline.is_closed => {
+                let previous = full_lookback(previous, *lookback)?;
+                let high = previous.iter().map(|kline| kline.ohlcv.high).max()?;
+                (kline.ohlcv.high > high, format!("{} made a {}-candle high at {}", symbol, lookback, kline.ohlcv.high))
+            }
+            AlertCondition::NewLow { lookback, .. } if !kline.is_closed => {
+                let previous = full_lookback(previous, *lookback)?;
+                let low = previous.iter().map(|kline| kline.ohlcv.low).min()?;
+                (kline.ohlcv.low < low, format!("{} made a {}-candle low at {}", symbol, lookback, kline.ohlcv.low))
+            }
+            _ => return None,
+        };
+        if !fired {
+            return None;
+        }
+        self.fired_candle = Some(kline.open_time);
+        Some(self.alert(kline.ohlcv.close, message, at))
+    }
+}
+
+/// The last `lookback` candles, once there are that many.
+fn full_lookback(previous: &[KLine], lookback: usize) -> Option<&[KLine]> {
+    previous.len().checked_sub(lookback).map(|start| &previous[start..])
+}
+
+/// Evaluates alert rules against trades and candle updates.
+#[derive(Default)]
+pub struct AlertEngine {
+    /// Rules per venue and symbol.
+    rules: DashMap<(String, String), Vec<RuleState>>,
+}
+
+impl AlertEngine {
+    pub fn add(&self, rule: AlertRule) -> Result<()> {
+        if self.len() >= MAX_ALERT_RULES {
+            bail!("Too many alert rules; delete some first");
+        }
+        let mut states = self.rules.entry((rule.venue.clone(), rule.symbol.clone())).or_default();
+        if states.len() >= MAX_ALERT_RULES_PER_SYMBOL {
+            bail!("Too many alert rules for {} on {}; delete some first", rule.symbol, rule.venue);
+        }
+        states.push(RuleState {
+            rule,
+            last_price: None,
+            window: VecDeque::new(),
+            armed: true,
+            fired_candle: None,
+        });
+        Ok(())
+    }
+
+    pub fn remove(&self, id: Uuid) -> bool {
+        let mut removed = false;
+        self.rules.retain(|_, states| {
+            let before = states.len();
+            states.retain(|state| state.rule.id != id);
+            removed |= states.len() != before;
+            !states.is_empty()
+        });
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.iter().map(|entry| entry.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Registered rules, oldest first, optionally for one symbol.
+    pub fn list(&self, symbol: Option<&str>) -> Vec<AlertRule> {
+        let mut rules: Vec<AlertRule> = self
+            .rules
+            .iter()
+            .filter(|entry| symbol.is_none_or(|symbol| entry.key().1 == symbol))
+            .flat_map(|entry| entry.value().iter().map(|state| state.rule.clone()).collect::<Vec<_>>())
+            .collect();
+        rules.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        rules
+    }
+
+    /// Evaluates the price rules of the trade's venue and symbol. Expects the venue to
+    /// be assigned.
+    pub fn on_trade(&self, transaction: &Transaction) -> Vec<Alert> {
+        let key = (transaction.venue.clone().unwrap_or_default(), transaction.symbol.clone());
+        match self.rules.get_mut(&key) {
+            Some(mut states) => states.iter_mut().filter_map(|state| state.on_trade(transaction)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether any candle rule watches this series, so callers can skip gathering history.
+    pub fn watches_candles(&self, venue: &str, symbol: &str, interval: KLineInterval) -> bool {
+        self.rules
+            .get(&(venue.to_string(), symbol.to_string()))
+            .is_some_and(|states| states.iter().any(|state| state.rule.condition.interval() == Some(interval)))
+    }
+
+    /// Evaluates candle rules on an update of `kline` at `at`, given the closed candles
+    /// before it.
+    pub fn on_kline(&self, kline: &KLine, previous: &[KLine], at: DateTime<Utc>) -> Vec<Alert> {
+        let key = (kline.venue.clone(), kline.symbol.clone());
+        match self.rules.get_mut(&key) {
+            Some(mut states) => states
+                .iter_mut()
+                .filter(|state| state.rule.condition.interval() == Some(kline.interval))
+                .filter_map(|state| state.on_kline(kline, previous, at))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use chrono::TimeZone;
+
+    fn rule(condition: AlertCondition) -> AlertRule {
+        AlertRule {
+            id: Uuid::new_v4(),
+            symbol: "DOGE".to_string(),
+            venue: "default".to_string(),
+            condition,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn trade(price: i64, secs: i64) -> Transaction {
+        let at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::seconds(secs);
+        Transaction::new_at(Uuid::new_v4(), "DOGE".to_string(), Decimal::new(price, 0), Decimal::ONE, TradeSide::Buy, at)
+            .with_venue("default")
+    }
+
+    #[test]
+    fn test_price_rules_fire_on_transitions() {
+        let engine = AlertEngine::default();
+        let cross = rule(AlertCondition::PriceCross {
+            level: Decimal::new(105, 0),
+            direction: CrossDirection::Up,
+        });
+        let cross_id = cross.id;
+        engine.add(cross).unwrap();
+        engine
+            .add(rule(AlertCondition::PercentChange {
+                percent: Decimal::new(10, 0),
+                window_secs: 60,
+            }))
+            .unwrap();
+
+        assert!(engine.on_trade(&trade(100, 0)).is_empty());
+        let alerts = engine.on_trade(&trade(106, 10));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].rule_id, cross_id);
+
+        let alerts = engine.on_trade(&trade(111, 20));
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].message.contains("moved 11%"));
+        // Still up 10%: no repeat until the move falls back under the threshold.
+        assert!(engine.on_trade(&trade(112, 30)).is_empty());
+        // The trade at 100 has left the window.
+        assert!(engine.on_trade(&trade(104, 70)).is_empty());
+        assert_eq!(engine.on_trade(&trade(125, 80)).len(), 2);
+
+        assert!(engine.remove(cross_id));
+        assert!(!engine.remove(cross_id));
+        assert_eq!(engine.len(), 1);
+    }
+
+    #[test]
+    fn test_candle_rules() {
+        let engine = AlertEngine::default();
+        engine
+            .add(rule(AlertCondition::NewHigh {
+                interval: KLineInterval::OneMinute,
+                lookback: 2,
+            }))
+            .unwrap();
+        engine
+            .add(rule(AlertCondition::VolumeSpike {
+                interval: KLineInterval::OneMinute,
+                multiplier: Decimal::new(3, 0),
+                lookback: 2,
+            }))
+            .unwrap();
+        assert!(engine.watches_candles("default", "DOGE", KLineInterval::OneMinute));
+        assert!(!engine.watches_candles("default", "DOGE", KLineInterval::OneHour));
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let candle = |minute: i64, price: i64, volume: i64| {
+            let mut kline = KLine::new(
+                "default".to_string(),
+                "DOGE".to_string(),
+                KLineInterval::OneMinute,
+                start + chrono::Duration::minutes(minute),
+                Decimal::new(price, 0),
+            );
+            kline.update(Decimal::new(price, 0), Decimal::new(volume, 0), TradeSide::Buy);
+            kline
+        };
+        let history = vec![candle(0, 100, 10), candle(1, 102, 10)];
+
+        // Not enough history yet.
+        assert!(engine.on_kline(&candle(1, 200, 10), &history[..1], start).is_empty());
+
+        let mut open = candle(2, 103, 5);
+        assert_eq!(engine.on_kline(&open, &history, start).len(), 1);
+        open.update(Decimal::new(104, 0), Decimal::new(25, 0), TradeSide::Sell);
+        assert!(engine.on_kline(&open, &history, start).is_empty());
+
+        open.close();
+        let alerts = engine.on_kline(&open, &history, start);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].message.contains("traded 30"));
+    }
+
+    #[test]
+    fn test_percent_change_window_is_bounded() {
+        let huge = AlertCondition::PercentChange {
+            percent: Decimal::ONE,
+            window_secs: u64::MAX,
+        };
+        assert!(huge.validate().is_err());
+
+        // Even an unchecked window neither panics nor grows without bound
+        let engine = AlertEngine::default();
+        engine.add(rule(huge)).unwrap();
+        for secs in 0..(MAX_WINDOW_TRADES as i64 + 10) {
+            engine.on_trade(&trade(100, secs));
+        }
+        let states = engine.rules.get(&("default".to_string(), "DOGE".to_string())).unwrap();
+        assert_eq!(states[0].window.len(), MAX_WINDOW_TRADES);
+    }
+
+    #[test]
+    fn test_rules_per_symbol_are_capped() {
+        let engine = AlertEngine::default();
+        let cross = || rule(AlertCondition::PriceCross { level: Decimal::ONE, direction: CrossDirection::Any });
+        for _ in 0..MAX_ALERT_RULES_PER_SYMBOL {
+            engine.add(cross()).unwrap();
+        }
+        assert!(engine.add(cross()).is_err());
+
+        let mut other = cross();
+        other.symbol = "PEPE".to_string();
+        assert!(engine.add(other).is_ok());
+        assert_eq!(engine.len(), MAX_ALERT_RULES_PER_SYMBOL + 1);
+    }
+}
@@ -0,0 +1,464 @@
+use super::clock::{Clock, SystemClock, Ticker};
+use super::mock_data::{new_rng, random_id, Checkpoint, GeneratorState};
+use crate::models::{LiquidityAction, LiquidityEvent, MarketEvent, TradeSide, Transaction};
+use anyhow::{bail, Context, Result};
+use async_stream::stream;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use rand::rngs::StdRng;
+use rand::Rng;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{pin::Pin, str::FromStr, sync::Arc};
+use tokio::time::Duration;
+use uuid::Uuid;
+
+/// Constant-product (`x * y = k`) pool holding a token reserve and a quote reserve.
+///
+/// Swap fees are taken from the input amount and left in the pool, so `k` grows
+/// slightly with every trade.
+#[derive(Debug, Clone)]
+pub struct AmmPool {
+    token_reserve: Decimal,
+    quote_reserve: Decimal,
+    fee_rate: Decimal,
+}
+
+/// Outcome of a single swap against an [`AmmPool`].
+#[derive(Debug, Clone)]
+pub struct SwapResult {
+    pub side: TradeSide,
+    pub token_amount: Decimal,
+    pub quote_amount: Decimal,
+    /// Fee charged on the input side, denominated in the input asset.
+    pub fee: Decimal,
+    pub price_before: Decimal,
+    pub price_after: Decimal,
+}
+
+impl SwapResult {
+    /// Average price paid or received per token, including fee and price impact.
+    pub fn execution_price(&self) -> Decimal {
+        self.quote_amount / self.token_amount
+    }
+
+    /// Relative move of the pool price caused by this swap.
+    pub fn price_impact(&self) -> Decimal {
+        (self.price_after - self.price_before).abs() / self.price_before
+    }
+
+    pub fn into_transaction(self, id: Uuid, symbol: String, timestamp: DateTime<Utc>) -> Transaction {
+        Transaction::new_at(id, symbol, self.execution_price(), self.token_amount, self.side, timestamp)
+    }
+}
+
+impl AmmPool {
+    pub fn new(token_reserve: Decimal, quote_reserve: Decimal, fee_rate: Decimal) -> Result<Self> {
+        if token_reserve <= Decimal::ZERO || quote_reserve <= Decimal::ZERO {
+            bail!("Pool reserves must be positive");
+        }
+        if fee_rate < Decimal::ZERO || fee_rate >= Decimal::ONE {
+            bail!("Pool fee rate must be in [0, 1)");
+        }
+        Ok(Self {
+            token_reserve,
+            quote_reserve,
+            fee_rate,
+        })
+    }
+
+    pub fn token_reserve(&self) -> Decimal {
+        self.token_reserve
+    }
+
+    pub fn quote_reserve(&self) -> Decimal {
+        self.quote_reserve
+    }
+
+    /// Spot price of one token in quote units.
+    pub fn price(&self) -> Decimal {
+        self.quote_reserve / self.token_reserve
+    }
+
+    /// Swaps `quote_in` quote units for tokens.
+    pub fn buy(&mut self, quote_in: Decimal) -> Result<SwapResult> {
+        if quote_in <= Decimal::ZERO {
+            bail!("Swap input must be positive");
+        }
+        let price_before = self.price();
+        let fee = quote_in * self.fee_rate;
+        let effective_in = quote_in - fee;
+        let token_out = self.token_reserve * effective_in / (self.quote_reserve + effective_in);
+        if token_out <= Decimal::ZERO {
+            bail!("Swap output rounds to zero");
+        }
+
+        self.quote_reserve += quote_in;
+        self.token_reserve -= token_out;
+
+        Ok(SwapResult {
+            side: TradeSide::Buy,
+            token_amount: token_out,
+            quote_amount: quote_in,
+            fee,
+            price_before,
+            price_after: self.price(),
+        })
+    }
+
+    /// Swaps `token_in` tokens for quote units.
+    pub fn sell(&mut self, token_in: Decimal) -> Result<SwapResult> {
+        if token_in <= Decimal::ZERO {
+            bail!("Swap input must be positive");
+        }
+        let price_before = self.price();
+        let fee = token_in * self.fee_rate;
+        let effective_in = token_in - fee;
+        let quote_out = self.quote_reserve * effective_in / (self.token_reserve + effective_in);
+        if quote_out <= Decimal::ZERO {
+            bail!("Swap output rounds to zero");
+        }
+
+        self.token_reserve += token_in;
+        self.quote_reserve -= quote_out;
+
+        Ok(SwapResult {
+            side: TradeSide::Sell,
+            token_amount: token_in,
+            quote_amount: quote_out,
+            fee,
+            price_before,
+            price_after: self.price(),
+        })
+    }
+
+    /// Adds liquidity at the current price, returning the `(token, quote)` amounts deposited.
+    pub fn add_liquidity(&mut self, quote_amount: Decimal) -> Result<(Decimal, Decimal)> {
+        if quote_amount <= Decimal::ZERO {
+            bail!("Liquidity amount must be positive");
+        }
+        let token_amount = quote_amount / self.price();
+        self.token_reserve += token_amount;
+        self.quote_reserve += quote_amount;
+        Ok((token_amount, quote_amount))
+    }
+
+    /// Withdraws `share` (between 0 and 1, exclusive) of both reserves, returning the
+    /// `(token, quote)` amounts removed.
+    pub fn remove_liquidity(&mut self, share: Decimal) -> Result<(Decimal, Decimal)> {
+        if share <= Decimal::ZERO || share >= Decimal::ONE {
+            bail!("Liquidity share must be in (0, 1)");
+        }
+        let token_amount = self.token_reserve * share;
+        let quote_amount = self.quote_reserve * share;
+        self.token_reserve -= token_amount;
+        self.quote_reserve -= quote_amount;
+        Ok((token_amount, quote_amount))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AmmPoolConfig {
+    pub token_reserve: Decimal,
+    pub quote_reserve: Decimal,
+    pub fee_rate: Decimal,
+    /// Bounds on the quote value of each simulated swap.
+    pub min_trade_quote: Decimal,
+    pub max_trade_quote: Decimal,
+    pub buy_probability: f64,
+    /// Chance that a tick produces a liquidity add/remove instead of a swap.
+    pub liquidity_event_probability: f64,
+    /// Largest fraction of the reserves moved by a single liquidity event.
+    pub max_liquidity_change: f64,
+}
+
+impl Default for AmmPoolConfig {
+    fn default() -> Self {
+        Self {
+            token_reserve: Decimal::from_str("1000000").expect("Invalid default token reserve"),
+            quote_reserve: Decimal::from_str("100000").expect("Invalid default quote reserve"),
+            fee_rate: Decimal::from_str("0.003").expect("Invalid default fee rate"),
+            min_trade_quote: Decimal::from_str("1.0").expect("Invalid default min trade"),
+            max_trade_quote: Decimal::from_str("500.0").expect("Invalid default max trade"),
+            buy_probability: 0.5,
+            liquidity_event_probability: 0.01,
+            max_liquidity_change: 0.05,
+        }
+    }
+}
+
+impl AmmPoolConfig {
+    pub fn validate(&self) -> Result<()> {
+        AmmPool::new(self.token_reserve, self.quote_reserve, self.fee_rate)?;
+        if self.min_trade_quote <= Decimal::ZERO || self.max_trade_quote < self.min_trade_quote {
+            bail!("Pool trade sizes need 0 < min_trade_quote <= max_trade_quote");
+        }
+        if !(0.0..=1.0).contains(&self.buy_probability) {
+            bail!("Pool buy_probability must be in [0, 1]");
+        }
+        if !(0.0..=1.0).contains(&self.liquidity_event_probability) {
+            bail!("Pool liquidity_event_probability must be in [0, 1]");
+        }
+        // A liquidity event removes up to this share of the reserves, which must leave some behind
+        if !(0.0..1.0).contains(&self.max_liquidity_change) {
+            bail!("Pool max_liquidity_change must be in [0, 1)");
+        }
+        Ok(())
+    }
+}
+
+pub struct AmmPoolGenerator {
+    config: AmmPoolConfig,
+    seed: Option<u64>,
+    clock: Arc<dyn Clock>,
+    checkpoint: Checkpoint,
+}
+
+impl Default for AmmPoolGenerator {
+    fn default() -> Self {
+        Self::new(AmmPoolConfig::default())
+    }
+}
+
+impl AmmPoolGenerator {
+    pub fn new(config: AmmPoolConfig) -> Self {
+        Self {
+            config,
+            seed: None,
+            clock: Arc::new(SystemClock),
+            checkpoint: Checkpoint::default(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Resumes from and records into `checkpoint`.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Streams swaps and liquidity changes against a simulated pool, one per tick.
+    pub fn generate_event_stream(
+        &self,
+        symbol: String,
+        interval_ms: u64,
+    ) -> Pin<Box<dyn Stream<Item = Result<MarketEvent>> + Send>> {
+        let config = self.config.clone();
+        let clock = self.clock.clone();
+        let tick = Duration::from_millis(interval_ms);
+        let mut rng = new_rng(self.seed);
+        let checkpoint = self.checkpoint.clone();
+        let (token_reserve, quote_reserve) = match checkpoint.get() {
+            GeneratorState::Pool { token_reserve, quote_reserve } => (token_reserve, quote_reserve),
+            _ => (config.token_reserve, config.quote_reserve),
+        };
+
+        Box::pin(stream! {
+            let mut ticker = Ticker::new(clock.clone(), tick);
+            let mut pool = AmmPool::new(token_reserve, quote_reserve, config.fee_rate)?;
+
+            loop {
+                ticker.tick().await;
+
+                let event = next_pool_event(&mut pool, &config, &mut rng, &symbol, clock.now())?;
+                checkpoint.set(GeneratorState::Pool {
+                    token_reserve: pool.token_reserve(),
+                    quote_reserve: pool.quote_reserve(),
+                });
+                if let Some(event) = event {
+                    yield Ok(event);
+                }
+            }
+        })
+    }
+
+    /// Same as [`generate_event_stream`](Self::generate_event_stream) but only yields swaps,
+    /// so it can be used anywhere a `MockDataGenerator` stream is expected.
+    pub fn generate_transaction_stream(
+        &self,
+        symbol: String,
+        interval_ms: u64,
+    ) -> Pin<Box<dyn Stream<Item = Result<Transaction>> + Send>> {
+        Box::pin(
+            self.generate_event_stream(symbol, interval_ms)
+                .filter_map(|event| async move {
+                    match event {
+                        Ok(MarketEvent::Trade(transaction)) => Some(Ok(transaction)),
+                        Ok(_) => None,
+                        Err(err) => Some(Err(err)),
+                    }
+                }),
+        )
+    }
+}
+
+/// Advances a simulated pool by one tick: either a liquidity change or a swap sized
+/// from `config`. Returns `None` when the tick produced nothing worth publishing.
+pub(crate) fn next_pool_event(
+    pool: &mut AmmPool,
+    config: &AmmPoolConfig,
+    rng: &mut StdRng,
+    symbol: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<MarketEvent>> {
+    if rng.random_bool(config.liquidity_event_probability) {
+        let fraction = Decimal::from_f64(rng.random::<f64>() * config.max_liquidity_change)
+            .context("Failed to generate liquidity fraction")?;
+        if fraction.is_zero() {
+            return Ok(None);
+        }
+
+        let (action, (token_amount, quote_amount)) = if rng.random_bool(0.5) {
+            (LiquidityAction::Add, pool.add_liquidity(pool.quote_reserve() * fraction)?)
+        } else {
+            (LiquidityAction::Remove, pool.remove_liquidity(fraction)?)
+        };
+
+        return Ok(Some(MarketEvent::Liquidity(LiquidityEvent {
+            id: random_id(rng),
+            symbol: symbol.to_string(),
+            action,
+            token_amount,
+            quote_amount,
+            token_reserve: pool.token_reserve(),
+            quote_reserve: pool.quote_reserve(),
+            timestamp: now,
+        })));
+    }
+
+    let trade_range = config.max_trade_quote - config.min_trade_quote;
+    let trade_quote = config.min_trade_quote
+        + trade_range * Decimal::from_f64(rng.random::<f64>()).context("Failed to generate random trade size")?;
+
+    let swap = if rng.random_bool(config.buy_probability) {
+        pool.buy(trade_quote)?
+    } else {
+        pool.sell(trade_quote / pool.price())?
+    };
+
+    Ok(Some(MarketEvent::Trade(swap.into_transaction(random_id(rng), symbol.to_string(), now))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn pool() -> AmmPool {
+        AmmPool::new(Decimal::from(1_000_000), Decimal::from(100_000), Decimal::from_str("0.003").unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_buy_moves_price_up_and_charges_fee() {
+        let mut pool = pool();
+        let k_before = pool.token_reserve() * pool.quote_reserve();
+
+        let swap = pool.buy(Decimal::from(1_000)).unwrap();
+
+        assert_eq!(swap.side, TradeSide::Buy);
+        assert_eq!(swap.fee, Decimal::from(3));
+        assert!(swap.price_after > swap.price_before);
+        // Execution price sits above the starting spot price because of fee and impact.
+        assert!(swap.execution_price() > swap.price_before);
+        assert!(pool.token_reserve() * pool.quote_reserve() >= k_before);
+    }
+
+    #[test]
+    fn test_sell_moves_price_down() {
+        let mut pool = pool();
+        let swap = pool.sell(Decimal::from(10_000)).unwrap();
+
+        assert_eq!(swap.side, TradeSide::Sell);
+        assert!(swap.price_after < swap.price_before);
+        assert!(swap.execution_price() < swap.price_before);
+        assert!(swap.price_impact() > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_liquidity_keeps_price() {
+        let mut pool = pool();
+        let price = pool.price();
+
+        pool.add_liquidity(Decimal::from(5_000)).unwrap();
+        assert_eq!(pool.price(), price);
+
+        pool.remove_liquidity(Decimal::from_str("0.5").unwrap()).unwrap();
+        assert_eq!(pool.price(), price);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(AmmPoolConfig::default().validate().is_ok());
+        for config in [
+            AmmPoolConfig { token_reserve: Decimal::ZERO, ..AmmPoolConfig::default() },
+            AmmPoolConfig { quote_reserve: Decimal::from(-1), ..AmmPoolConfig::default() },
+            AmmPoolConfig { fee_rate: Decimal::ONE, ..AmmPoolConfig::default() },
+            AmmPoolConfig { min_trade_quote: Decimal::from(600), ..AmmPoolConfig::default() },
+            AmmPoolConfig { min_trade_quote: Decimal::ZERO, ..AmmPoolConfig::default() },
+            AmmPoolConfig { max_liquidity_change: 1.0, ..AmmPoolConfig::default() },
+            AmmPoolConfig { max_liquidity_change: -0.1, ..AmmPoolConfig::default() },
+            AmmPoolConfig { max_liquidity_change: f64::NAN, ..AmmPoolConfig::default() },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_amm_pool_generator() {
+        let generator = AmmPoolGenerator::default();
+        let mut stream = generator.generate_transaction_stream("PEPE".to_string(), 1);
+
+        let transaction = stream.next().await.unwrap().unwrap();
+        assert_eq!(transaction.symbol, "PEPE");
+        assert!(transaction.price > Decimal::ZERO);
+        assert!(transaction.volume > Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_restarted_generator_resumes_from_checkpoint() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(crate::services::SimulatedClock::new(start));
+        let checkpoint = Checkpoint::default();
+        let generator = AmmPoolGenerator::new(AmmPoolConfig {
+            min_trade_quote: Decimal::from(5_000),
+            max_trade_quote: Decimal::from(10_000),
+            buy_probability: 1.0,
+            liquidity_event_probability: 0.0,
+            ..AmmPoolConfig::default()
+        })
+        .with_clock(clock)
+        .with_checkpoint(checkpoint.clone());
+
+        let first: Vec<Transaction> = generator
+            .generate_transaction_stream("PEPE".to_string(), 1)
+            .take(10)
+            .map(|result| result.unwrap())
+            .collect()
+            .await;
+        let GeneratorState::Pool { quote_reserve, .. } = checkpoint.get() else {
+            panic!("pool state not recorded");
+        };
+        assert!(quote_reserve > Decimal::from(150_000));
+
+        // Buys only push the price up, so a fresh pool would trade below the last price
+        let resumed = generator
+            .generate_transaction_stream("PEPE".to_string(), 1)
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(resumed.price > first.last().unwrap().price);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::models::{Bar, BarType, Transaction};
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+const MAX_BAR_HISTORY: usize = 1000;
+
+/// Bar series key: venue, symbol, bar type and threshold.
+type BarKey = (String, String, BarType, Decimal);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BarConfig {
+    pub symbol: String,
+    #[serde(rename = "type")]
+    pub bar_type: BarType,
+    /// Trades for tick bars, volume for volume bars, quote value for dollar bars.
+    pub threshold: Decimal,
+}
+
+impl BarConfig {
+    pub fn validate_all(configs: &[BarConfig]) -> Result<()> {
+        let mut seen = HashSet::new();
+        for config in configs {
+            if config.bar_type == BarType::Renko {
+                bail!("Bars for {} need a tick, volume or dollar type", config.symbol);
+            }
+            if config.threshold <= Decimal::ZERO {
+                bail!("Bars for {} need a positive threshold", config.symbol);
+            }
+            if config.bar_type == BarType::Tick && !config.threshold.fract().is_zero() {
+                bail!("Tick bars for {} need a whole number of trades", config.symbol);
+            }
+            if !seen.insert((config.symbol.as_str(), config.bar_type, config.threshold.normalize())) {
+                bail!("Bars for {} are configured more than once", config.symbol);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The open bar of a series, or its last bar once closed until the next trade.
+struct OpenBar {
+    bar: Bar,
+    trades: u64,
+}
+
+/// Builds tick, volume and dollar bars for the configured symbols, on every venue.
+///
+/// A bar closes on the trade that reaches its threshold; trades are never split, so
+/// volume and dollar bars can overshoot by up to one trade.
+#[derive(Default)]
+pub struct BarAggregator {
+    configs: HashMap<String, Vec<(BarType, Decimal)>>,
+    current: DashMap<BarKey, OpenBar>,
+    history: DashMap<BarKey, Vec<Bar>>,
+}
+
+impl BarAggregator {
+    pub fn new(configs: Vec<BarConfig>) -> Self {
+        let mut by_symbol: HashMap<String, Vec<(BarType, Decimal)>> = HashMap::new();
+        for config in configs {
+            by_symbol
+                .entry(config.symbol)
+                .or_default()
+                .push((config.bar_type, config.threshold.normalize()));
+        }
+        Self {
+            configs: by_symbol,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self, symbol: &str, bar_type: BarType, threshold: Decimal) -> bool {
+        self.configs
+            .get(symbol)
+            .is_some_and(|bars| bars.contains(&(bar_type, threshold.normalize())))
+    }
+
+    /// Adds `transaction` to every bar series of its symbol on `venue` and returns the
+    /// updated bars. A bar the trade completes comes back closed.
+    pub fn on_trade(&self, venue: &str, transaction: &Transaction) -> Vec<Bar> {
+        let Some(bars) = self.configs.get(&transaction.symbol) else {
+            return Vec::new();
+        };
+        let mut updates = Vec::with_capacity(bars.len());
+        for &(bar_type, threshold) in bars {
+            let key = (venue.to_string(), transaction.symbol.clone(), bar_type, threshold);
+            let new_bar = || {
+                Bar::new(
+                    venue.to_string(),
+                    transaction.symbol.clone(),
+                    bar_type,
+                    threshold,
+                    transaction.timestamp,
+                    transaction.price,
+                )
+            };
+            let mut open = self.current.entry(key.clone()).or_insert_with(|| OpenBar {
+                bar: new_bar(),
+                trades: 0,
+            });
+
+            // Open the next bar, carrying the cumulative delta forward
+            if open.bar.is_closed {
+                let mut bar = new_bar();
+                bar.ohlcv.cumulative_delta = open.bar.ohlcv.cumulative_delta;
+                *open = OpenBar { bar, trades: 0 };
+            }
+
+            open.bar.update(transaction.price, transaction.volume, transaction.side, transaction.timestamp);
+            open.trades += 1;
+
+            let progress = match bar_type {
+                BarType::Tick => Decimal::from(open.trades),
+                BarType::Volume => open.bar.ohlcv.volume,
+                BarType::Dollar => open.bar.ohlcv.quote_volume,
+                BarType::Renko => Decimal::ZERO,
+            };
+            if progress >= threshold {
+                open.bar.close();
+                let mut history = self.history.entry(key).or_default();
+                history.push(open.bar.clone());
+                if history.len() > MAX_BAR_HISTORY {
+                    let len = history.len();
+                    history.drain(0..len - MAX_BAR_HISTORY);
+                }
+            }
+            updates.push(open.bar.clone());
+        }
+        updates
+    }
+
+    /// Closed bars, oldest first.
+    pub fn history(&self, venue: &str, symbol: &str, bar_type: BarType, threshold: Decimal, limit: usize) -> Vec<Bar> {
+        self.history
+            .get(&(venue.to_string(), symbol.to_string(), bar_type, threshold.normalize()))
+            .map(|bars| bars[bars.len().saturating_sub(limit)..].to_vec())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Ohlc, TradeSide};
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_bars_close_on_threshold() {
+        let aggregator = BarAggregator::new(vec![
+            BarConfig { symbol: "DOGE".to_string(), bar_type: BarType::Tick, threshold: Decimal::new(3, 0) },
+            BarConfig { symbol: "DOGE".to_string(), bar_type: BarType::Volume, threshold: Decimal::new(10, 0) },
+            BarConfig { symbol: "DOGE".to_string(), bar_type: BarType::Dollar, threshold: Decimal::new(500, 0) },
+        ]);
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trades = [(100, 4, TradeSide::Buy), (101, 4, TradeSide::Sell), (99, 4, TradeSide::Buy), (102, 1, TradeSide::Buy)];
+        for (index, (price, volume, side)) in trades.into_iter().enumerate() {
+            let transaction = Transaction::new_at(
+                Uuid::new_v4(),
+                "DOGE".to_string(),
+                Decimal::new(price, 0),
+                Decimal::new(volume, 0),
+                side,
+                start + chrono::Duration::seconds(index as i64),
+            );
+            aggregator.on_trade("default", &transaction);
+        }
+
+        // Three trades close a tick bar; the fourth opens the next one
+        let ticks = aggregator.history("default", "DOGE", BarType::Tick, Decimal::new(3, 0), 10);
+        assert_eq!(ticks.len(), 1);
+        let bar = &ticks[0];
+        assert_eq!(bar.ohlc(), (Decimal::new(100, 0), Decimal::new(101, 0), Decimal::new(99, 0), Decimal::new(99, 0)));
+        assert_eq!(bar.ohlcv.volume, Decimal::new(12, 0));
+        assert_eq!((bar.open_time, bar.close_time), (start, start + chrono::Duration::seconds(2)));
+        assert_eq!((bar.bar_type, bar.threshold), (BarType::Tick, Decimal::new(3, 0)));
+
+        // 12 units overshoot the volume threshold of 10 in the third trade
+        let volume = aggregator.history("default", "DOGE", BarType::Volume, Decimal::TEN, 10);
+        assert_eq!(volume.len(), 1);
+        assert_eq!(volume[0].ohlcv.delta, Decimal::new(4, 0));
+
+        // 400 + 404 of quote value closes the dollar bar on the second trade
+        let dollar = aggregator.history("default", "DOGE", BarType::Dollar, Decimal::new(500, 0), 10);
+        assert_eq!(dollar.len(), 1);
+        assert_eq!(dollar[0].ohlcv.close, Decimal::new(101, 0));
+
+        assert!(aggregator.is_enabled("DOGE", BarType::Volume, "10.0".parse().unwrap()));
+        assert!(!aggregator.is_enabled("DOGE", BarType::Tick, Decimal::TEN));
+        assert!(BarConfig::validate_all(&[BarConfig {
+            symbol: "DOGE".to_string(),
+            bar_type: BarType::Tick,
+            threshold: Decimal::new(25, 1),
+        }])
+        .is_err());
+    }
+}
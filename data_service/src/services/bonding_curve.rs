@@ -0,0 +1,423 @@
+use super::amm_pool::{next_pool_event, AmmPool, AmmPoolConfig};
+use super::clock::{Clock, SystemClock, Ticker};
+use super::mock_data::{new_rng, random_id, Checkpoint, GeneratorState};
+use crate::models::{GraduationEvent, MarketEvent, TradeSide, Transaction};
+use anyhow::{bail, Context, Result};
+use async_stream::stream;
+use futures::Stream;
+use rand::Rng;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{pin::Pin, sync::Arc};
+use tokio::time::Duration;
+
+/// Price as a function of tokens sold from the curve.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CurveShape {
+    /// `price = base_price + slope * supply`
+    Linear { base_price: f64, slope: f64 },
+    /// `price = base_price * e^(growth * supply)`
+    Exponential { base_price: f64, growth: f64 },
+}
+
+impl CurveShape {
+    /// Requires a positive starting price and a curve that never falls.
+    pub fn validate(&self) -> Result<()> {
+        let (base_price, steepness) = match *self {
+            CurveShape::Linear { base_price, slope } => (base_price, slope),
+            CurveShape::Exponential { base_price, growth } => (base_price, growth),
+        };
+        if !base_price.is_finite() || base_price <= 0.0 {
+            bail!("Curve base_price must be positive");
+        }
+        if !steepness.is_finite() || steepness < 0.0 {
+            bail!("Curve slope and growth must be finite and non-negative");
+        }
+        Ok(())
+    }
+
+    pub fn price_at(&self, supply: f64) -> f64 {
+        match *self {
+            CurveShape::Linear { base_price, slope } => base_price + slope * supply,
+            CurveShape::Exponential { base_price, growth } => base_price * (growth * supply).exp(),
+        }
+    }
+
+    /// Quote needed to move the curve from `from` to `to` tokens sold.
+    fn cost(&self, from: f64, to: f64) -> f64 {
+        match *self {
+            CurveShape::Linear { base_price, slope } => {
+                base_price * (to - from) + slope / 2.0 * (to * to - from * from)
+            }
+            CurveShape::Exponential { base_price, growth: 0.0 } => base_price * (to - from),
+            CurveShape::Exponential { base_price, growth } => {
+                base_price / growth * ((growth * to).exp() - (growth * from).exp())
+            }
+        }
+    }
+
+    /// Supply reached after spending `quote` starting at `from`; inverse of [`cost`](Self::cost).
+    fn supply_after_spending(&self, from: f64, quote: f64) -> f64 {
+        match *self {
+            CurveShape::Linear { base_price, slope: 0.0 } => from + quote / base_price,
+            CurveShape::Linear { base_price, slope } => {
+                let c = base_price * from + slope / 2.0 * from * from + quote;
+                (-base_price + (base_price * base_price + 2.0 * slope * c).sqrt()) / slope
+            }
+            CurveShape::Exponential { base_price, growth: 0.0 } => from + quote / base_price,
+            CurveShape::Exponential { base_price, growth } => {
+                ((growth * from).exp() + quote * growth / base_price).ln() / growth
+            }
+        }
+    }
+}
+
+/// Token launch state: tokens are minted on buys and burned on sells, with the quote
+/// paid in held as the curve reserve.
+#[derive(Debug, Clone)]
+pub struct BondingCurve {
+    shape: CurveShape,
+    fee_rate: f64,
+    supply: f64,
+    reserve: f64,
+}
+
+impl BondingCurve {
+    pub fn new(shape: CurveShape, fee_rate: f64) -> Result<Self> {
+        if !(0.0..1.0).contains(&fee_rate) {
+            bail!("Curve fee rate must be in [0, 1)");
+        }
+        if shape.price_at(0.0) <= 0.0 {
+            bail!("Curve starting price must be positive");
+        }
+        Ok(Self {
+            shape,
+            fee_rate,
+            supply: 0.0,
+            reserve: 0.0,
+        })
+    }
+
+    pub fn supply(&self) -> f64 {
+        self.supply
+    }
+
+    pub fn reserve(&self) -> f64 {
+        self.reserve
+    }
+
+    pub fn price(&self) -> f64 {
+        self.shape.price_at(self.supply)
+    }
+
+    /// Spends `quote_in` on the curve, returning the tokens minted.
+    pub fn buy(&mut self, quote_in: f64) -> Result<f64> {
+        if quote_in <= 0.0 {
+            bail!("Curve buy amount must be positive");
+        }
+        let effective_in = quote_in * (1.0 - self.fee_rate);
+        let new_supply = self.shape.supply_after_spending(self.supply, effective_in);
+        let tokens_out = new_supply - self.supply;
+        if !tokens_out.is_finite() || tokens_out <= 0.0 {
+            bail!("Curve buy produced no tokens");
+        }
+        self.supply = new_supply;
+        self.reserve += effective_in;
+        Ok(tokens_out)
+    }
+
+    /// Returns `tokens_in` to the curve, returning the quote paid out.
+    pub fn sell(&mut self, tokens_in: f64) -> Result<f64> {
+        if tokens_in <= 0.0 || tokens_in > self.supply {
+            bail!("Curve sell amount must be positive and within supply");
+        }
+        let new_supply = self.supply - tokens_in;
+        let gross_out = self.shape.cost(new_supply, self.supply).min(self.reserve);
+        self.supply = new_supply;
+        self.reserve -= gross_out;
+        Ok(gross_out * (1.0 - self.fee_rate))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BondingCurveConfig {
+    pub shape: CurveShape,
+    pub fee_rate: f64,
+    /// Supply used for market cap, including tokens not yet sold from the curve.
+    pub total_supply: f64,
+    pub graduation_market_cap: f64,
+    pub min_trade_quote: f64,
+    pub max_trade_quote: f64,
+    pub buy_probability: f64,
+    /// Trading parameters once the token has graduated. Reserves are replaced by the
+    /// curve's reserve at graduation.
+    pub pool: AmmPoolConfig,
+}
+
+impl Default for BondingCurveConfig {
+    fn default() -> Self {
+        Self {
+            shape: CurveShape::Exponential {
+                base_price: 0.000005,
+                growth: 0.0000000033,
+            },
+            fee_rate: 0.01,
+            total_supply: 1_000_000_000.0,
+            graduation_market_cap: 69_000.0,
+            min_trade_quote: 1.0,
+            max_trade_quote: 200.0,
+            buy_probability: 0.65,
+            pool: AmmPoolConfig::default(),
+        }
+    }
+}
+
+impl BondingCurveConfig {
+    pub fn validate(&self) -> Result<()> {
+        self.shape.validate()?;
+        if !(0.0..1.0).contains(&self.fee_rate) {
+            bail!("Curve fee_rate must be in [0, 1)");
+        }
+        if !self.total_supply.is_finite() || self.total_supply <= 0.0 {
+            bail!("Curve total_supply must be positive");
+        }
+        if !self.graduation_market_cap.is_finite() || self.graduation_market_cap <= 0.0 {
+            bail!("Curve graduation_market_cap must be positive");
+        }
+        if !(self.min_trade_quote > 0.0 && self.min_trade_quote <= self.max_trade_quote && self.max_trade_quote.is_finite()) {
+            bail!("Curve trade sizes need 0 < min_trade_quote <= max_trade_quote");
+        }
+        if !(0.0..=1.0).contains(&self.buy_probability) {
+            bail!("Curve buy_probability must be in [0, 1]");
+        }
+        self.pool.validate().context("Invalid graduated pool")
+    }
+}
+
+pub struct BondingCurveGenerator {
+    config: BondingCurveConfig,
+    seed: Option<u64>,
+    clock: Arc<dyn Clock>,
+    checkpoint: Checkpoint,
+}
+
+impl Default for BondingCurveGenerator {
+    fn default() -> Self {
+        Self::new(BondingCurveConfig::default())
+    }
+}
+
+impl BondingCurveGenerator {
+    pub fn new(config: BondingCurveConfig) -> Self {
+        Self {
+            config,
+            seed: None,
+            clock: Arc::new(SystemClock),
+            checkpoint: Checkpoint::default(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Resumes from and records into `checkpoint`.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// Streams curve trades until the graduation market cap is reached, then a single
+    /// [`MarketEvent::Graduated`] followed by pool-driven events for the same symbol.
+    pub fn generate_event_stream(
+        &self,
+        symbol: String,
+        interval_ms: u64,
+    ) -> Pin<Box<dyn Stream<Item = Result<MarketEvent>> + Send>> {
+        let config = self.config.clone();
+        let clock = self.clock.clone();
+        let tick = Duration::from_millis(interval_ms);
+        let mut rng = new_rng(self.seed);
+        let checkpoint = self.checkpoint.clone();
+        let resume = checkpoint.get();
+
+        Box::pin(stream! {
+            let mut ticker = Ticker::new(clock.clone(), tick);
+
+            // A restart after graduation goes straight back to the pool
+            let mut pool = if let GeneratorState::Pool { token_reserve, quote_reserve } = resume {
+                AmmPool::new(token_reserve, quote_reserve, config.pool.fee_rate)?
+            } else {
+                let mut curve = BondingCurve::new(config.shape, config.fee_rate)?;
+                if let GeneratorState::Curve { supply, reserve } = resume {
+                    curve.supply = supply;
+                    curve.reserve = reserve;
+                }
+
+                loop {
+                    ticker.tick().await;
+
+                    let trade_quote = config.min_trade_quote
+                        + (config.max_trade_quote - config.min_trade_quote) * rng.random::<f64>();
+
+                    let (side, tokens, quote) = if curve.supply() == 0.0 || rng.random_bool(config.buy_probability) {
+                        (TradeSide::Buy, curve.buy(trade_quote)?, trade_quote)
+                    } else {
+                        let tokens = (trade_quote / curve.price()).min(curve.supply());
+                        (TradeSide::Sell, tokens, curve.sell(tokens)?)
+                    };
+                    checkpoint.set(GeneratorState::Curve {
+                        supply: curve.supply(),
+                        reserve: curve.reserve(),
+                    });
+
+                    let price = Decimal::from_f64(quote / tokens).context("Failed to convert curve trade price")?;
+                    let volume = Decimal::from_f64(tokens).context("Failed to convert curve trade volume")?;
+                    yield Ok(MarketEvent::Trade(Transaction::new_at(
+                        random_id(&mut rng),
+                        symbol.clone(),
+                        price,
+                        volume,
+                        side,
+                        clock.now(),
+                    )));
+
+                    let market_cap = curve.price() * config.total_supply;
+                    if market_cap >= config.graduation_market_cap {
+                        break;
+                    }
+                }
+
+                // Seed the pool at the curve's final price so the chart stays continuous.
+                let quote_reserve = Decimal::from_f64(curve.reserve()).context("Failed to convert curve reserve")?;
+                let price = Decimal::from_f64(curve.price()).context("Failed to convert curve price")?;
+                let token_reserve = quote_reserve / price;
+                let pool = AmmPool::new(token_reserve, quote_reserve, config.pool.fee_rate)?;
+                checkpoint.set(GeneratorState::Pool { token_reserve, quote_reserve });
+
+                yield Ok(MarketEvent::Graduated(GraduationEvent {
+                    id: random_id(&mut rng),
+                    symbol: symbol.clone(),
+                    price,
+                    market_cap: Decimal::from_f64(curve.price() * config.total_supply)
+                        .context("Failed to convert market cap")?,
+                    supply: Decimal::from_f64(curve.supply()).context("Failed to convert curve supply")?,
+                    token_reserve,
+                    quote_reserve,
+                    timestamp: clock.now(),
+                }));
+                pool
+            };
+
+            loop {
+                ticker.tick().await;
+
+                let event = next_pool_event(&mut pool, &config.pool, &mut rng, &symbol, clock.now())?;
+                checkpoint.set(GeneratorState::Pool {
+                    token_reserve: pool.token_reserve(),
+                    quote_reserve: pool.quote_reserve(),
+                });
+                if let Some(event) = event {
+                    yield Ok(event);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_linear_curve_round_trip() {
+        let shape = CurveShape::Linear {
+            base_price: 0.01,
+            slope: 0.0001,
+        };
+        let mut curve = BondingCurve::new(shape, 0.0).unwrap();
+
+        let tokens = curve.buy(100.0).unwrap();
+        assert!(curve.price() > 0.01);
+        assert!((curve.reserve() - 100.0).abs() < 1e-9);
+
+        let quote = curve.sell(tokens).unwrap();
+        assert!((quote - 100.0).abs() < 1e-6);
+        assert!(curve.supply().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exponential_curve_prices_rise_with_supply() {
+        let mut curve = BondingCurve::new(BondingCurveConfig::default().shape, 0.01).unwrap();
+        let start = curve.price();
+
+        let first = curve.buy(1_000.0).unwrap();
+        let second = curve.buy(1_000.0).unwrap();
+
+        assert!(curve.price() > start);
+        // The same spend buys fewer tokens further up the curve.
+        assert!(second < first);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(BondingCurveConfig::default().validate().is_ok());
+        let flat = BondingCurveConfig {
+            shape: CurveShape::Linear { base_price: 0.01, slope: 0.0 },
+            ..Default::default()
+        };
+        assert!(flat.validate().is_ok());
+
+        for config in [
+            BondingCurveConfig { shape: CurveShape::Linear { base_price: 0.0, slope: 0.0001 }, ..Default::default() },
+            BondingCurveConfig { shape: CurveShape::Linear { base_price: 0.01, slope: -0.0001 }, ..Default::default() },
+            BondingCurveConfig { shape: CurveShape::Exponential { base_price: 0.01, growth: f64::INFINITY }, ..Default::default() },
+            BondingCurveConfig { fee_rate: 1.0, ..Default::default() },
+            BondingCurveConfig { total_supply: 0.0, ..Default::default() },
+            BondingCurveConfig { graduation_market_cap: f64::NAN, ..Default::default() },
+            BondingCurveConfig { min_trade_quote: 0.0, ..Default::default() },
+            BondingCurveConfig { min_trade_quote: 300.0, ..Default::default() },
+            BondingCurveConfig { max_trade_quote: f64::INFINITY, ..Default::default() },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generator_graduates_into_pool() {
+        let config = BondingCurveConfig {
+            graduation_market_cap: 6_000.0,
+            min_trade_quote: 500.0,
+            max_trade_quote: 500.0,
+            buy_probability: 1.0,
+            ..Default::default()
+        };
+        let mut stream = BondingCurveGenerator::new(config).generate_event_stream("LAUNCH".to_string(), 1);
+
+        let mut graduated = None;
+        for _ in 0..100 {
+            match stream.next().await.unwrap().unwrap() {
+                MarketEvent::Graduated(event) => {
+                    graduated = Some(event);
+                    break;
+                }
+                MarketEvent::Trade(transaction) => assert_eq!(transaction.side, TradeSide::Buy),
+                MarketEvent::Liquidity(_) => panic!("Curve should not emit liquidity events"),
+            }
+        }
+
+        let graduated = graduated.expect("Curve never graduated");
+        assert!(graduated.market_cap >= Decimal::from(6_000));
+        assert!(matches!(stream.next().await.unwrap().unwrap(), MarketEvent::Trade(_) | MarketEvent::Liquidity(_)));
+    }
+}
@@ -0,0 +1,342 @@
+use super::volume_profile::TradePrint;
+use crate::models::{Bar, BarType, KLine, Ohlcv};
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use std::collections::{HashSet, VecDeque};
+use uuid::Uuid;
+
+/// Most bricks a single trade can form. A larger jump skips the boxes before its last
+/// `MAX_BRICKS_PER_TRADE`, so the bricks leave a gap but stay on the grid.
+pub const MAX_BRICKS_PER_TRADE: usize = 100;
+
+/// Heikin-Ashi candle of `kline`, following `previous`, the Heikin-Ashi candle before it.
+pub fn heikin_ashi(previous: Option<&KLine>, kline: &KLine) -> KLine {
+    let close = (kline.ohlcv.open + kline.ohlcv.high + kline.ohlcv.low + kline.ohlcv.close) / Decimal::new(4, 0);
+    let open = match previous {
+        Some(previous) => (previous.ohlcv.open + previous.ohlcv.close) / Decimal::TWO,
+        None => (kline.ohlcv.open + kline.ohlcv.close) / Decimal::TWO,
+    };
+    KLine {
+        ohlcv: Ohlcv {
+            open,
+            high: kline.ohlcv.high.max(open).max(close),
+            low: kline.ohlcv.low.min(open).min(close),
+            close,
+            ..kline.ohlcv.clone()
+        },
+        ..kline.clone()
+    }
+}
+
+/// Heikin-Ashi candles of a series, oldest first. The first candle seeds the series.
+pub fn heikin_ashi_series(klines: &[KLine]) -> Vec<KLine> {
+    let mut candles: Vec<KLine> = Vec::with_capacity(klines.len());
+    for kline in klines {
+        let candle = heikin_ashi(candles.last(), kline);
+        candles.push(candle);
+    }
+    candles
+}
+
+/// Turns a live candle stream into Heikin-Ashi candles.
+pub struct HeikinAshiStream {
+    /// Heikin-Ashi candle of the last closed candle.
+    previous: Option<KLine>,
+}
+
+impl HeikinAshiStream {
+    /// Starts after `history`, the closed candles so far.
+    pub fn new(history: &[KLine]) -> Self {
+        Self {
+            previous: heikin_ashi_series(history).pop(),
+        }
+    }
+
+    /// Open candles are recomputed on every update; closed ones become the base of the
+    /// next. Returns `None` for candles already covered by the history.
+    pub fn on_kline(&mut self, kline: &KLine) -> Option<KLine> {
+        if self
+            .previous
+            .as_ref()
+            .is_some_and(|previous| kline.open_time <= previous.open_time)
+        {
+            return None;
+        }
+        let candle = heikin_ashi(self.previous.as_ref(), kline);
+        if candle.is_closed {
+            self.previous = Some(candle.clone());
+        }
+        Some(candle)
+    }
+}
+
+/// Smallest Renko box allowed at `price`, which keeps prices within 10,000 boxes of zero.
+pub fn min_box_size(price: Decimal) -> Decimal {
+    price / Decimal::new(10_000, 0)
+}
+
+/// Checks a requested Renko `box_size` against the highest price in `trades`.
+pub fn validate_box_size(box_size: Decimal, trades: &[TradePrint]) -> Result<()> {
+    if box_size <= Decimal::ZERO {
+        bail!("Renko charts need a positive box_size");
+    }
+    if trades.iter().any(|trade| box_size < min_box_size(trade.price)) {
+        bail!("box_size is too small for the traded prices");
+    }
+    Ok(())
+}
+
+/// Builds Renko bricks of `box_size` from trades.
+///
+/// Bricks sit on a grid of multiples of `box_size`. An up brick forms when the price
+/// reaches a box above the top of the last brick, a down brick when it reaches a box
+/// below its bottom, so reversals take two boxes. The volume of the trades that formed a
+/// brick goes to the first brick of a gap.
+pub struct RenkoBuilder {
+    venue: String,
+    symbol: String,
+    box_size: Decimal,
+    /// Bottom and top of the last brick, or the grid line under the first trade.
+    range: Option<(Decimal, Decimal)>,
+    /// Trades since the last brick, as a brick opening at the last brick's close.
+    forming: Option<Bar>,
+    /// Close of the last brick; the first trade's grid line until one forms.
+    last_close: Decimal,
+}
+
+impl RenkoBuilder {
+    pub fn new(venue: impl Into<String>, symbol: impl Into<String>, box_size: Decimal) -> Self {
+        Self {
+            venue: venue.into(),
+            symbol: symbol.into(),
+            box_size,
+            range: None,
+            forming: None,
+            last_close: Decimal::ZERO,
+        }
+    }
+
+    /// Adds a trade. Returns the bricks it completed, oldest first, followed by the
+    /// forming brick. Trades priced beyond 10,000 boxes are left out.
+    pub fn on_trade(&mut self, trade: &TradePrint) -> Vec<Bar> {
+        if self.box_size < min_box_size(trade.price) {
+            return Vec::new();
+        }
+        let (mut bottom, mut top) = *self.range.get_or_insert_with(|| {
+            let line = (trade.price / self.box_size).floor() * self.box_size;
+            self.last_close = line;
+            (line, line)
+        });
+        let mut forming = self.forming.take().unwrap_or_else(|| self.open_brick(trade, self.last_close));
+        forming.update(trade.price, trade.volume, trade.side, trade.timestamp);
+
+        let limit = Decimal::from(MAX_BRICKS_PER_TRADE);
+        let up = ((trade.price - top) / self.box_size).floor();
+        let down = ((bottom - trade.price) / self.box_size).floor();
+        if up > limit {
+            top += (up - limit) * self.box_size;
+            bottom = top - self.box_size;
+        } else if down > limit {
+            bottom -= (down - limit) * self.box_size;
+            top = bottom + self.box_size;
+        }
+
+        let mut bricks = Vec::new();
+        loop {
+            let (open, close) = if trade.price >= top + self.box_size {
+                (top, top + self.box_size)
+            } else if trade.price <= bottom - self.box_size {
+                (bottom, bottom - self.box_size)
+            } else {
+                break;
+            };
+            bricks.push(Bar {
+                ohlcv: Ohlcv {
+                    open,
+                    high: open.max(close),
+                    low: open.min(close),
+                    close,
+                    ..forming.ohlcv.clone()
+                },
+                is_closed: true,
+                ..forming.clone()
+            });
+            (bottom, top) = (open.min(close), open.max(close));
+            self.last_close = close;
+            let cumulative_delta = forming.ohlcv.cumulative_delta;
+            forming = self.open_brick(trade, close);
+            forming.ohlcv.cumulative_delta = cumulative_delta;
+        }
+        self.range = Some((bottom, top));
+
+        forming.ohlcv.close = trade.price;
+        forming.ohlcv.high = forming.ohlcv.high.max(trade.price);
+        forming.ohlcv.low = forming.ohlcv.low.min(trade.price);
+        bricks.push(forming.clone());
+        self.forming = Some(forming);
+        bricks
+    }
+
+    fn open_brick(&self, trade: &TradePrint, open: Decimal) -> Bar {
+        Bar::new(
+            self.venue.clone(),
+            self.symbol.clone(),
+            BarType::Renko,
+            self.box_size,
+            trade.timestamp,
+            open,
+        )
+    }
+}
+
+/// Turns a live trade stream into Renko bricks, following on from stored trades.
+pub struct RenkoStream {
+    builder: RenkoBuilder,
+    /// Stored trades that may still come in on the stream.
+    seeded: HashSet<Uuid>,
+}
+
+impl RenkoStream {
+    /// Starts after `history`, whose last `overlap` trades may be repeated by the stream
+    /// since trades are broadcast before they are stored.
+    pub fn new(mut builder: RenkoBuilder, history: &[TradePrint], overlap: usize) -> Self {
+        for trade in history {
+            builder.on_trade(trade);
+        }
+        Self {
+            builder,
+            seeded: history.iter().rev().take(overlap).map(|trade| trade.id).collect(),
+        }
+    }
+
+    /// Bricks completed by `trade` and the forming brick; nothing for trades already seeded.
+    pub fn on_trade(&mut self, trade: &TradePrint) -> Vec<Bar> {
+        if self.seeded.remove(&trade.id) {
+            return Vec::new();
+        }
+        self.builder.on_trade(trade)
+    }
+}
+
+/// The last `limit` closed Renko bricks of `box_size` over `trades`, oldest first.
+pub fn renko_bricks(venue: &str, symbol: &str, box_size: Decimal, trades: &[TradePrint], limit: usize) -> Vec<Bar> {
+    let mut builder = RenkoBuilder::new(venue, symbol, box_size);
+    let mut bricks = VecDeque::new();
+    for trade in trades {
+        for brick in builder.on_trade(trade).into_iter().filter(|brick| brick.is_closed) {
+            bricks.push_back(brick);
+            if bricks.len() > limit {
+                bricks.pop_front();
+            }
+        }
+    }
+    bricks.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{KLineInterval, TradeSide};
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_heikin_ashi_updates_open_candle() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut first = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, start, Decimal::new(10, 0));
+        first.update(Decimal::new(14, 0), Decimal::ONE, TradeSide::Buy);
+        first.update(Decimal::new(12, 0), Decimal::ONE, TradeSide::Sell);
+        first.close();
+
+        // O 10, H 14, L 10, C 12: close 11.5, open 11
+        let mut stream = HeikinAshiStream::new(std::slice::from_ref(&first));
+        assert!(stream.on_kline(&first).is_none());
+
+        let mut second = KLine::new(
+            "default".to_string(),
+            "DOGE".to_string(),
+            KLineInterval::OneMinute,
+            start + chrono::Duration::minutes(1),
+            Decimal::new(12, 0),
+        );
+        second.update(Decimal::new(12, 0), Decimal::ONE, TradeSide::Buy);
+        let candle = stream.on_kline(&second).unwrap();
+        assert_eq!(candle.ohlcv.open, Decimal::new(1125, 2));
+        assert_eq!(candle.ohlcv.close, Decimal::new(12, 0));
+        assert_eq!(candle.ohlcv.low, Decimal::new(1125, 2));
+
+        // The open candle is recomputed against the same base until it closes
+        second.update(Decimal::new(8, 0), Decimal::ONE, TradeSide::Sell);
+        let candle = stream.on_kline(&second).unwrap();
+        let ohlcv = &candle.ohlcv;
+        assert_eq!((ohlcv.open, ohlcv.close, ohlcv.low), (Decimal::new(1125, 2), Decimal::TEN, Decimal::new(8, 0)));
+    }
+
+    #[test]
+    fn test_renko_bricks() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trades: Vec<TradePrint> = ["10.3", "11.1", "13.4", "12.5", "11.9", "10.8"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, price)| TradePrint {
+                id: Uuid::new_v4(),
+                timestamp: start + chrono::Duration::seconds(index as i64),
+                price: price.parse().unwrap(),
+                volume: Decimal::ONE,
+                side: TradeSide::Buy,
+            })
+            .collect();
+
+        let bricks = renko_bricks("default", "DOGE", Decimal::ONE, &trades, 10);
+        let edges: Vec<(Decimal, Decimal)> = bricks.iter().map(|brick| (brick.ohlcv.open, brick.ohlcv.close)).collect();
+        // Up 10-11, then a gap to 13.4 forms 11-12 and 12-13; the reversal needs 11.
+        assert_eq!(
+            edges,
+            [
+                (Decimal::new(10, 0), Decimal::new(11, 0)),
+                (Decimal::new(11, 0), Decimal::new(12, 0)),
+                (Decimal::new(12, 0), Decimal::new(13, 0)),
+                (Decimal::new(12, 0), Decimal::new(11, 0)),
+            ]
+        );
+        assert_eq!(bricks[0].ohlcv.volume, Decimal::TWO);
+        assert_eq!(bricks[1].ohlcv.volume, Decimal::ONE);
+        assert!(bricks[2].ohlcv.volume.is_zero());
+        assert_eq!(bricks[3].ohlcv.volume, Decimal::new(3, 0));
+        assert_eq!(bricks[3].bar_type, BarType::Renko);
+
+        // A stream seeded with the first three trades skips the one repeated live
+        let mut stream = RenkoStream::new(RenkoBuilder::new("default", "DOGE", Decimal::ONE), &trades[..3], 1);
+        assert!(stream.on_trade(&trades[2]).is_empty());
+        assert_eq!(stream.on_trade(&trades[3]).len(), 1);
+    }
+
+    #[test]
+    fn test_renko_jumps_are_capped() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trades: Vec<TradePrint> = ["10.5", "1000.5"]
+            .into_iter()
+            .map(|price| TradePrint {
+                id: Uuid::new_v4(),
+                timestamp: start,
+                price: price.parse().unwrap(),
+                volume: Decimal::ONE,
+                side: TradeSide::Buy,
+            })
+            .collect();
+
+        // 990 boxes up: the first 890 are skipped
+        let bricks = renko_bricks("default", "DOGE", Decimal::ONE, &trades, 1000);
+        assert_eq!(bricks.len(), MAX_BRICKS_PER_TRADE);
+        assert_eq!((bricks[0].ohlcv.open, bricks[99].ohlcv.close), (Decimal::new(900, 0), Decimal::new(1000, 0)));
+        assert_eq!(renko_bricks("default", "DOGE", Decimal::ONE, &trades, 10).len(), 10);
+
+        // Boxes under 1/10,000 of a price are refused, and trades priced beyond them ignored
+        assert!(validate_box_size(Decimal::ONE, &trades).is_ok());
+        assert!(validate_box_size(Decimal::new(1, 2), &trades).is_err());
+        assert!(validate_box_size(Decimal::ZERO, &[]).is_err());
+        let mut builder = RenkoBuilder::new("default", "DOGE", Decimal::new(1, 2));
+        assert!(builder.on_trade(&trades[1]).is_empty());
+        assert_eq!(builder.on_trade(&trades[0]).len(), 1);
+    }
+}
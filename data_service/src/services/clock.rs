@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Source of "now" and of waiting, so generators and the data service can run on
+/// simulated time as well as wall-clock time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>>;
+
+    /// Waits until `deadline`, returning at once if it has passed. Producers ticking at a
+    /// fixed rate should sleep to successive deadlines so that time spent between sleeps
+    /// doesn't accumulate as drift.
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.sleep((deadline - self.now()).to_std().unwrap_or_default())
+    }
+}
+
+/// Fixed-rate ticks on a [`Clock`]. Each tick sleeps until a deadline one period after
+/// the last, so time spent between ticks doesn't accumulate as drift.
+pub struct Ticker {
+    clock: Arc<dyn Clock>,
+    period: chrono::Duration,
+    next: DateTime<Utc>,
+}
+
+impl Ticker {
+    pub fn new(clock: Arc<dyn Clock>, period: Duration) -> Self {
+        Self {
+            next: clock.now(),
+            period: chrono::Duration::from_std(period).unwrap_or(chrono::Duration::MAX),
+            clock,
+        }
+    }
+
+    pub async fn tick(&mut self) {
+        let deadline = self.next_deadline(self.period);
+        self.tick_at(deadline).await;
+    }
+
+    /// Deadline `period` after the last tick, for producers whose gaps vary from tick to
+    /// tick.
+    pub fn next_deadline(&self, period: chrono::Duration) -> DateTime<Utc> {
+        // After a stall of more than a period, start again from now instead of
+        // catching up with a burst of back-to-back ticks.
+        let now = self.clock.now();
+        if now - self.next > period {
+            now + period
+        } else {
+            self.next + period
+        }
+    }
+
+    /// Waits until `deadline` and counts the next tick from there.
+    pub async fn tick_at(&mut self, deadline: DateTime<Utc>) {
+        self.next = deadline;
+        self.clock.sleep_until(deadline).await;
+    }
+}
+
+/// Wall-clock time backed by `Utc::now` and the tokio timer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Manually driven time. Sleeping advances the clock instantly instead of waiting,
+/// so a generator can produce a day of trades in milliseconds.
+///
+/// Every `sleep` moves the shared time forward, so a simulated clock should drive a
+/// single producer at a time to keep timestamps monotonic.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl SimulatedClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("Simulated clock lock poisoned");
+        *now += chrono::Duration::from_std(duration).expect("Simulated clock step out of range");
+    }
+
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.now.lock().expect("Simulated clock lock poisoned") = time;
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("Simulated clock lock poisoned")
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.advance(duration);
+        Box::pin(std::future::ready(()))
+    }
+
+    fn sleep_until(&self, deadline: DateTime<Utc>) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        let mut now = self.now.lock().expect("Simulated clock lock poisoned");
+        *now = (*now).max(deadline);
+        Box::pin(std::future::ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[tokio::test]
+    async fn test_simulated_clock_sleep_advances_time() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock = SimulatedClock::new(start);
+
+        clock.sleep(Duration::from_secs(90)).await;
+
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(90));
+
+        // Deadlines in the past leave the clock where it is
+        clock.sleep_until(start + chrono::Duration::seconds(120)).await;
+        clock.sleep_until(start).await;
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(120));
+    }
+
+    #[tokio::test]
+    async fn test_ticker_keeps_to_deadlines() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock = Arc::new(SimulatedClock::new(start));
+        let mut ticker = Ticker::new(clock.clone(), Duration::from_millis(100));
+
+        // Time spent between ticks comes out of the next wait
+        ticker.tick().await;
+        clock.advance(Duration::from_millis(30));
+        ticker.tick().await;
+        assert_eq!(clock.now(), start + chrono::Duration::milliseconds(200));
+
+        // A long stall restarts the schedule from now
+        clock.advance(Duration::from_secs(5));
+        ticker.tick().await;
+        assert_eq!(clock.now(), start + chrono::Duration::milliseconds(5_300));
+    }
+}
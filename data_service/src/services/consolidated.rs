@@ -0,0 +1,230 @@
+use crate::models::{ConstituentPrice, ConstituentStatus, IndexTicker, Transaction};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Venue that consolidated candles and index pseudo-symbols are published on.
+pub const CONSOLIDATED_VENUE: &str = "consolidated";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsolidatedConfig {
+    pub symbol: String,
+    /// Venues to consolidate; every venue when empty.
+    #[serde(default)]
+    pub venues: Vec<String>,
+    /// Name of the index pseudo-symbol, `{symbol}.IDX` by default.
+    #[serde(default)]
+    pub index_symbol: Option<String>,
+    /// Venues without a trade for this long drop out of the index.
+    #[serde(default = "default_stale_after_secs")]
+    pub stale_after_secs: u64,
+    /// Venues further than this fraction from the median are rejected as outliers.
+    #[serde(default = "default_max_deviation")]
+    pub max_deviation: f64,
+}
+
+fn default_stale_after_secs() -> u64 {
+    60
+}
+
+fn default_max_deviation() -> f64 {
+    0.05
+}
+
+/// What one constituent trade turns into.
+pub struct ConsolidatedUpdate {
+    /// The trade re-booked to [`CONSOLIDATED_VENUE`], building cross-venue candles.
+    pub merged: Transaction,
+    /// A tick of the index pseudo-symbol carrying the trade's volume, with the ticker
+    /// behind it. Absent while every venue is stale.
+    pub index: Option<(Transaction, IndexTicker)>,
+}
+
+/// Consolidates one symbol across venues into volume-weighted candles and a median
+/// price index.
+pub struct Consolidator {
+    config: ConsolidatedConfig,
+    index_symbol: String,
+    max_deviation: Decimal,
+    /// Last trade price and time per venue.
+    last_prices: Mutex<BTreeMap<String, (Decimal, DateTime<Utc>)>>,
+}
+
+impl Consolidator {
+    pub fn new(config: ConsolidatedConfig) -> Self {
+        let index_symbol = config.index_symbol.clone().unwrap_or_else(|| format!("{}.IDX", config.symbol));
+        let max_deviation = Decimal::from_f64(config.max_deviation).unwrap_or(Decimal::ZERO);
+        Self {
+            config,
+            index_symbol,
+            max_deviation,
+            last_prices: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn index_symbol(&self) -> &str {
+        &self.index_symbol
+    }
+
+    /// Whether `transaction` is a constituent trade. Expects its venue to be assigned.
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let Some(venue) = transaction.venue.as_deref() else {
+            return false;
+        };
+        transaction.symbol == self.config.symbol
+            && venue != CONSOLIDATED_VENUE
+            && (self.config.venues.is_empty() || self.config.venues.iter().any(|v| v == venue))
+    }
+
+    pub fn on_trade(&self, transaction: &Transaction) -> ConsolidatedUpdate {
+        let venue = transaction.venue.clone().unwrap_or_default();
+        let ticker = {
+            let mut last_prices = self.last_prices.lock().expect("Consolidator lock poisoned");
+            last_prices.insert(venue, (transaction.price, transaction.timestamp));
+            self.compute(&last_prices, transaction.timestamp)
+        };
+
+        // Wallets stay with the original trade so positions aren't counted twice.
+        let mut merged = transaction.clone().with_wallets(None, None);
+        merged.venue = Some(CONSOLIDATED_VENUE.to_string());
+
+        let index = ticker.price.map(|price| {
+            let mut tick = merged.clone();
+            tick.symbol = self.index_symbol.clone();
+            tick.venue = Some(CONSOLIDATED_VENUE.to_string());
+            tick.price = price;
+            (tick, ticker)
+        });
+
+        ConsolidatedUpdate { merged, index }
+    }
+
+    /// The index as of `now`, with venues gone quiet since the last trade marked stale.
+    pub fn ticker(&self, now: DateTime<Utc>) -> IndexTicker {
+        let last_prices = self.last_prices.lock().expect("Consolidator lock poisoned");
+        self.compute(&last_prices, now)
+    }
+
+    fn compute(&self, last_prices: &BTreeMap<String, (Decimal, DateTime<Utc>)>, now: DateTime<Utc>) -> IndexTicker {
+        let stale_after = chrono::Duration::seconds(self.config.stale_after_secs as i64);
+        let is_fresh = |at: DateTime<Utc>| now - at <= stale_after;
+
+        let fresh: Vec<Decimal> = last_prices
+            .values()
+            .filter(|(_, at)| is_fresh(*at))
+            .map(|(price, _)| *price)
+            .collect();
+        let reference = median(fresh.clone());
+        let within_band = |price: Decimal| match reference {
+            Some(reference) if !reference.is_zero() => {
+                ((price - reference) / reference).abs() <= self.max_deviation
+            }
+            _ => true,
+        };
+
+        let mut constituents: Vec<ConstituentPrice> = last_prices
+            .iter()
+            .map(|(venue, (price, at))| ConstituentPrice {
+                venue: venue.clone(),
+                price: *price,
+                last_trade_at: *at,
+                status: if !is_fresh(*at) {
+                    ConstituentStatus::Stale
+                } else if within_band(*price) {
+                    ConstituentStatus::Included
+                } else {
+                    ConstituentStatus::Outlier
+                },
+            })
+            .collect();
+
+        // When the venues disagree so much that nobody is within the band (e.g. two
+        // venues far apart), fall back to the plain median rather than publishing nothing.
+        if !constituents.iter().any(|c| c.status == ConstituentStatus::Included) {
+            for constituent in constituents.iter_mut() {
+                if constituent.status == ConstituentStatus::Outlier {
+                    constituent.status = ConstituentStatus::Included;
+                }
+            }
+        }
+        let price = median(
+            constituents
+                .iter()
+                .filter(|c| c.status == ConstituentStatus::Included)
+                .map(|c| c.price)
+                .collect(),
+        );
+
+        IndexTicker {
+            symbol: self.index_symbol.clone(),
+            underlying: self.config.symbol.clone(),
+            price,
+            constituents,
+            timestamp: now,
+        }
+    }
+}
+
+fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / Decimal::TWO
+    } else {
+        values[mid]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn trade(venue: &str, price: i64, at: DateTime<Utc>) -> Transaction {
+        Transaction::new_at(Uuid::new_v4(), "DOGE".to_string(), Decimal::new(price, 0), Decimal::ONE, TradeSide::Buy, at)
+            .with_venue(venue)
+    }
+
+    #[test]
+    fn test_index_rejects_outliers_and_stale_venues() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let consolidator = Consolidator::new(ConsolidatedConfig {
+            symbol: "DOGE".to_string(),
+            venues: Vec::new(),
+            index_symbol: None,
+            stale_after_secs: 30,
+            max_deviation: 0.05,
+        });
+
+        assert!(!consolidator.matches(&trade(CONSOLIDATED_VENUE, 100, start)));
+        consolidator.on_trade(&trade("alpha", 100, start));
+        consolidator.on_trade(&trade("beta", 102, start));
+        let update = consolidator.on_trade(&trade("gamma", 150, start));
+
+        assert_eq!(update.merged.venue.as_deref(), Some(CONSOLIDATED_VENUE));
+        let (tick, ticker) = update.index.unwrap();
+        assert_eq!(tick.symbol, "DOGE.IDX");
+        // The median of all three is 102; gamma is 47% away and dropped.
+        assert_eq!(ticker.price, Some(Decimal::new(101, 0)));
+        assert_eq!(tick.price, Decimal::new(101, 0));
+        assert_eq!(ticker.constituents[2].status, ConstituentStatus::Outlier);
+
+        // A minute later only beta has traded again.
+        let later = start + chrono::Duration::seconds(60);
+        let (_, ticker) = consolidator.on_trade(&trade("beta", 103, later)).index.unwrap();
+        assert_eq!(ticker.price, Some(Decimal::new(103, 0)));
+        assert_eq!(ticker.constituents[0].status, ConstituentStatus::Stale);
+        assert_eq!(ticker.constituents[2].status, ConstituentStatus::Stale);
+
+        let much_later = later + chrono::Duration::seconds(60);
+        assert_eq!(consolidator.ticker(much_later).price, None);
+    }
+}
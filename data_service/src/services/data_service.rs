@@ -1,32 +1,286 @@
-use crate::models::{KLine, KLineInterval, Transaction};
-use anyhow::{Context, Result};
+use super::alerts::AlertEngine;
+use super::bars::{BarAggregator, BarConfig};
+use super::clock::{Clock, SystemClock};
+use super::consolidated::{ConsolidatedConfig, Consolidator};
+use super::footprint::{FootprintConfig, FootprintTracker};
+use super::patterns::{PatternConfig, PatternDetector};
+use super::positions::{PositionTracker, WalletActivity};
+use super::source::SourceRegistry;
+use super::stats::{compute_stats, StatsConfig, StatsTracker};
+use super::synthetic::{SyntheticConfig, SyntheticSymbol};
+use super::volume_profile::{build_profile, ProfileRequest, TradePrint};
+use super::webhooks::WebhookDispatcher;
+use super::whales::{WhaleConfig, WhaleDetector};
+use crate::models::{
+    Alert, AlertRule, Bar, BarType, CandlePattern, FootprintCandle, IndexTicker, KLine,
+    KLineInterval, MarketEvent, NewAlertRule, PatternEvent, Position, ReturnStats, SymbolMetadata,
+    TradeSide, TraderMetrics, Transaction, VenueInfo, VolumeProfile, WhaleTrade, DEFAULT_VENUE,
+};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use dashmap::DashMap;
-use std::sync::Arc;
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
 use tokio::sync::broadcast;
 use tracing::info;
-use tokio::runtime::Runtime;
+use uuid::Uuid;
 
 const MAX_HISTORY: usize = 1000;
 const BROADCAST_CHANNEL_SIZE: usize = 1000;
+/// Trades kept per venue and symbol for volume profiles.
+const MAX_TRADE_HISTORY: usize = 100_000;
+
+/// Candle series key: venue, symbol and interval.
+type KLineKey = (String, String, KLineInterval);
+
+/// Recent trades of one venue and symbol, oldest first.
+#[derive(Default)]
+struct TradeTape {
+    prints: VecDeque<TradePrint>,
+    /// Whether older trades have been dropped to stay under `MAX_TRADE_HISTORY`.
+    truncated: bool,
+}
+
+/// Wallet metrics of the open candle, with the wallets behind the unique counts.
+struct CandleTraders {
+    metrics: TraderMetrics,
+    buyers: HashSet<String>,
+    sellers: HashSet<String>,
+}
+
+impl CandleTraders {
+    fn new(key: &KLineKey, kline: &KLine) -> Self {
+        Self {
+            metrics: TraderMetrics {
+                venue: key.0.clone(),
+                symbol: key.1.clone(),
+                interval: key.2,
+                open_time: kline.open_time,
+                close_time: kline.close_time,
+                unique_buyers: 0,
+                unique_sellers: 0,
+                new_wallets: 0,
+                holders: 0,
+                is_closed: false,
+            },
+            buyers: HashSet::new(),
+            sellers: HashSet::new(),
+        }
+    }
+}
 
 pub struct DataService {
-    klines: Arc<DashMap<(String, KLineInterval), Vec<KLine>>>,
-    current_klines: Arc<DashMap<(String, KLineInterval), KLine>>,
+    klines: Arc<DashMap<KLineKey, Vec<KLine>>>,
+    current_klines: Arc<DashMap<KLineKey, KLine>>,
+    trader_metrics: DashMap<KLineKey, Vec<TraderMetrics>>,
+    trades: DashMap<(String, String), TradeTape>,
+    current_traders: DashMap<KLineKey, CandleTraders>,
+    /// Symbols seen on each venue.
+    venues: DashMap<String, BTreeSet<String>>,
+    metadata: DashMap<String, SymbolMetadata>,
+    positions: PositionTracker,
+    whales: WhaleDetector,
+    footprints: FootprintTracker,
+    bars: BarAggregator,
+    patterns: PatternDetector,
+    stats: StatsTracker,
+    alerts: AlertEngine,
+    default_venue: String,
     tx: broadcast::Sender<KLine>,
     transaction_tx: broadcast::Sender<Transaction>,
+    market_event_tx: broadcast::Sender<MarketEvent>,
+    ticker_tx: broadcast::Sender<IndexTicker>,
+    trader_metrics_tx: broadcast::Sender<TraderMetrics>,
+    whale_tx: broadcast::Sender<WhaleTrade>,
+    footprint_tx: broadcast::Sender<FootprintCandle>,
+    bar_tx: broadcast::Sender<Bar>,
+    pattern_tx: broadcast::Sender<PatternEvent>,
+    alert_tx: broadcast::Sender<Alert>,
+    consolidators: Vec<Consolidator>,
+    synthetics: Vec<SyntheticSymbol>,
+    clock: Arc<dyn Clock>,
+    sources: SourceRegistry,
+    webhooks: OnceLock<Arc<WebhookDispatcher>>,
+}
+
+impl Default for DataService {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DataService {
     pub fn new() -> Self {
         let (tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
         let (transaction_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (market_event_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (ticker_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (trader_metrics_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (whale_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (footprint_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (bar_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (pattern_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
+        let (alert_tx, _) = broadcast::channel(BROADCAST_CHANNEL_SIZE);
         Self {
             klines: Arc::new(DashMap::new()),
             current_klines: Arc::new(DashMap::new()),
+            trader_metrics: DashMap::new(),
+            trades: DashMap::new(),
+            current_traders: DashMap::new(),
+            venues: DashMap::new(),
+            metadata: DashMap::new(),
+            positions: PositionTracker::default(),
+            whales: WhaleDetector::default(),
+            footprints: FootprintTracker::default(),
+            bars: BarAggregator::default(),
+            patterns: PatternDetector::default(),
+            stats: StatsTracker::new(StatsConfig::default()),
+            alerts: AlertEngine::default(),
+            default_venue: DEFAULT_VENUE.to_string(),
             tx,
             transaction_tx,
+            market_event_tx,
+            ticker_tx,
+            trader_metrics_tx,
+            whale_tx,
+            footprint_tx,
+            bar_tx,
+            pattern_tx,
+            alert_tx,
+            consolidators: Vec::new(),
+            synthetics: Vec::new(),
+            clock: Arc::new(SystemClock),
+            sources: SourceRegistry::default(),
+            webhooks: OnceLock::new(),
+        }
+    }
+
+    /// Runs the service on a different clock, e.g. a `SimulatedClock` for backtests.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Books trades without a venue, and serves requests without one, from `venue`.
+    pub fn with_default_venue(mut self, venue: impl Into<String>) -> Self {
+        self.default_venue = venue.into();
+        self
+    }
+
+    /// Consolidates symbols across venues, publishing cross-venue candles and a price
+    /// index pseudo-symbol on the `consolidated` venue.
+    pub fn with_consolidated(mut self, configs: Vec<ConsolidatedConfig>) -> Self {
+        self.consolidators.extend(configs.into_iter().map(Consolidator::new));
+        self
+    }
+
+    /// Derives synthetic symbols from the product or ratio of two others. Derived trades
+    /// go through the normal trade pipeline, so synthetic symbols are served like any other.
+    pub fn with_synthetics(mut self, configs: Vec<SyntheticConfig>) -> Self {
+        self.synthetics.extend(configs.into_iter().map(SyntheticSymbol::new));
+        self
+    }
+
+    /// Flags trades above per-symbol value thresholds as whale trades.
+    pub fn with_whales(mut self, configs: Vec<WhaleConfig>) -> Self {
+        self.whales = WhaleDetector::new(configs);
+        self
+    }
+
+    /// Builds footprint candles for the configured symbols and intervals.
+    pub fn with_footprints(mut self, configs: Vec<FootprintConfig>) -> Self {
+        self.footprints = FootprintTracker::new(configs);
+        self
+    }
+
+    /// Builds tick, volume and dollar bars for the configured symbols.
+    pub fn with_bars(mut self, configs: Vec<BarConfig>) -> Self {
+        self.bars = BarAggregator::new(configs);
+        self
+    }
+
+    /// Replaces the default candlestick pattern thresholds.
+    pub fn with_patterns(mut self, config: PatternConfig) -> Self {
+        self.patterns = PatternDetector::new(config);
+        self
+    }
+
+    /// Replaces the default windows kept up to date for `return_stats`.
+    pub fn with_stats(mut self, config: StatsConfig) -> Self {
+        self.stats = StatsTracker::new(config);
+        self
+    }
+
+    pub fn with_symbol_metadata(self, metadata: Vec<SymbolMetadata>) -> Self {
+        for entry in metadata {
+            self.set_symbol_metadata(entry);
+        }
+        self
+    }
+
+    /// Adds or replaces a symbol's metadata, e.g. after a supply change.
+    pub fn set_symbol_metadata(&self, metadata: SymbolMetadata) {
+        self.metadata.insert(metadata.symbol.clone(), metadata);
+    }
+
+    pub fn symbol_metadata(&self, symbol: &str) -> Option<SymbolMetadata> {
+        self.metadata.get(symbol).map(|entry| entry.value().clone())
+    }
+
+    pub fn all_symbol_metadata(&self) -> Vec<SymbolMetadata> {
+        let mut metadata: Vec<SymbolMetadata> = self.metadata.iter().map(|entry| entry.value().clone()).collect();
+        metadata.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        metadata
+    }
+
+    pub fn default_venue(&self) -> &str {
+        &self.default_venue
+    }
+
+    /// Every venue that has seen a trade, plus the default venue.
+    pub fn venues(&self) -> Vec<VenueInfo> {
+        let mut venues: Vec<VenueInfo> = self
+            .venues
+            .iter()
+            .map(|entry| VenueInfo {
+                venue: entry.key().clone(),
+                symbols: entry.value().iter().cloned().collect(),
+                is_default: *entry.key() == self.default_venue,
+            })
+            .collect();
+        if !venues.iter().any(|venue| venue.is_default) {
+            venues.push(VenueInfo {
+                venue: self.default_venue.clone(),
+                symbols: Vec::new(),
+                is_default: true,
+            });
         }
+        venues.sort_by(|a, b| a.venue.cmp(&b.venue));
+        venues
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// Health of the ingestion sources feeding this service.
+    pub fn sources(&self) -> &SourceRegistry {
+        &self.sources
+    }
+
+    /// Makes the webhook dispatcher reachable from the admin endpoints. Only the first
+    /// call has any effect.
+    pub fn set_webhooks(&self, dispatcher: Arc<WebhookDispatcher>) {
+        let _ = self.webhooks.set(dispatcher);
+    }
+
+    pub fn webhooks(&self) -> Option<&Arc<WebhookDispatcher>> {
+        self.webhooks.get()
     }
 
     pub fn subscribe(&self) -> broadcast::Receiver<KLine> {
@@ -41,9 +295,152 @@ impl DataService {
         rx
     }
 
+    pub fn subscribe_market_events(&self) -> broadcast::Receiver<MarketEvent> {
+        self.market_event_tx.subscribe()
+    }
+
+    pub fn subscribe_tickers(&self) -> broadcast::Receiver<IndexTicker> {
+        self.ticker_tx.subscribe()
+    }
+
+    pub fn subscribe_trader_metrics(&self) -> broadcast::Receiver<TraderMetrics> {
+        self.trader_metrics_tx.subscribe()
+    }
+
+    pub fn subscribe_whales(&self) -> broadcast::Receiver<WhaleTrade> {
+        self.whale_tx.subscribe()
+    }
+
+    /// Most recent whale trades, newest first.
+    pub fn recent_whales(&self, symbol: Option<&str>, limit: usize) -> Vec<WhaleTrade> {
+        self.whales.recent(symbol, limit)
+    }
+
+    /// Tick, volume and dollar bars; candles go to `subscribe`.
+    pub fn subscribe_bars(&self) -> broadcast::Receiver<Bar> {
+        self.bar_tx.subscribe()
+    }
+
+    pub fn bars_enabled(&self, symbol: &str, bar_type: BarType, threshold: Decimal) -> bool {
+        self.bars.is_enabled(symbol, bar_type, threshold)
+    }
+
+    /// Closed tick, volume or dollar bars, oldest first.
+    pub fn get_venue_bars(
+        &self,
+        venue: &str,
+        symbol: &str,
+        bar_type: BarType,
+        threshold: Decimal,
+        limit: usize,
+    ) -> Vec<Bar> {
+        self.bars.history(venue, symbol, bar_type, threshold, limit)
+    }
+
+    pub fn subscribe_patterns(&self) -> broadcast::Receiver<PatternEvent> {
+        self.pattern_tx.subscribe()
+    }
+
+    /// Most recent candlestick pattern matches, newest first.
+    pub fn recent_patterns(
+        &self,
+        symbol: Option<&str>,
+        interval: Option<KLineInterval>,
+        pattern: Option<CandlePattern>,
+        limit: usize,
+    ) -> Vec<PatternEvent> {
+        self.patterns.recent(symbol, interval, pattern, limit)
+    }
+
+    /// Window used by `return_stats` when none is asked for.
+    pub fn default_stats_window(&self) -> Option<usize> {
+        self.stats.default_window()
+    }
+
+    /// Volatility and return statistics over the last `window` closed candles, from the
+    /// running windows where one is kept and from the candle history otherwise.
+    pub fn return_stats(&self, venue: &str, symbol: &str, interval: KLineInterval, window: usize) -> Option<ReturnStats> {
+        if self.stats.tracks(window) {
+            return self.stats.get(venue, symbol, interval, window);
+        }
+        compute_stats(&self.get_venue_klines(venue, symbol, interval, window), window)
+    }
+
+    pub fn subscribe_footprints(&self) -> broadcast::Receiver<FootprintCandle> {
+        self.footprint_tx.subscribe()
+    }
+
+    pub fn footprints_enabled(&self, symbol: &str, interval: KLineInterval) -> bool {
+        self.footprints.is_enabled(symbol, interval)
+    }
+
+    /// Closed footprint candles, oldest first.
+    pub fn get_venue_footprints(&self, venue: &str, symbol: &str, interval: KLineInterval, limit: usize) -> Vec<FootprintCandle> {
+        self.footprints.history(venue, symbol, interval, limit)
+    }
+
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<Alert> {
+        self.alert_tx.subscribe()
+    }
+
+    /// Registers an alert rule, booking it to the default venue if it names none. The
+    /// symbol must have traded or be configured.
+    pub fn add_alert_rule(&self, rule: NewAlertRule) -> Result<AlertRule> {
+        rule.condition.validate()?;
+        if !self.is_known_symbol(&rule.symbol) {
+            bail!("Unknown symbol {}", rule.symbol);
+        }
+        let rule = AlertRule {
+            id: Uuid::new_v4(),
+            venue: rule.venue.unwrap_or_else(|| self.default_venue.clone()),
+            symbol: rule.symbol,
+            condition: rule.condition,
+            created_at: self.now(),
+        };
+        self.alerts.add(rule.clone())?;
+        Ok(rule)
+    }
+
+    fn is_known_symbol(&self, symbol: &str) -> bool {
+        self.metadata.contains_key(symbol)
+            || self.venues.iter().any(|entry| entry.value().contains(symbol))
+            || self.synthetics.iter().any(|synthetic| synthetic.symbol() == symbol)
+            || self.consolidators.iter().any(|consolidator| consolidator.index_symbol() == symbol)
+    }
+
+    pub fn alert_rules(&self, symbol: Option<&str>) -> Vec<AlertRule> {
+        self.alerts.list(symbol)
+    }
+
+    pub fn remove_alert_rule(&self, id: Uuid) -> bool {
+        self.alerts.remove(id)
+    }
+
+    /// Every symbol `wallet` has traded, with its balance and PnL.
+    pub fn wallet_positions(&self, wallet: &str) -> Vec<Position> {
+        self.positions.wallet_positions(wallet)
+    }
+
+    pub fn top_holders(&self, symbol: &str, limit: usize) -> Vec<Position> {
+        self.positions.top_holders(symbol, limit)
+    }
+
+    /// Current ticker of an index pseudo-symbol.
+    pub fn ticker(&self, index_symbol: &str) -> Option<IndexTicker> {
+        self.consolidators
+            .iter()
+            .find(|consolidator| consolidator.index_symbol() == index_symbol)
+            .map(|consolidator| consolidator.ticker(self.now()))
+    }
+
+    /// Candles for `symbol` on the default venue.
     pub fn get_klines(&self, symbol: &str, interval: KLineInterval, limit: usize) -> Vec<KLine> {
+        self.get_venue_klines(&self.default_venue, symbol, interval, limit)
+    }
+
+    pub fn get_venue_klines(&self, venue: &str, symbol: &str, interval: KLineInterval, limit: usize) -> Vec<KLine> {
         self.klines
-            .get(&(symbol.to_string(), interval))
+            .get(&(venue.to_string(), symbol.to_string(), interval))
             .map(|klines| {
                 let start = if klines.len() > limit {
                     klines.len() - limit
@@ -55,11 +452,58 @@ impl DataService {
             .unwrap_or_default()
     }
 
+    /// Closed wallet metrics matching the candles of `get_venue_klines`.
+    pub fn get_venue_trader_metrics(
+        &self,
+        venue: &str,
+        symbol: &str,
+        interval: KLineInterval,
+        limit: usize,
+    ) -> Vec<TraderMetrics> {
+        self.trader_metrics
+            .get(&(venue.to_string(), symbol.to_string(), interval))
+            .map(|metrics| metrics[metrics.len().saturating_sub(limit)..].to_vec())
+            .unwrap_or_default()
+    }
+
     pub fn process_transaction(&self, transaction: &Transaction) -> Result<()> {
-        // Broadcast the transaction first
-        self.transaction_tx.send(transaction.clone())
-            .context("Failed to broadcast transaction")?;
+        let mut transaction = transaction.clone();
+        self.assign_venue(&mut transaction);
+
+        // Broadcast the transaction first. Having no subscribers is not an error:
+        // candles must keep building while nobody is connected.
+        let _ = self.transaction_tx.send(transaction.clone());
+        self.record_trade(&transaction);
+        let activity = self.positions.record(&transaction);
+        if let Some(whale) = self.whales.on_trade(&transaction) {
+            let _ = self.whale_tx.send(whale);
+        }
+        for alert in self.alerts.on_trade(&transaction) {
+            let _ = self.alert_tx.send(alert);
+        }
+
+        self.update_klines(&transaction, activity)?;
+        let venue = transaction.venue.as_deref().unwrap_or(&self.default_venue);
+        for bar in self.bars.on_trade(venue, &transaction) {
+            let _ = self.bar_tx.send(bar);
+        }
+        self.derive_trades(&transaction)
+    }
+
+    /// Books a consolidated trade or index tick. It repeats trades already counted on their
+    /// own venue, so it only feeds alerts, candles and further derived symbols, not the
+    /// trade stream, tape, positions, whales or bars.
+    fn process_consolidated_transaction(&self, transaction: &Transaction) -> Result<()> {
+        let mut transaction = transaction.clone();
+        self.assign_venue(&mut transaction);
+        for alert in self.alerts.on_trade(&transaction) {
+            let _ = self.alert_tx.send(alert);
+        }
+        self.update_klines(&transaction, WalletActivity::default())?;
+        self.derive_trades(&transaction)
+    }
 
+    fn update_klines(&self, transaction: &Transaction, activity: WalletActivity) -> Result<()> {
         for interval in [
             KLineInterval::OneSecond,
             KLineInterval::OneMinute,
@@ -67,20 +511,159 @@ impl DataService {
             KLineInterval::FifteenMinutes,
             KLineInterval::OneHour,
         ] {
-            self.update_kline(transaction, interval)
+            self.update_kline(transaction, interval, activity)
                 .with_context(|| format!("Failed to update kline for interval {:?}", interval))?;
         }
         Ok(())
     }
 
-    fn update_kline(&self, transaction: &Transaction, interval: KLineInterval) -> Result<()> {
-        let key = (transaction.symbol.clone(), interval);
+    /// Feeds `transaction` to the consolidators and synthetic symbols and books what they derive.
+    fn derive_trades(&self, transaction: &Transaction) -> Result<()> {
+        // Consolidated output is booked to its own venue, so this never recurses further.
+        for consolidator in self.consolidators.iter().filter(|c| c.matches(transaction)) {
+            let update = consolidator.on_trade(transaction);
+            self.process_consolidated_transaction(&update.merged)?;
+            if let Some((tick, ticker)) = update.index {
+                self.process_consolidated_transaction(&tick)?;
+                let _ = self.ticker_tx.send(ticker);
+            }
+        }
+
+        // A synthetic symbol trades nowhere else, so its trades are booked like any other,
+        // without wallets. Config validation rules out synthetic symbols that feed into
+        // themselves.
+        for synthetic in &self.synthetics {
+            if let Some(derived) = synthetic.on_trade(transaction) {
+                self.process_transaction(&derived)
+                    .with_context(|| format!("Failed to process synthetic symbol {}", synthetic.symbol()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Trades kept for `venue` and `symbol`, oldest first; the last `MAX_TRADE_HISTORY` at most.
+    pub fn recent_trades(&self, venue: &str, symbol: &str) -> Vec<TradePrint> {
+        self.trades
+            .get(&(venue.to_string(), symbol.to_string()))
+            .map(|tape| tape.prints.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Volume by price over `[from, to)`, from stored trades and, for the part of the
+    /// range older than those, from the finest candles reaching back far enough.
+    pub fn volume_profile(
+        &self,
+        venue: &str,
+        symbol: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        buckets: usize,
+        value_area: Decimal,
+    ) -> VolumeProfile {
+        let (mut trades, covered_from) = match self.trades.get(&(venue.to_string(), symbol.to_string())) {
+            Some(tape) => {
+                let covered_from = tape.prints.front().filter(|_| tape.truncated).map(|print| print.timestamp);
+                let trades: Vec<TradePrint> = tape
+                    .prints
+                    .iter()
+                    .filter(|print| print.timestamp >= from && print.timestamp < to)
+                    .copied()
+                    .collect();
+                (trades, covered_from)
+            }
+            None => (Vec::new(), None),
+        };
+
+        let mut candles = Vec::new();
+        if let Some(covered_from) = covered_from.filter(|covered_from| from < *covered_from) {
+            let intervals = [
+                KLineInterval::OneSecond,
+                KLineInterval::OneMinute,
+                KLineInterval::FiveMinutes,
+                KLineInterval::FifteenMinutes,
+                KLineInterval::OneHour,
+            ];
+            let histories: Vec<Vec<KLine>> = intervals
+                .into_iter()
+                .filter_map(|interval| {
+                    self.klines
+                        .get(&(venue.to_string(), symbol.to_string(), interval))
+                        .map(|klines| klines.clone())
+                })
+                .filter(|klines| !klines.is_empty())
+                .collect();
+            let history = histories
+                .iter()
+                .find(|klines| klines[0].open_time <= from)
+                .or_else(|| histories.iter().min_by_key(|klines| klines[0].open_time));
+            if let Some(history) = history {
+                candles = history
+                    .iter()
+                    .filter(|kline| kline.open_time >= from && kline.close_time <= covered_from.min(to))
+                    .cloned()
+                    .collect();
+            }
+            // Trades older than the last candle would be counted twice.
+            let trades_from = candles.last().map_or(covered_from, |kline| kline.close_time.max(covered_from));
+            trades.retain(|print| print.timestamp >= trades_from);
+        }
+
+        let request = ProfileRequest {
+            venue,
+            symbol,
+            from,
+            to,
+            buckets,
+            value_area,
+        };
+        build_profile(&request, &trades, &candles)
+    }
+
+    /// Handles an event from a pool-style source. Trades go through the normal
+    /// transaction path; every event is also published to market event subscribers.
+    pub fn process_market_event(&self, event: &MarketEvent) -> Result<()> {
+        let mut event = event.clone();
+        if let MarketEvent::Trade(transaction) = &mut event {
+            self.assign_venue(transaction);
+            self.process_transaction(transaction)?;
+        }
+        // Nobody listening for market events is not an error.
+        let _ = self.market_event_tx.send(event);
+        Ok(())
+    }
+
+    /// Adds a trade to its series' tape, dropping the oldest beyond `MAX_TRADE_HISTORY`.
+    /// Expects the venue to be assigned.
+    fn record_trade(&self, transaction: &Transaction) {
+        let key = (transaction.venue.clone().unwrap_or_default(), transaction.symbol.clone());
+        let mut tape = self.trades.entry(key).or_default();
+        tape.prints.push_back(TradePrint::from(transaction));
+        if tape.prints.len() > MAX_TRADE_HISTORY {
+            tape.prints.pop_front();
+            tape.truncated = true;
+        }
+    }
+
+    /// Books a trade without a venue to the default one and records the venue's symbol.
+    fn assign_venue(&self, transaction: &mut Transaction) {
+        let venue = transaction.venue.get_or_insert_with(|| self.default_venue.clone());
+        let mut symbols = self.venues.entry(venue.clone()).or_default();
+        if !symbols.contains(&transaction.symbol) {
+            symbols.insert(transaction.symbol.clone());
+        }
+    }
+
+    /// Expects the venue to have been assigned already.
+    fn update_kline(&self, transaction: &Transaction, interval: KLineInterval, activity: WalletActivity) -> Result<()> {
+        let venue = transaction.venue.clone().unwrap_or_else(|| self.default_venue.clone());
+        let key = (venue.clone(), transaction.symbol.clone(), interval);
         let timestamp = transaction.timestamp;
         
         // Get or create current KLine
         let mut current_kline = self.current_klines.entry(key.clone()).or_insert_with(|| {
             let open_time = self.calculate_kline_start(timestamp, interval);
             KLine::new(
+                venue.clone(),
                 transaction.symbol.clone(),
                 interval,
                 open_time,
@@ -92,6 +675,9 @@ impl DataService {
         if timestamp >= current_kline.close_time {
             // Close current KLine
             current_kline.close();
+            self.evaluate_candle_alerts(&key, &current_kline, timestamp);
+            self.detect_patterns(&key, &current_kline);
+            self.stats.on_close(&current_kline);
             let closed_kline = current_kline.clone();
 
             // Store in history
@@ -109,29 +695,98 @@ impl DataService {
             }
 
             // Broadcast the closed KLine
-            self.tx.send(closed_kline)
-                .context("Failed to broadcast closed KLine")?;
+            let _ = self.tx.send(closed_kline);
 
-            // Create new KLine
-            let new_kline = KLine::new(
+            // Create new KLine, carrying the cumulative delta forward
+            let mut new_kline = KLine::new(
+                venue.clone(),
                 transaction.symbol.clone(),
                 interval,
                 self.calculate_kline_start(timestamp, interval),
                 transaction.price,
             );
+            new_kline.ohlcv.cumulative_delta = current_kline.ohlcv.cumulative_delta;
             *current_kline = new_kline;
         }
 
         // Update the current KLine
-        current_kline.update(transaction.price, transaction.volume);
+        current_kline.update(transaction.price, transaction.volume, transaction.side);
 
         // Broadcast the updated current KLine
-        self.tx.send(current_kline.clone())
-            .context("Failed to broadcast updated KLine")?;
+        let _ = self.tx.send(current_kline.clone());
+        self.evaluate_candle_alerts(&key, &current_kline, timestamp);
+
+        self.update_trader_metrics(&key, &current_kline, transaction, activity);
+        if let Some(update) = self.footprints.on_trade(&current_kline, transaction) {
+            if let Some(closed) = update.closed {
+                let _ = self.footprint_tx.send(closed);
+            }
+            let _ = self.footprint_tx.send(update.current);
+        }
 
         Ok(())
     }
 
+    /// Runs candle alert rules on `kline`, before it joins the history if it is closed.
+    fn evaluate_candle_alerts(&self, key: &KLineKey, kline: &KLine, at: DateTime<Utc>) {
+        if !self.alerts.watches_candles(&key.0, &key.1, key.2) {
+            return;
+        }
+        let history = self.klines.get(key);
+        let previous = history.as_deref().map(Vec::as_slice).unwrap_or_default();
+        for alert in self.alerts.on_kline(kline, previous, at) {
+            let _ = self.alert_tx.send(alert);
+        }
+    }
+
+    /// Publishes the candlestick patterns `kline` completes, before it joins the history.
+    fn detect_patterns(&self, key: &KLineKey, kline: &KLine) {
+        let history = self.klines.get(key);
+        let previous = history.as_deref().map(Vec::as_slice).unwrap_or_default();
+        for event in self.patterns.on_close(kline, previous) {
+            let _ = self.pattern_tx.send(event);
+        }
+    }
+
+    /// Keeps the wallet metrics of `key` in step with its candle, which `transaction`
+    /// has just been added to.
+    fn update_trader_metrics(&self, key: &KLineKey, kline: &KLine, transaction: &Transaction, activity: WalletActivity) {
+        let mut current = self
+            .current_traders
+            .entry(key.clone())
+            .or_insert_with(|| CandleTraders::new(key, kline));
+
+        if current.metrics.open_time != kline.open_time {
+            let mut closed = current.metrics.clone();
+            closed.is_closed = true;
+            let mut history = self.trader_metrics.entry(key.clone()).or_default();
+            history.push(closed.clone());
+            if history.len() > MAX_HISTORY {
+                let len = history.len();
+                history.drain(0..len - MAX_HISTORY);
+            }
+            let _ = self.trader_metrics_tx.send(closed);
+            *current = CandleTraders::new(key, kline);
+        }
+
+        let (buyer, seller) = match transaction.side {
+            TradeSide::Buy => (&transaction.taker, &transaction.maker),
+            TradeSide::Sell => (&transaction.maker, &transaction.taker),
+        };
+        if let Some(buyer) = buyer {
+            current.buyers.insert(buyer.clone());
+        }
+        if let Some(seller) = seller {
+            current.sellers.insert(seller.clone());
+        }
+        current.metrics.unique_buyers = current.buyers.len() as u64;
+        current.metrics.unique_sellers = current.sellers.len() as u64;
+        current.metrics.new_wallets += activity.new_wallets;
+        current.metrics.holders = activity.holders;
+
+        let _ = self.trader_metrics_tx.send(current.metrics.clone());
+    }
+
     fn calculate_kline_start(&self, timestamp: DateTime<Utc>, interval: KLineInterval) -> DateTime<Utc> {
         let seconds = timestamp.timestamp();
         let interval_seconds = interval.as_seconds();
@@ -147,6 +802,7 @@ mod tests {
     use super::*;
     use crate::models::TradeSide;
     use rust_decimal::Decimal;
+    use tokio::runtime::Runtime;
 
     #[test]
     fn test_data_service() -> Result<()> {
@@ -186,11 +842,11 @@ mod tests {
             for _ in 0..5 {
                 if let Ok(kline) = kline_rx.recv().await {
                     assert_eq!(kline.symbol, symbol);
-                    assert_eq!(kline.open, Decimal::new(100, 0));
-                    assert_eq!(kline.high, Decimal::new(100, 0));
-                    assert_eq!(kline.low, Decimal::new(100, 0));
-                    assert_eq!(kline.close, Decimal::new(100, 0));
-                    assert_eq!(kline.volume, Decimal::new(1, 0));
+                    assert_eq!(kline.ohlcv.open, Decimal::new(100, 0));
+                    assert_eq!(kline.ohlcv.high, Decimal::new(100, 0));
+                    assert_eq!(kline.ohlcv.low, Decimal::new(100, 0));
+                    assert_eq!(kline.ohlcv.close, Decimal::new(100, 0));
+                    assert_eq!(kline.ohlcv.volume, Decimal::new(1, 0));
                     received_intervals.push(kline.interval);
                 }
             }
@@ -204,4 +860,254 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_venues_keep_separate_candles() -> Result<()> {
+        use chrono::TimeZone;
+        use uuid::Uuid;
+
+        let service = DataService::new().with_default_venue("alpha");
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trade = |price: i64, secs: i64| {
+            Transaction::new_at(
+                Uuid::new_v4(),
+                "DOGE".to_string(),
+                Decimal::new(price, 0),
+                Decimal::ONE,
+                TradeSide::Buy,
+                start + chrono::Duration::seconds(secs),
+            )
+        };
+
+        let mut rx = service.subscribe_transactions();
+        service.process_transaction(&trade(1, 0))?;
+        service.process_transaction(&trade(100, 0).with_venue("beta"))?;
+        service.process_transaction(&trade(2, 1).with_venue("alpha"))?;
+        service.process_transaction(&trade(200, 1).with_venue("beta"))?;
+        assert_eq!(rx.try_recv()?.venue.as_deref(), Some("alpha"));
+
+        let alpha = service.get_klines("DOGE", KLineInterval::OneSecond, 10);
+        let beta = service.get_venue_klines("beta", "DOGE", KLineInterval::OneSecond, 10);
+        assert_eq!((alpha.len(), beta.len()), (1, 1));
+        assert_eq!((alpha[0].venue.as_str(), alpha[0].ohlcv.close), ("alpha", Decimal::new(1, 0)));
+        assert_eq!((beta[0].venue.as_str(), beta[0].ohlcv.close), ("beta", Decimal::new(100, 0)));
+
+        let venues = service.venues();
+        assert_eq!(venues.len(), 2);
+        assert!(venues[0].is_default && venues[0].venue == "alpha");
+        assert_eq!(venues[1].symbols, vec!["DOGE".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_consolidated_candles_span_venues() -> Result<()> {
+        use crate::services::{ConsolidatedConfig, CONSOLIDATED_VENUE};
+        use chrono::TimeZone;
+        use uuid::Uuid;
+
+        let service = DataService::new().with_consolidated(vec![ConsolidatedConfig {
+            symbol: "DOGE".to_string(),
+            venues: Vec::new(),
+            index_symbol: None,
+            stale_after_secs: 60,
+            max_deviation: 0.05,
+        }]);
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trade = |venue: &str, price: i64, volume: i64, secs: i64| {
+            Transaction::new_at(
+                Uuid::new_v4(),
+                "DOGE".to_string(),
+                Decimal::new(price, 0),
+                Decimal::new(volume, 0),
+                TradeSide::Buy,
+                start + chrono::Duration::seconds(secs),
+            )
+            .with_venue(venue)
+        };
+
+        let mut tickers = service.subscribe_tickers();
+        let mut transactions = service.subscribe_transactions();
+        service.process_transaction(&trade("alpha", 100, 3, 0))?;
+        service.process_transaction(&trade("beta", 104, 1, 0))?;
+        service.process_transaction(&trade("alpha", 100, 1, 1))?;
+
+        let merged = service.get_venue_klines(CONSOLIDATED_VENUE, "DOGE", KLineInterval::OneSecond, 10);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].ohlcv.volume, Decimal::new(4, 0));
+        assert_eq!(merged[0].ohlcv.vwap, Decimal::new(101, 0));
+
+        let index = service.get_venue_klines(CONSOLIDATED_VENUE, "DOGE.IDX", KLineInterval::OneSecond, 10);
+        assert_eq!(index[0].ohlcv.open, Decimal::new(100, 0));
+        assert_eq!(index[0].ohlcv.close, Decimal::new(102, 0));
+        assert_eq!(tickers.try_recv()?.price, Some(Decimal::new(100, 0)));
+        assert!(service.ticker("DOGE.IDX").is_some());
+        assert!(service.ticker("DOGE").is_none());
+
+        // Derived trades only feed candles; the venue trades were already published.
+        assert_eq!(std::iter::from_fn(|| transactions.try_recv().ok()).count(), 3);
+        assert!(service.recent_trades(CONSOLIDATED_VENUE, "DOGE").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_synthetic_trades_are_booked_like_any_other() -> Result<()> {
+        use crate::services::BarConfig;
+
+        let synthetics = serde_json::from_str(
+            r#"[{ "symbol": "TOKEN-USD", "op": "product", "left": "TOKEN-SOL", "right": "SOL-USD" }]"#,
+        )?;
+        let service = DataService::new().with_synthetics(synthetics).with_bars(vec![BarConfig {
+            symbol: "TOKEN-USD".to_string(),
+            bar_type: BarType::Tick,
+            threshold: Decimal::ONE,
+        }]);
+        let trade = |symbol: &str, price: &str| {
+            Transaction::new(symbol.to_string(), price.parse().unwrap(), Decimal::TEN, TradeSide::Buy)
+                .with_wallets(Some("mm".to_string()), Some("alice".to_string()))
+        };
+
+        let mut transactions = service.subscribe_transactions();
+        service.process_transaction(&trade("SOL-USD", "150"))?;
+        service.process_transaction(&trade("TOKEN-SOL", "0.002"))?;
+
+        let published: Vec<Transaction> = std::iter::from_fn(|| transactions.try_recv().ok()).collect();
+        let derived: Vec<&Transaction> = published.iter().filter(|t| t.symbol == "TOKEN-USD").collect();
+        assert_eq!(derived.len(), 1);
+        assert_eq!((derived[0].price, derived[0].volume), ("0.3".parse()?, Decimal::TEN));
+        assert!(derived[0].maker.is_none() && derived[0].taker.is_none());
+        assert_eq!(service.recent_trades(DEFAULT_VENUE, "TOKEN-USD").len(), 1);
+        assert_eq!(service.get_venue_bars(DEFAULT_VENUE, "TOKEN-USD", BarType::Tick, Decimal::ONE, 10).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trader_metrics_follow_candles() -> Result<()> {
+        use chrono::TimeZone;
+        use uuid::Uuid;
+
+        let service = DataService::new();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let trade = |side: TradeSide, maker: &str, taker: &str, secs: i64| {
+            Transaction::new_at(
+                Uuid::new_v4(),
+                "DOGE".to_string(),
+                Decimal::ONE,
+                Decimal::new(10, 0),
+                side,
+                start + chrono::Duration::seconds(secs),
+            )
+            .with_wallets(Some(maker.to_string()), Some(taker.to_string()))
+        };
+
+        let mut rx = service.subscribe_trader_metrics();
+        service.process_transaction(&trade(TradeSide::Buy, "mm", "alice", 0))?;
+        service.process_transaction(&trade(TradeSide::Buy, "mm", "bob", 10))?;
+        service.process_transaction(&trade(TradeSide::Sell, "alice", "bob", 20))?;
+        service.process_transaction(&trade(TradeSide::Buy, "mm", "alice", 60))?;
+        assert_eq!(rx.try_recv()?.unique_buyers, 1);
+
+        let metrics = service.get_venue_trader_metrics(DEFAULT_VENUE, "DOGE", KLineInterval::OneMinute, 10);
+        assert_eq!(metrics.len(), 1);
+        let candle = &metrics[0];
+        // Alice and bob bought from mm, then bob sold his 10 to alice.
+        assert_eq!((candle.unique_buyers, candle.unique_sellers), (2, 2));
+        assert_eq!((candle.new_wallets, candle.holders), (3, 1));
+        assert!(candle.is_closed);
+        assert_eq!(candle.open_time, service.get_klines("DOGE", KLineInterval::OneMinute, 10)[0].open_time);
+        Ok(())
+    }
+
+    #[test]
+    fn test_alert_rules_need_a_known_symbol() -> Result<()> {
+        use crate::models::{AlertCondition, CrossDirection, NewAlertRule};
+
+        let service = DataService::new();
+        let rule = |symbol: &str| NewAlertRule {
+            symbol: symbol.to_string(),
+            venue: None,
+            condition: AlertCondition::PriceCross {
+                level: Decimal::ONE,
+                direction: CrossDirection::Any,
+            },
+        };
+        assert!(service.add_alert_rule(rule("DOGE")).is_err());
+
+        service.process_transaction(&Transaction::new("DOGE".to_string(), Decimal::ONE, Decimal::ONE, TradeSide::Buy))?;
+        assert_eq!(service.add_alert_rule(rule("DOGE"))?.venue, DEFAULT_VENUE);
+        assert_eq!(service.alert_rules(Some("DOGE")).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_rules_fire_on_derived_symbols() -> Result<()> {
+        use crate::models::{AlertCondition, CrossDirection, NewAlertRule};
+        use crate::services::{ConsolidatedConfig, CONSOLIDATED_VENUE};
+
+        let synthetics = serde_json::from_str(
+            r#"[{ "symbol": "TOKEN-USD", "op": "product", "left": "TOKEN-SOL", "right": "SOL-USD" }]"#,
+        )?;
+        let service = DataService::new()
+            .with_synthetics(synthetics)
+            .with_consolidated(vec![ConsolidatedConfig {
+                symbol: "TOKEN-SOL".to_string(),
+                venues: Vec::new(),
+                index_symbol: None,
+                stale_after_secs: 60,
+                max_deviation: 0.05,
+            }]);
+        let cross = |symbol: &str, venue: Option<&str>, level: &str| NewAlertRule {
+            symbol: symbol.to_string(),
+            venue: venue.map(str::to_string),
+            condition: AlertCondition::PriceCross {
+                level: level.parse().unwrap(),
+                direction: CrossDirection::Up,
+            },
+        };
+        let synthetic_rule = service.add_alert_rule(cross("TOKEN-USD", None, "0.5"))?;
+        let index_rule = service.add_alert_rule(cross("TOKEN-SOL.IDX", Some(CONSOLIDATED_VENUE), "0.003"))?;
+
+        let mut alerts = service.subscribe_alerts();
+        let trade = |symbol: &str, price: &str| {
+            Transaction::new(symbol.to_string(), price.parse().unwrap(), Decimal::ONE, TradeSide::Buy)
+        };
+        service.process_transaction(&trade("SOL-USD", "150"))?;
+        service.process_transaction(&trade("TOKEN-SOL", "0.002"))?;
+        service.process_transaction(&trade("TOKEN-SOL", "0.004"))?;
+
+        let fired: Vec<Uuid> = std::iter::from_fn(|| alerts.try_recv().ok()).map(|alert| alert.rule_id).collect();
+        assert!(fired.contains(&synthetic_rule.id));
+        assert!(fired.contains(&index_rule.id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seeded_day_produces_identical_candles() -> Result<()> {
+        use crate::services::{MockDataGenerator, SimulatedClock};
+        use chrono::TimeZone;
+        use futures::StreamExt;
+
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let run = || async {
+            let clock = Arc::new(SimulatedClock::new(start));
+            let service = DataService::new().with_clock(clock.clone());
+            let mut stream = MockDataGenerator::default()
+                .with_seed(7)
+                .with_clock(clock)
+                .generate_transaction_stream("DOGE".to_string(), 5000)
+                .take(24 * 60 * 60 / 5);
+            while let Some(transaction) = stream.next().await {
+                service.process_transaction(&transaction?)?;
+            }
+            assert_eq!(service.now(), start + chrono::Duration::days(1));
+            Ok::<_, anyhow::Error>(service.get_klines("DOGE", KLineInterval::OneHour, 100))
+        };
+
+        let first = run().await?;
+        let second = run().await?;
+
+        // The last trade lands exactly on midnight and closes the 24th hourly candle.
+        assert_eq!(first.len(), 24);
+        assert_eq!(serde_json::to_string(&first)?, serde_json::to_string(&second)?);
+        Ok(())
+    }
+}
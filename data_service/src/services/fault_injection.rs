@@ -0,0 +1,299 @@
+use super::clock::{Clock, SystemClock};
+use super::mock_data::{new_rng, random_id};
+use super::supervisor::{MarketEventStream, TransactionStream};
+use crate::models::{MarketEvent, Transaction};
+use anyhow::{bail, Result};
+use async_stream::stream;
+use futures::StreamExt;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Per-trade probabilities of each kind of bad upstream data. Everything is off by default.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FaultConfig {
+    /// Emit the same trade (same id) a second time.
+    pub duplicate_probability: f64,
+    /// Move the timestamp back by up to `max_skew_ms`.
+    pub out_of_order_probability: f64,
+    /// Move the timestamp forward by up to `max_skew_ms`.
+    pub future_timestamp_probability: f64,
+    pub max_skew_ms: u64,
+    /// Multiply or divide the price by `price_spike_factor`.
+    pub price_spike_probability: f64,
+    pub price_spike_factor: f64,
+    pub zero_volume_probability: f64,
+    /// Follow the trade with `burst_size` copies under fresh ids, back to back.
+    pub burst_probability: f64,
+    pub burst_size: usize,
+    /// Go silent for `gap_ms` before delivering the trade.
+    pub gap_probability: f64,
+    pub gap_ms: u64,
+    pub seed: Option<u64>,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_probability: 0.0,
+            out_of_order_probability: 0.0,
+            future_timestamp_probability: 0.0,
+            max_skew_ms: 5_000,
+            price_spike_probability: 0.0,
+            price_spike_factor: 10.0,
+            zero_volume_probability: 0.0,
+            burst_probability: 0.0,
+            burst_size: 20,
+            gap_probability: 0.0,
+            gap_ms: 5_000,
+            seed: None,
+        }
+    }
+}
+
+impl FaultConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, probability) in [
+            ("duplicate_probability", self.duplicate_probability),
+            ("out_of_order_probability", self.out_of_order_probability),
+            ("future_timestamp_probability", self.future_timestamp_probability),
+            ("price_spike_probability", self.price_spike_probability),
+            ("zero_volume_probability", self.zero_volume_probability),
+            ("burst_probability", self.burst_probability),
+            ("gap_probability", self.gap_probability),
+        ] {
+            if !(0.0..=1.0).contains(&probability) {
+                bail!("Fault {} must be in [0, 1]", name);
+            }
+        }
+        if !self.price_spike_factor.is_finite() || self.price_spike_factor <= 0.0 {
+            bail!("Fault price_spike_factor must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Number of faults of each kind injected so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FaultCounts {
+    pub passed: u64,
+    pub duplicates: u64,
+    pub out_of_order: u64,
+    pub future_timestamps: u64,
+    pub price_spikes: u64,
+    pub zero_volumes: u64,
+    pub bursts: u64,
+    pub burst_trades: u64,
+    pub gaps: u64,
+}
+
+#[derive(Default)]
+struct FaultStats {
+    passed: AtomicU64,
+    duplicates: AtomicU64,
+    out_of_order: AtomicU64,
+    future_timestamps: AtomicU64,
+    price_spikes: AtomicU64,
+    zero_volumes: AtomicU64,
+    bursts: AtomicU64,
+    burst_trades: AtomicU64,
+    gaps: AtomicU64,
+}
+
+/// Wraps transaction streams and corrupts them according to a [`FaultConfig`].
+///
+/// Clones share the same RNG and counters, so one injector can wrap several streams
+/// and report a combined total.
+#[derive(Clone)]
+pub struct FaultInjector {
+    config: FaultConfig,
+    rng: Arc<Mutex<StdRng>>,
+    stats: Arc<FaultStats>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FaultInjector {
+    pub fn new(config: FaultConfig) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(new_rng(config.seed))),
+            config,
+            stats: Arc::new(FaultStats::default()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn counts(&self) -> FaultCounts {
+        let stats = &self.stats;
+        FaultCounts {
+            passed: stats.passed.load(Ordering::Relaxed),
+            duplicates: stats.duplicates.load(Ordering::Relaxed),
+            out_of_order: stats.out_of_order.load(Ordering::Relaxed),
+            future_timestamps: stats.future_timestamps.load(Ordering::Relaxed),
+            price_spikes: stats.price_spikes.load(Ordering::Relaxed),
+            zero_volumes: stats.zero_volumes.load(Ordering::Relaxed),
+            bursts: stats.bursts.load(Ordering::Relaxed),
+            burst_trades: stats.burst_trades.load(Ordering::Relaxed),
+            gaps: stats.gaps.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn wrap(&self, inner: TransactionStream) -> TransactionStream {
+        let injector = self.clone();
+        Box::pin(stream! {
+            let mut inner = inner;
+            while let Some(result) = inner.next().await {
+                match result {
+                    Ok(transaction) => {
+                        for faulty in injector.inject(transaction).await {
+                            yield Ok(faulty);
+                        }
+                    }
+                    Err(err) => yield Err(err),
+                }
+            }
+        })
+    }
+
+    /// Like [`wrap`](Self::wrap) for pool-style sources: trades are corrupted, other
+    /// events pass through untouched.
+    pub fn wrap_events(&self, inner: MarketEventStream) -> MarketEventStream {
+        let injector = self.clone();
+        Box::pin(stream! {
+            let mut inner = inner;
+            while let Some(result) = inner.next().await {
+                match result {
+                    Ok(MarketEvent::Trade(transaction)) => {
+                        for faulty in injector.inject(transaction).await {
+                            yield Ok(MarketEvent::Trade(faulty));
+                        }
+                    }
+                    other => yield other,
+                }
+            }
+        })
+    }
+
+    /// Applies every configured fault to one trade and returns what should be emitted.
+    async fn inject(&self, mut transaction: Transaction) -> Vec<Transaction> {
+        let config = &self.config;
+        let stats = &self.stats;
+
+        let (gap, output) = {
+            let mut rng = self.rng.lock().expect("Fault injector lock poisoned");
+            let skew = |rng: &mut StdRng| {
+                chrono::Duration::milliseconds(rng.random_range(1..=config.max_skew_ms.max(1)) as i64)
+            };
+
+            if rng.random_bool(config.out_of_order_probability) {
+                transaction.timestamp -= skew(&mut rng);
+                stats.out_of_order.fetch_add(1, Ordering::Relaxed);
+            } else if rng.random_bool(config.future_timestamp_probability) {
+                transaction.timestamp += skew(&mut rng);
+                stats.future_timestamps.fetch_add(1, Ordering::Relaxed);
+            }
+
+            if rng.random_bool(config.price_spike_probability) {
+                if let Some(factor) = Decimal::from_f64(config.price_spike_factor).filter(|f| !f.is_zero()) {
+                    if rng.random_bool(0.5) {
+                        transaction.price *= factor;
+                    } else {
+                        transaction.price /= factor;
+                    }
+                    stats.price_spikes.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            if rng.random_bool(config.zero_volume_probability) {
+                transaction.volume = Decimal::ZERO;
+                stats.zero_volumes.fetch_add(1, Ordering::Relaxed);
+            }
+
+            let mut output = vec![transaction.clone()];
+
+            if rng.random_bool(config.duplicate_probability) {
+                output.push(transaction.clone());
+                stats.duplicates.fetch_add(1, Ordering::Relaxed);
+            }
+
+            if config.burst_size > 0 && rng.random_bool(config.burst_probability) {
+                for _ in 0..config.burst_size {
+                    let mut copy = transaction.clone();
+                    copy.id = random_id(&mut rng);
+                    output.push(copy);
+                }
+                stats.bursts.fetch_add(1, Ordering::Relaxed);
+                stats.burst_trades.fetch_add(config.burst_size as u64, Ordering::Relaxed);
+            }
+
+            (rng.random_bool(config.gap_probability), output)
+        };
+
+        if gap {
+            stats.gaps.fetch_add(1, Ordering::Relaxed);
+            self.clock.sleep(Duration::from_millis(config.gap_ms)).await;
+        }
+
+        stats.passed.fetch_add(1, Ordering::Relaxed);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{MockDataGenerator, SimulatedClock};
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn test_injected_faults_are_counted() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock = Arc::new(SimulatedClock::new(start));
+        let injector = FaultInjector::new(FaultConfig {
+            duplicate_probability: 0.1,
+            out_of_order_probability: 0.1,
+            zero_volume_probability: 0.1,
+            burst_probability: 0.01,
+            burst_size: 5,
+            gap_probability: 0.05,
+            gap_ms: 60_000,
+            seed: Some(4),
+            ..Default::default()
+        })
+        .with_clock(clock.clone());
+
+        let source: TransactionStream = Box::pin(
+            MockDataGenerator::default()
+                .with_seed(3)
+                .with_clock(clock.clone())
+                .generate_transaction_stream("DOGE".to_string(), 100)
+                .take(1_000),
+        );
+        let output: Vec<Transaction> = injector.wrap(source).map(|result| result.unwrap()).collect().await;
+
+        let counts = injector.counts();
+        assert_eq!(counts.passed, 1_000);
+        assert!(counts.duplicates > 0 && counts.out_of_order > 0 && counts.zero_volumes > 0 && counts.gaps > 0);
+        assert_eq!(output.len() as u64, counts.passed + counts.duplicates + counts.burst_trades);
+
+        let unique_ids: HashSet<_> = output.iter().map(|t| t.id).collect();
+        assert_eq!(output.len() - unique_ids.len(), counts.duplicates as usize);
+        let zero_volumes = output.iter().filter(|t| t.volume.is_zero()).count() as u64;
+        assert!(zero_volumes >= counts.zero_volumes);
+
+        // Each gap moved simulated time forward by a full minute.
+        let expected_end = start + chrono::Duration::milliseconds(100 * 1_000 + 60_000 * counts.gaps as i64);
+        assert_eq!(clock.now(), expected_end);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::models::{
+    FootprintCandle, FootprintLevel, KLine, KLineInterval, TradeSide, Transaction,
+};
+use anyhow::{bail, Result};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+/// Footprint candles kept per series; they are much larger than plain candles.
+const MAX_FOOTPRINT_HISTORY: usize = 200;
+
+type SeriesKey = (String, String, KLineInterval);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FootprintConfig {
+    pub symbol: String,
+    /// Width of a price level.
+    pub tick_size: Decimal,
+    #[serde(default = "default_intervals")]
+    pub intervals: Vec<KLineInterval>,
+}
+
+fn default_intervals() -> Vec<KLineInterval> {
+    vec![KLineInterval::OneMinute]
+}
+
+impl FootprintConfig {
+    pub fn validate_all(configs: &[FootprintConfig]) -> Result<()> {
+        let mut seen = HashSet::new();
+        for config in configs {
+            if !seen.insert(config.symbol.as_str()) {
+                bail!("Footprint for {} is configured more than once", config.symbol);
+            }
+            if config.tick_size <= Decimal::ZERO {
+                bail!("Footprint for {} needs a positive tick_size", config.symbol);
+            }
+            if config.intervals.is_empty() {
+                bail!("Footprint for {} needs at least one interval", config.symbol);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a trade did to a footprint series.
+pub struct FootprintUpdate {
+    /// The previous candle, if the trade opened a new one.
+    pub closed: Option<FootprintCandle>,
+    pub current: FootprintCandle,
+}
+
+/// Builds footprint candles for the configured symbols, on every venue.
+#[derive(Default)]
+pub struct FootprintTracker {
+    configs: HashMap<String, FootprintConfig>,
+    current: DashMap<SeriesKey, FootprintCandle>,
+    history: DashMap<SeriesKey, Vec<FootprintCandle>>,
+}
+
+impl FootprintTracker {
+    pub fn new(configs: Vec<FootprintConfig>) -> Self {
+        Self {
+            configs: configs.into_iter().map(|config| (config.symbol.clone(), config)).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_enabled(&self, symbol: &str, interval: KLineInterval) -> bool {
+        self.configs
+            .get(symbol)
+            .is_some_and(|config| config.intervals.contains(&interval))
+    }
+
+    /// Follows `kline`, which `transaction` has just been added to.
+    pub fn on_trade(&self, kline: &KLine, transaction: &Transaction) -> Option<FootprintUpdate> {
+        let config = self.configs.get(&kline.symbol)?;
+        if !config.intervals.contains(&kline.interval) {
+            return None;
+        }
+        let key = (kline.venue.clone(), kline.symbol.clone(), kline.interval);
+        let new_candle = || FootprintCandle {
+            kline: kline.clone(),
+            tick_size: config.tick_size,
+            levels: Vec::new(),
+        };
+        let mut current = self.current.entry(key.clone()).or_insert_with(new_candle);
+
+        let mut closed = None;
+        if current.kline.open_time != kline.open_time {
+            let mut candle = std::mem::replace(&mut *current, new_candle());
+            candle.kline.is_closed = true;
+            let mut history = self.history.entry(key).or_default();
+            history.push(candle.clone());
+            if history.len() > MAX_FOOTPRINT_HISTORY {
+                let len = history.len();
+                history.drain(0..len - MAX_FOOTPRINT_HISTORY);
+            }
+            closed = Some(candle);
+        }
+
+        current.kline = kline.clone();
+        let price = ((transaction.price / config.tick_size).floor() * config.tick_size).normalize();
+        let index = match current.levels.binary_search_by(|level| level.price.cmp(&price)) {
+            Ok(index) => index,
+            Err(index) => {
+                let level = FootprintLevel {
+                    price,
+                    bid_volume: Decimal::ZERO,
+                    ask_volume: Decimal::ZERO,
+                    delta: Decimal::ZERO,
+                };
+                current.levels.insert(index, level);
+                index
+            }
+        };
+        let level = &mut current.levels[index];
+        match transaction.side {
+            TradeSide::Buy => level.ask_volume += transaction.volume,
+            TradeSide::Sell => level.bid_volume += transaction.volume,
+        }
+        level.delta = level.ask_volume - level.bid_volume;
+
+        Some(FootprintUpdate {
+            closed,
+            current: current.clone(),
+        })
+    }
+
+    /// Closed footprint candles, oldest first.
+    pub fn history(&self, venue: &str, symbol: &str, interval: KLineInterval, limit: usize) -> Vec<FootprintCandle> {
+        self.history
+            .get(&(venue.to_string(), symbol.to_string(), interval))
+            .map(|candles| candles[candles.len().saturating_sub(limit)..].to_vec())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_levels_split_bid_and_ask() {
+        let tracker = FootprintTracker::new(vec![FootprintConfig {
+            symbol: "DOGE".to_string(),
+            tick_size: Decimal::new(5, 1),
+            intervals: default_intervals(),
+        }]);
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut kline = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, start, Decimal::TEN);
+        let mut trade = |price: &str, volume: i64, side: TradeSide, secs: i64| {
+            let transaction = Transaction::new_at(
+                Uuid::new_v4(),
+                "DOGE".to_string(),
+                price.parse().unwrap(),
+                Decimal::new(volume, 0),
+                side,
+                start + chrono::Duration::seconds(secs),
+            );
+            if secs >= 60 && kline.open_time == start {
+                kline = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, start + chrono::Duration::minutes(1), transaction.price);
+            }
+            kline.update(transaction.price, transaction.volume, side);
+            tracker.on_trade(&kline, &transaction).unwrap()
+        };
+
+        trade("10.2", 3, TradeSide::Buy, 0);
+        trade("9.9", 2, TradeSide::Sell, 10);
+        let update = trade("10.4", 4, TradeSide::Sell, 20);
+        assert!(update.closed.is_none());
+        let levels = &update.current.levels;
+        assert_eq!(levels.len(), 2);
+        assert_eq!((levels[0].price, levels[0].bid_volume), (Decimal::new(95, 1), Decimal::TWO));
+        assert_eq!(levels[1].price, Decimal::TEN);
+        assert_eq!((levels[1].ask_volume, levels[1].bid_volume, levels[1].delta), (Decimal::new(3, 0), Decimal::new(4, 0), -Decimal::ONE));
+
+        let update = trade("10.1", 1, TradeSide::Buy, 60);
+        let closed = update.closed.unwrap();
+        assert!(closed.kline.is_closed);
+        assert_eq!(closed.kline.ohlcv.delta, Decimal::new(-3, 0));
+        assert_eq!(update.current.levels.len(), 1);
+        assert_eq!(tracker.history("default", "DOGE", KLineInterval::OneMinute, 10).len(), 1);
+        assert!(!tracker.is_enabled("DOGE", KLineInterval::OneHour));
+    }
+}
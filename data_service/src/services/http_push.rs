@@ -0,0 +1,68 @@
+use super::source::{SourceRegistry, TransactionSource};
+use super::supervisor::MarketEventStream;
+use crate::models::{MarketEvent, Transaction};
+use async_stream::stream;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+/// Trades pushed through `POST /api/v1/ingest/{source}`.
+///
+/// The endpoint hands trades to a bounded channel; when the supervisor falls behind,
+/// pushes are refused rather than buffered without limit.
+pub struct HttpPushSource {
+    name: String,
+    receiver: Arc<Mutex<mpsc::Receiver<Transaction>>>,
+}
+
+impl HttpPushSource {
+    /// Creates the source and registers its sender so the ingest endpoint can find it.
+    pub fn register(name: &str, capacity: usize, registry: &SourceRegistry) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        registry.register_push(name, sender);
+        Self {
+            name: name.to_string(),
+            receiver: Arc::new(Mutex::new(receiver)),
+        }
+    }
+}
+
+impl TransactionSource for HttpPushSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open(&self) -> MarketEventStream {
+        let receiver = self.receiver.clone();
+        Box::pin(stream! {
+            let mut receiver = receiver.lock_owned().await;
+            while let Some(transaction) = receiver.recv().await {
+                yield Ok(MarketEvent::Trade(transaction));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use futures::StreamExt;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_pushed_trades_reach_the_stream() {
+        let registry = SourceRegistry::default();
+        let source = HttpPushSource::register("partner", 4, &registry);
+        let sender = registry.push_sender("partner").unwrap();
+        assert!(registry.push_sender("unknown").is_none());
+
+        let transaction = Transaction::new("DOGE".to_string(), Decimal::ONE, Decimal::ONE, TradeSide::Sell);
+        sender.send(transaction.clone()).await.unwrap();
+
+        let mut stream = source.open();
+        match stream.next().await {
+            Some(Ok(MarketEvent::Trade(received))) => assert_eq!(received.id, transaction.id),
+            other => panic!("unexpected item: {:?}", other.map(|result| result.is_ok())),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::mock_data::{new_rng, standard_normal};
+use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Number of past factor levels kept for generators that read slightly behind.
+const FACTOR_WINDOW: usize = 3600;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MarketFactorConfig {
+    /// Standard deviation of the factor's log-return per step.
+    pub volatility: f64,
+    pub step_ms: u64,
+}
+
+impl Default for MarketFactorConfig {
+    fn default() -> Self {
+        Self {
+            volatility: 0.002,
+            step_ms: 1000,
+        }
+    }
+}
+
+/// Shared random walk that mock generators can follow to produce correlated moves.
+///
+/// The factor's value at a given time depends only on the seed and the step index, not on
+/// which generator asks first, so seeded runs stay reproducible.
+#[derive(Clone)]
+pub struct MarketFactor {
+    config: MarketFactorConfig,
+    state: Arc<Mutex<FactorState>>,
+}
+
+struct FactorState {
+    rng: StdRng,
+    origin: Option<DateTime<Utc>>,
+    /// Log-levels for steps `first_step..first_step + levels.len()`.
+    first_step: i64,
+    levels: VecDeque<f64>,
+}
+
+impl MarketFactor {
+    pub fn new(config: MarketFactorConfig, seed: Option<u64>) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(FactorState {
+                rng: new_rng(seed),
+                origin: None,
+                first_step: 0,
+                levels: VecDeque::from([0.0]),
+            })),
+        }
+    }
+
+    /// Log-level of the factor at `time`. The first call fixes the factor's origin.
+    pub fn log_level(&self, time: DateTime<Utc>) -> f64 {
+        let mut state = self.state.lock().expect("Market factor lock poisoned");
+        let origin = *state.origin.get_or_insert(time);
+        let step = (time - origin).num_milliseconds().max(0) / self.config.step_ms.max(1) as i64;
+
+        while state.first_step + (state.levels.len() as i64) <= step {
+            let last = *state.levels.back().expect("Factor window is never empty");
+            let shock = self.config.volatility * standard_normal(&mut state.rng);
+            state.levels.push_back(last + shock);
+            if state.levels.len() > FACTOR_WINDOW {
+                state.levels.pop_front();
+                state.first_step += 1;
+            }
+        }
+
+        // Steps older than the window have been dropped. A reader that far behind (a
+        // generator stalled for over FACTOR_WINDOW steps) gets the oldest level kept, so
+        // its next move against the factor is flat rather than a jump.
+        if step < state.first_step {
+            tracing::debug!("Market factor step {} is older than the window, using step {}", step, state.first_step);
+            return state.levels[0];
+        }
+        state.levels[(step - state.first_step) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_factor_is_independent_of_read_order() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let config = MarketFactorConfig::default();
+
+        let forward = MarketFactor::new(config.clone(), Some(1));
+        forward.log_level(start);
+        let a = forward.log_level(start + chrono::Duration::seconds(10));
+        let b = forward.log_level(start + chrono::Duration::seconds(20));
+
+        let skipping = MarketFactor::new(config, Some(1));
+        skipping.log_level(start);
+        assert_eq!(skipping.log_level(start + chrono::Duration::seconds(20)), b);
+        assert_eq!(skipping.log_level(start + chrono::Duration::seconds(10)), a);
+    }
+
+    #[test]
+    fn test_steps_before_the_window_clamp_to_the_oldest_level() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let factor = MarketFactor::new(MarketFactorConfig::default(), Some(1));
+        factor.log_level(start);
+        let window_start = start + chrono::Duration::seconds(100);
+        factor.log_level(window_start + chrono::Duration::seconds(FACTOR_WINDOW as i64 - 1));
+
+        assert_eq!(factor.log_level(start), factor.log_level(window_start));
+    }
+}
@@ -1,21 +1,41 @@
+use super::clock::{Clock, SystemClock, Ticker};
+use super::market_factor::MarketFactor;
 use crate::models::{TradeSide, Transaction};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_stream::stream;
+use chrono::{DateTime, Utc};
 use futures::Stream;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use rust_decimal::Decimal;
-use std::{pin::Pin, str::FromStr};
-use tokio::time::{interval, Duration};
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::{pin::Pin, str::FromStr, sync::Arc};
+use tokio::time::Duration;
+use uuid::Uuid;
 
 pub struct MockDataGenerator {
     base_price: Decimal,
     volatility: f64,
     min_volume: Decimal,
     max_volume: Decimal,
+    seed: Option<u64>,
+    clock: Arc<dyn Clock>,
+    market_factor: Option<(MarketFactor, f64)>,
+    checkpoint: Checkpoint,
 }
 
-impl Default for MockDataGenerator {
+/// Random-walk parameters for [`MockDataGenerator`], as read from the service config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MockDataConfig {
+    pub base_price: Decimal,
+    pub volatility: f64,
+    pub min_volume: Decimal,
+    pub max_volume: Decimal,
+}
+
+impl Default for MockDataConfig {
     fn default() -> Self {
         Self {
             base_price: Decimal::from_str("100.0").expect("Invalid default base price"),
@@ -26,6 +46,82 @@ impl Default for MockDataGenerator {
     }
 }
 
+impl MockDataConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.base_price <= Decimal::ZERO {
+            bail!("Random walk base_price must be positive");
+        }
+        // Each step moves the price by at most `volatility` either way, which must keep it positive
+        if !(0.0..1.0).contains(&self.volatility) {
+            bail!("Random walk volatility must be in [0, 1)");
+        }
+        if self.min_volume < Decimal::ZERO || self.max_volume < self.min_volume || self.max_volume <= Decimal::ZERO {
+            bail!("Random walk volumes need 0 <= min_volume <= max_volume and a positive max_volume");
+        }
+        Ok(())
+    }
+}
+
+/// Seeded generators replay the exact same sequence; unseeded ones draw from the OS.
+pub(crate) fn new_rng(seed: Option<u64>) -> StdRng {
+    seed.map(StdRng::seed_from_u64).unwrap_or_else(StdRng::from_os_rng)
+}
+
+/// Random v4 id drawn from the generator's own RNG so seeded runs get stable ids.
+pub(crate) fn random_id(rng: &mut StdRng) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.random()).into_uuid()
+}
+
+/// Box-Muller sample from N(0, 1).
+pub(crate) fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1 = rng.random::<f64>().max(f64::MIN_POSITIVE);
+    let u2 = rng.random::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Where a generator's simulated market stood after its last event.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GeneratorState {
+    /// Nothing generated yet; start from the configured values.
+    #[default]
+    Initial,
+    /// Last price of a random walk.
+    Price(Decimal),
+    /// Reserves of an AMM pool, including a bonding curve's pool after graduation.
+    Pool { token_reserve: Decimal, quote_reserve: Decimal },
+    /// Tokens sold and quote held by a bonding curve that hasn't graduated.
+    Curve { supply: f64, reserve: f64 },
+    /// Phase a scenario is in, when that phase ends and the last price. A phase index past
+    /// the last phase means a scenario that doesn't repeat has finished.
+    Scenario {
+        phase: usize,
+        phase_end: DateTime<Utc>,
+        price: f64,
+    },
+}
+
+/// Shared slot a generator records its state in after every event. A stream opened with
+/// the same checkpoint, such as after a supervised restart, carries on from there instead
+/// of jumping back to the configured start.
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoint(Arc<Mutex<GeneratorState>>);
+
+impl Checkpoint {
+    pub fn get(&self) -> GeneratorState {
+        *self.0.lock().expect("Checkpoint lock poisoned")
+    }
+
+    pub fn set(&self, state: GeneratorState) {
+        *self.0.lock().expect("Checkpoint lock poisoned") = state;
+    }
+}
+
+impl Default for MockDataGenerator {
+    fn default() -> Self {
+        Self::from_config(MockDataConfig::default())
+    }
+}
+
 impl MockDataGenerator {
     pub fn new(base_price: Decimal, volatility: f64, min_volume: Decimal, max_volume: Decimal) -> Self {
         Self {
@@ -33,32 +129,78 @@ impl MockDataGenerator {
             volatility,
             min_volume,
             max_volume,
+            seed: None,
+            clock: Arc::new(SystemClock),
+            market_factor: None,
+            checkpoint: Checkpoint::default(),
         }
     }
 
+    pub fn from_config(config: MockDataConfig) -> Self {
+        Self::new(config.base_price, config.volatility, config.min_volume, config.max_volume)
+    }
+
+    /// Makes every stream from this generator reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Adds `beta` times the shared factor's log-return to every price move, so
+    /// generators following the same factor move together.
+    pub fn with_market_factor(mut self, factor: MarketFactor, beta: f64) -> Self {
+        self.market_factor = Some((factor, beta));
+        self
+    }
+
+    /// Resumes from and records into `checkpoint`.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
     pub fn generate_transaction_stream(
         &self,
         symbol: String,
         interval_ms: u64,
     ) -> Pin<Box<dyn Stream<Item = Result<Transaction>> + Send>> {
-        let base_price = self.base_price;
+        let base_price = match self.checkpoint.get() {
+            GeneratorState::Price(price) => price,
+            _ => self.base_price,
+        };
         let volatility = self.volatility;
         let min_volume = self.min_volume;
         let max_volume = self.max_volume;
 
-        let mut interval = interval(Duration::from_millis(interval_ms));
+        let clock = self.clock.clone();
+        let tick = Duration::from_millis(interval_ms);
         let mut current_price = base_price;
-        let mut rng = StdRng::from_os_rng();
+        let mut rng = new_rng(self.seed);
+        let market_factor = self.market_factor.clone();
+        let checkpoint = self.checkpoint.clone();
+        let mut factor_level = market_factor.as_ref().map(|(factor, _)| factor.log_level(clock.now()));
 
         Box::pin(stream! {
+            let mut ticker = Ticker::new(clock.clone(), tick);
             loop {
-                interval.tick().await;
+                ticker.tick().await;
 
                 // Generate price movement
-                let price_change = (rng.gen::<f64>() - 0.5) * 2.0 * volatility;
+                let mut price_change = (rng.random::<f64>() - 0.5) * 2.0 * volatility;
+                if let (Some((factor, beta)), Some(last_level)) = (&market_factor, factor_level.as_mut()) {
+                    let level = factor.log_level(clock.now());
+                    price_change += beta * (level - *last_level);
+                    *last_level = level;
+                }
                 let price_multiplier = Decimal::from_str(&(1.0 + price_change).to_string())
                     .context("Failed to create price multiplier")?;
                 current_price *= price_multiplier;
+                checkpoint.set(GeneratorState::Price(current_price));
 
                 // Generate random volume
                 let volume_range = max_volume - min_volume;
@@ -67,17 +209,19 @@ impl MockDataGenerator {
                         .context("Failed to generate random volume")?);
 
                 // Randomly choose trade side
-                let side = if rng.gen_bool(0.5) {
+                let side = if rng.random_bool(0.5) {
                     TradeSide::Buy
                 } else {
                     TradeSide::Sell
                 };
 
-                yield Ok(Transaction::new(
+                yield Ok(Transaction::new_at(
+                    random_id(&mut rng),
                     symbol.clone(),
                     current_price,
                     random_volume,
                     side,
+                    clock.now(),
                 ));
             }
         })
@@ -87,6 +231,8 @@ impl MockDataGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::SimulatedClock;
+    use chrono::TimeZone;
     use futures::StreamExt;
 
     #[tokio::test]
@@ -99,4 +245,47 @@ mod tests {
         assert!(transaction.price > Decimal::ZERO);
         assert!(transaction.volume > Decimal::ZERO);
     }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(MockDataConfig::default().validate().is_ok());
+        for config in [
+            MockDataConfig { base_price: Decimal::ZERO, ..MockDataConfig::default() },
+            MockDataConfig { volatility: -0.1, ..MockDataConfig::default() },
+            MockDataConfig { volatility: 1.0, ..MockDataConfig::default() },
+            MockDataConfig { volatility: f64::NAN, ..MockDataConfig::default() },
+            MockDataConfig { min_volume: Decimal::from(-1), ..MockDataConfig::default() },
+            MockDataConfig { min_volume: Decimal::from(20), ..MockDataConfig::default() },
+            MockDataConfig { min_volume: Decimal::ZERO, max_volume: Decimal::ZERO, ..MockDataConfig::default() },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seeded_generator_is_reproducible() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let run = || {
+            MockDataGenerator::default()
+                .with_seed(42)
+                .with_clock(Arc::new(SimulatedClock::new(start)))
+                .generate_transaction_stream("DOGE".to_string(), 250)
+                .take(100)
+                .map(|result| result.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let first = run().await;
+        let second = run().await;
+
+        assert_eq!(first[0].timestamp, start + chrono::Duration::milliseconds(250));
+        assert_eq!(first[99].timestamp, start + chrono::Duration::seconds(25));
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.price, b.price);
+            assert_eq!(a.volume, b.volume);
+            assert_eq!(a.side, b.side);
+            assert_eq!(a.timestamp, b.timestamp);
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,194 @@
+use super::amm_pool::{AmmPoolConfig, AmmPoolGenerator};
+use super::bonding_curve::{BondingCurveConfig, BondingCurveGenerator};
+use super::fault_injection::{FaultConfig, FaultCounts, FaultInjector};
+use super::market_factor::MarketFactor;
+use super::mock_data::{Checkpoint, MockDataConfig, MockDataGenerator};
+use super::source::{SourceRegistry, TransactionSource};
+use super::supervisor::{trade_events, MarketEventStream};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockSymbolConfig {
+    pub symbol: String,
+    /// Venue the symbol trades on; the default venue when omitted.
+    #[serde(default)]
+    pub venue: Option<String>,
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Sensitivity to the shared market factor.
+    #[serde(default)]
+    pub beta: f64,
+    #[serde(default)]
+    pub generator: GeneratorConfig,
+    /// Corrupts this symbol's output to exercise downstream error handling.
+    #[serde(default)]
+    pub faults: Option<FaultConfig>,
+}
+
+fn default_interval_ms() -> u64 {
+    100
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GeneratorConfig {
+    RandomWalk(MockDataConfig),
+    AmmPool(AmmPoolConfig),
+    BondingCurve(BondingCurveConfig),
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig::RandomWalk(MockDataConfig::default())
+    }
+}
+
+impl MockSymbolConfig {
+    /// Builds a fresh event stream for this symbol; called again whenever the generator
+    /// restarts. The stream picks up from `checkpoint` and keeps it up to date, and its
+    /// trades are corrupted by `faults` if given.
+    pub fn generate_event_stream(
+        &self,
+        seed: Option<u64>,
+        market_factor: Option<&MarketFactor>,
+        checkpoint: &Checkpoint,
+        faults: Option<&FaultInjector>,
+    ) -> MarketEventStream {
+        let stream = self.generate_clean_event_stream(seed, market_factor, checkpoint);
+        match faults {
+            Some(injector) => injector.wrap_events(stream),
+            None => stream,
+        }
+    }
+
+    fn generate_clean_event_stream(
+        &self,
+        seed: Option<u64>,
+        market_factor: Option<&MarketFactor>,
+        checkpoint: &Checkpoint,
+    ) -> MarketEventStream {
+        let symbol = self.symbol.clone();
+        match &self.generator {
+            GeneratorConfig::RandomWalk(config) => {
+                let mut generator = MockDataGenerator::from_config(config.clone()).with_checkpoint(checkpoint.clone());
+                if let Some(seed) = seed {
+                    generator = generator.with_seed(seed);
+                }
+                if let Some(factor) = market_factor.filter(|_| self.beta != 0.0) {
+                    generator = generator.with_market_factor(factor.clone(), self.beta);
+                }
+                trade_events(generator.generate_transaction_stream(symbol, self.interval_ms))
+            }
+            GeneratorConfig::AmmPool(config) => {
+                let mut generator = AmmPoolGenerator::new(config.clone()).with_checkpoint(checkpoint.clone());
+                if let Some(seed) = seed {
+                    generator = generator.with_seed(seed);
+                }
+                generator.generate_event_stream(symbol, self.interval_ms)
+            }
+            GeneratorConfig::BondingCurve(config) => {
+                let mut generator = BondingCurveGenerator::new(config.clone()).with_checkpoint(checkpoint.clone());
+                if let Some(seed) = seed {
+                    generator = generator.with_seed(seed);
+                }
+                generator.generate_event_stream(symbol, self.interval_ms)
+            }
+        }
+    }
+}
+
+/// A configured mock symbol as a supervised source.
+pub struct MockSource {
+    name: String,
+    config: MockSymbolConfig,
+    seed: Option<u64>,
+    market_factor: Option<MarketFactor>,
+    /// Survives restarts so a reopened generator continues from its last price or reserves.
+    checkpoint: Checkpoint,
+    /// Shared by every stream the source opens, so fault draws and counts carry on
+    /// across restarts.
+    faults: Option<FaultInjector>,
+}
+
+impl MockSource {
+    pub fn new(config: MockSymbolConfig, seed: Option<u64>, market_factor: Option<MarketFactor>) -> Self {
+        let name = match &config.venue {
+            Some(venue) => format!("mock:{}/{}", venue, config.symbol),
+            None => format!("mock:{}", config.symbol),
+        };
+        let faults = config.faults.clone().map(|mut faults| {
+            // Offset the seed so fault draws don't mirror the generator's own RNG.
+            faults.seed = faults.seed.or(seed.map(|seed| seed ^ 0xFA17));
+            FaultInjector::new(faults)
+        });
+        Self {
+            name,
+            config,
+            seed,
+            market_factor,
+            checkpoint: Checkpoint::default(),
+            faults,
+        }
+    }
+
+    /// Registers the fault injector, if any, so its counts show up in the source's health.
+    pub fn register(self, registry: &SourceRegistry) -> Self {
+        if let Some(faults) = &self.faults {
+            registry.register_faults(&self.name, faults.clone());
+        }
+        self
+    }
+
+    pub fn fault_counts(&self) -> Option<FaultCounts> {
+        self.faults.as_ref().map(FaultInjector::counts)
+    }
+}
+
+impl TransactionSource for MockSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn venue(&self) -> Option<&str> {
+        self.config.venue.as_deref()
+    }
+
+    fn open(&self) -> MarketEventStream {
+        self.config
+            .generate_event_stream(self.seed, self.market_factor.as_ref(), &self.checkpoint, self.faults.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_fault_counts_survive_restarts() {
+        let config = MockSymbolConfig {
+            symbol: "DOGE".to_string(),
+            venue: None,
+            interval_ms: 1,
+            beta: 0.0,
+            generator: GeneratorConfig::default(),
+            faults: Some(FaultConfig {
+                duplicate_probability: 1.0,
+                ..FaultConfig::default()
+            }),
+        };
+        let registry = SourceRegistry::default();
+        let source = MockSource::new(config, Some(1), None).register(&registry);
+        registry.update(source.name(), |_| {});
+
+        // Each open is a supervised restart
+        for _ in 0..2 {
+            let events: Vec<_> = source.open().take(6).collect().await;
+            assert_eq!(events.len(), 6);
+        }
+
+        let counts = source.fault_counts().unwrap();
+        assert_eq!((counts.passed, counts.duplicates), (6, 6));
+        assert_eq!(registry.source_health(source.name()).unwrap().faults, Some(counts));
+    }
+}
@@ -1,5 +1,64 @@
+mod alerts;
+mod amm_pool;
+mod bars;
+mod bonding_curve;
+mod chart;
+mod clock;
+mod consolidated;
 mod data_service;
+mod fault_injection;
+mod footprint;
+mod http_push;
+mod market_factor;
 mod mock_data;
+mod mock_source;
+mod patterns;
+mod positions;
+mod replay;
+mod scenario;
+mod source;
+mod stats;
+mod supervisor;
+mod synthetic;
+mod tcp_ingest;
+mod tcp_source;
+mod upstream_ws;
+mod volume_profile;
+mod webhooks;
+mod whales;
 
+pub use alerts::AlertEngine;
+pub use amm_pool::{AmmPool, AmmPoolConfig, AmmPoolGenerator, SwapResult};
+pub use bars::{BarAggregator, BarConfig};
+pub use bonding_curve::{BondingCurve, BondingCurveConfig, BondingCurveGenerator, CurveShape};
+pub use chart::{
+    heikin_ashi, heikin_ashi_series, renko_bricks, validate_box_size, HeikinAshiStream,
+    RenkoBuilder, RenkoStream,
+};
+pub use clock::{Clock, SimulatedClock, SystemClock};
+pub use consolidated::{ConsolidatedConfig, ConsolidatedUpdate, Consolidator, CONSOLIDATED_VENUE};
 pub use data_service::DataService;
-pub use mock_data::MockDataGenerator;
+pub use fault_injection::{FaultConfig, FaultCounts, FaultInjector};
+pub use footprint::{FootprintConfig, FootprintTracker, FootprintUpdate};
+pub use http_push::HttpPushSource;
+pub use market_factor::{MarketFactor, MarketFactorConfig};
+pub use mock_data::{Checkpoint, GeneratorState, MockDataConfig, MockDataGenerator};
+pub use mock_source::{GeneratorConfig, MockSource, MockSymbolConfig};
+pub use patterns::{PatternConfig, PatternDetector};
+pub use positions::{PositionTracker, WalletActivity};
+pub use replay::{ReplayConfig, ReplayControl, ReplayFormat, ReplaySource, ReplaySpeed};
+pub use scenario::{Regime, Scenario, ScenarioGenerator, SymbolScenario, VolumeDistribution};
+pub use source::{SourceHealth, SourceRegistry, SourceState, TransactionSource};
+pub use stats::{compute_stats, StatsConfig, StatsTracker, MAX_STATS_WINDOW};
+pub use supervisor::{trade_events, MarketEventStream, Supervisor, TransactionStream};
+pub use synthetic::{SyntheticConfig, SyntheticLeg, SyntheticOp, SyntheticSymbol};
+pub(crate) use tcp_ingest::default_max_line_bytes;
+pub use tcp_ingest::{TcpIngestConfig, TcpIngestSource};
+pub use tcp_source::TcpNdjsonSource;
+pub use upstream_ws::{FieldMapping, TimestampUnit, UpstreamWsConfig, UpstreamWsSource};
+pub use volume_profile::{build_profile, ProfileRequest, TradePrint};
+pub use webhooks::{
+    sign_webhook, WebhookConfig, WebhookDelivery, WebhookDispatcher, WebhookFilter, WebhookStatus,
+    WebhooksConfig,
+};
+pub use whales::{WhaleConfig, WhaleDetector};
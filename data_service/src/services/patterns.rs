@@ -0,0 +1,237 @@
+use crate::models::{CandlePattern, KLine, KLineInterval, PatternEvent};
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Pattern matches kept for the REST history, across all series.
+const MAX_PATTERN_HISTORY: usize = 1000;
+
+/// Thresholds for the candle shapes, as shares of a candle's high-low range unless noted.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PatternConfig {
+    /// Largest body of a doji.
+    pub doji_body: Decimal,
+    /// Smallest long shadow of a hammer or shooting star, as a multiple of the body.
+    pub shadow_ratio: Decimal,
+    /// Largest short shadow of a hammer or shooting star.
+    pub short_shadow: Decimal,
+    /// Smallest body of the first candle of a morning or evening star.
+    pub long_body: Decimal,
+    /// Largest body of the middle candle of a morning or evening star.
+    pub star_body: Decimal,
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self {
+            doji_body: Decimal::new(1, 1),
+            shadow_ratio: Decimal::TWO,
+            short_shadow: Decimal::new(1, 1),
+            long_body: Decimal::new(6, 1),
+            star_body: Decimal::new(3, 1),
+        }
+    }
+}
+
+impl PatternConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, share) in [
+            ("doji_body", self.doji_body),
+            ("short_shadow", self.short_shadow),
+            ("long_body", self.long_body),
+            ("star_body", self.star_body),
+        ] {
+            if share < Decimal::ZERO || share > Decimal::ONE {
+                bail!("patterns.{} must be between 0 and 1", name);
+            }
+        }
+        if self.shadow_ratio <= Decimal::ZERO {
+            bail!("patterns.shadow_ratio must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Body and shadows of a candle with a non-zero range.
+struct Shape {
+    body: Decimal,
+    range: Decimal,
+    upper: Decimal,
+    lower: Decimal,
+    up: bool,
+    down: bool,
+}
+
+impl Shape {
+    fn of(kline: &KLine) -> Option<Self> {
+        let range = kline.ohlcv.high - kline.ohlcv.low;
+        if range <= Decimal::ZERO {
+            return None;
+        }
+        Some(Self {
+            body: (kline.ohlcv.close - kline.ohlcv.open).abs(),
+            range,
+            upper: kline.ohlcv.high - kline.ohlcv.open.max(kline.ohlcv.close),
+            lower: kline.ohlcv.open.min(kline.ohlcv.close) - kline.ohlcv.low,
+            up: kline.ohlcv.close > kline.ohlcv.open,
+            down: kline.ohlcv.close < kline.ohlcv.open,
+        })
+    }
+}
+
+/// Looks for candlestick patterns ending in each closed candle and keeps a history of them.
+#[derive(Default)]
+pub struct PatternDetector {
+    config: PatternConfig,
+    history: Mutex<VecDeque<PatternEvent>>,
+}
+
+impl PatternDetector {
+    pub fn new(config: PatternConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Patterns completed by `kline`, given the closed candles before it, oldest first.
+    pub fn on_close(&self, kline: &KLine, previous: &[KLine]) -> Vec<PatternEvent> {
+        let event = |pattern: CandlePattern, first: &KLine| PatternEvent {
+            venue: kline.venue.clone(),
+            symbol: kline.symbol.clone(),
+            interval: kline.interval,
+            pattern,
+            bias: pattern.bias(),
+            open_time: first.open_time,
+            close_time: kline.close_time,
+            close: kline.ohlcv.close,
+        };
+        let config = &self.config;
+        let mut events = Vec::new();
+        let Some(current) = Shape::of(kline) else {
+            return events;
+        };
+
+        if current.body <= config.doji_body * current.range {
+            events.push(event(CandlePattern::Doji, kline));
+        } else if current.lower >= config.shadow_ratio * current.body
+            && current.upper <= config.short_shadow * current.range
+        {
+            events.push(event(CandlePattern::Hammer, kline));
+        } else if current.upper >= config.shadow_ratio * current.body
+            && current.lower <= config.short_shadow * current.range
+        {
+            events.push(event(CandlePattern::ShootingStar, kline));
+        }
+
+        if let Some(prior) = previous.last() {
+            if let Some(prior_shape) = Shape::of(prior) {
+                let covers = kline.ohlcv.open.min(kline.ohlcv.close) <= prior.ohlcv.open.min(prior.ohlcv.close)
+                    && kline.ohlcv.open.max(kline.ohlcv.close) >= prior.ohlcv.open.max(prior.ohlcv.close)
+                    && current.body > prior_shape.body;
+                if covers && prior_shape.down && current.up {
+                    events.push(event(CandlePattern::BullishEngulfing, prior));
+                } else if covers && prior_shape.up && current.down {
+                    events.push(event(CandlePattern::BearishEngulfing, prior));
+                }
+            }
+        }
+
+        if let [.., first, middle] = previous {
+            if let (Some(first_shape), Some(middle_shape)) = (Shape::of(first), Shape::of(middle)) {
+                let midpoint = (first.ohlcv.open + first.ohlcv.close) / Decimal::TWO;
+                let long_first = first_shape.body >= config.long_body * first_shape.range;
+                let small_middle = middle_shape.body <= config.star_body * middle_shape.range;
+                if long_first && small_middle {
+                    if first_shape.down && current.up && kline.ohlcv.close > midpoint {
+                        events.push(event(CandlePattern::MorningStar, first));
+                    } else if first_shape.up && current.down && kline.ohlcv.close < midpoint {
+                        events.push(event(CandlePattern::EveningStar, first));
+                    }
+                }
+            }
+        }
+
+        if !events.is_empty() {
+            let mut history = self.history.lock().expect("Pattern history lock poisoned");
+            history.extend(events.iter().cloned());
+            while history.len() > MAX_PATTERN_HISTORY {
+                history.pop_front();
+            }
+        }
+        events
+    }
+
+    /// Most recent matches, newest first, optionally filtered.
+    pub fn recent(
+        &self,
+        symbol: Option<&str>,
+        interval: Option<KLineInterval>,
+        pattern: Option<CandlePattern>,
+        limit: usize,
+    ) -> Vec<PatternEvent> {
+        let history = self.history.lock().expect("Pattern history lock poisoned");
+        history
+            .iter()
+            .rev()
+            .filter(|event| symbol.is_none_or(|symbol| event.symbol == symbol))
+            .filter(|event| interval.is_none_or(|interval| event.interval == interval))
+            .filter(|event| pattern.is_none_or(|pattern| event.pattern == pattern))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use chrono::{TimeZone, Utc};
+
+    fn candle(minute: i64, open: i64, high: i64, low: i64, close: i64) -> KLine {
+        let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::minutes(minute);
+        let mut kline = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, open_time, Decimal::new(open, 0));
+        for price in [high, low, close] {
+            kline.update(Decimal::new(price, 0), Decimal::ONE, TradeSide::Buy);
+        }
+        kline.close();
+        kline
+    }
+
+    fn patterns(detector: &PatternDetector, kline: &KLine, previous: &[KLine]) -> Vec<CandlePattern> {
+        detector.on_close(kline, previous).into_iter().map(|event| event.pattern).collect()
+    }
+
+    #[test]
+    fn test_single_and_two_candle_patterns() {
+        let detector = PatternDetector::default();
+        assert_eq!(patterns(&detector, &candle(0, 100, 110, 90, 101), &[]), [CandlePattern::Doji]);
+        assert_eq!(patterns(&detector, &candle(0, 104, 110, 80, 109), &[]), [CandlePattern::Hammer]);
+        assert_eq!(patterns(&detector, &candle(0, 86, 110, 80, 81), &[]), [CandlePattern::ShootingStar]);
+        assert!(patterns(&detector, &candle(0, 100, 110, 90, 108), &[]).is_empty());
+
+        let down = candle(0, 105, 106, 99, 100);
+        assert_eq!(patterns(&detector, &candle(1, 99, 108, 98, 107), &[down]), [CandlePattern::BullishEngulfing]);
+
+        let events = detector.recent(Some("DOGE"), Some(KLineInterval::OneMinute), Some(CandlePattern::BullishEngulfing), 10);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].open_time, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+        assert_eq!(detector.recent(None, None, None, 10).len(), 4);
+    }
+
+    #[test]
+    fn test_morning_star() {
+        let detector = PatternDetector::default();
+        let previous = [candle(0, 120, 121, 99, 100), candle(1, 98, 101, 95, 97)];
+        let found = patterns(&detector, &candle(2, 99, 115, 98, 114), &previous);
+        assert!(found.contains(&CandlePattern::MorningStar));
+
+        // Closing below the middle of the first candle is not enough
+        let found = patterns(&detector, &candle(2, 99, 109, 98, 108), &previous);
+        assert!(!found.contains(&CandlePattern::MorningStar));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::models::{Position, TradeSide, Transaction};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+struct PositionState {
+    balance: Decimal,
+    average_entry_price: Decimal,
+    realized_pnl: Decimal,
+    trades: u64,
+    first_seen: DateTime<Utc>,
+    last_trade_at: DateTime<Utc>,
+}
+
+impl PositionState {
+    fn new(timestamp: DateTime<Utc>) -> Self {
+        Self {
+            balance: Decimal::ZERO,
+            average_entry_price: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            trades: 0,
+            first_seen: timestamp,
+            last_trade_at: timestamp,
+        }
+    }
+
+    /// Applies a fill of `quantity` tokens (positive bought, negative sold) at `price`,
+    /// using average-cost accounting.
+    fn apply(&mut self, quantity: Decimal, price: Decimal, timestamp: DateTime<Utc>) {
+        self.trades += 1;
+        self.last_trade_at = self.last_trade_at.max(timestamp);
+
+        let adds_to_position = self.balance.is_zero() || self.balance.is_sign_positive() == quantity.is_sign_positive();
+        if adds_to_position {
+            let size = self.balance.abs() + quantity.abs();
+            if !size.is_zero() {
+                self.average_entry_price =
+                    (self.average_entry_price * self.balance.abs() + price * quantity.abs()) / size;
+            }
+            self.balance += quantity;
+            return;
+        }
+
+        let closed = quantity.abs().min(self.balance.abs());
+        let direction = if self.balance.is_sign_positive() { Decimal::ONE } else { -Decimal::ONE };
+        self.realized_pnl += closed * (price - self.average_entry_price) * direction;
+        self.balance += quantity;
+        if self.balance.is_zero() {
+            self.average_entry_price = Decimal::ZERO;
+        } else if self.balance.is_sign_positive() != direction.is_sign_positive() {
+            // Flipped through zero: the remainder was opened at this price.
+            self.average_entry_price = price;
+        }
+    }
+
+    fn to_position(&self, wallet: &str, symbol: &str, last_price: Decimal) -> Position {
+        Position {
+            wallet: wallet.to_string(),
+            symbol: symbol.to_string(),
+            balance: self.balance,
+            average_entry_price: self.average_entry_price,
+            realized_pnl: self.realized_pnl,
+            unrealized_pnl: (last_price - self.average_entry_price) * self.balance,
+            last_price,
+            trades: self.trades,
+            first_seen: self.first_seen,
+            last_trade_at: self.last_trade_at,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SymbolPositions {
+    last_price: Decimal,
+    wallets: HashMap<String, PositionState>,
+    /// Wallets with a positive balance.
+    holders: u64,
+}
+
+/// What a trade did to its symbol's wallets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalletActivity {
+    /// Wallets that traded the symbol for the first time.
+    pub new_wallets: u64,
+    /// Wallets holding the symbol after the trade.
+    pub holders: u64,
+}
+
+/// Net balances, average entry prices and PnL per wallet and symbol, built from the
+/// maker and taker of each trade. Venues are pooled: a wallet is the same wallet
+/// wherever it trades.
+#[derive(Default)]
+pub struct PositionTracker {
+    symbols: DashMap<String, SymbolPositions>,
+}
+
+impl PositionTracker {
+    pub fn record(&self, transaction: &Transaction) -> WalletActivity {
+        let mut entry = self.symbols.entry(transaction.symbol.clone()).or_default();
+        let symbol = &mut *entry;
+        symbol.last_price = transaction.price;
+
+        let mut new_wallets = 0;
+        let bought = match transaction.side {
+            TradeSide::Buy => transaction.volume,
+            TradeSide::Sell => -transaction.volume,
+        };
+        // A wallet trading with itself doesn't change its position.
+        let self_trade = transaction.maker.is_some() && transaction.maker == transaction.taker;
+        for (wallet, quantity) in [(&transaction.taker, bought), (&transaction.maker, -bought)] {
+            let Some(wallet) = wallet else {
+                continue;
+            };
+            let state = symbol.wallets.entry(wallet.clone()).or_insert_with(|| {
+                new_wallets += 1;
+                PositionState::new(transaction.timestamp)
+            });
+            if self_trade {
+                break;
+            }
+            let was_holder = state.balance > Decimal::ZERO;
+            state.apply(quantity, transaction.price, transaction.timestamp);
+            match (was_holder, state.balance > Decimal::ZERO) {
+                (false, true) => symbol.holders += 1,
+                (true, false) => symbol.holders -= 1,
+                _ => {}
+            }
+        }
+
+        WalletActivity {
+            new_wallets,
+            holders: symbol.holders,
+        }
+    }
+
+    /// Wallets currently holding a positive balance of `symbol`.
+    pub fn holder_count(&self, symbol: &str) -> u64 {
+        self.symbols.get(symbol).map_or(0, |entry| entry.holders)
+    }
+
+    pub fn wallet_positions(&self, wallet: &str) -> Vec<Position> {
+        let mut positions: Vec<Position> = self
+            .symbols
+            .iter()
+            .filter_map(|entry| {
+                let state = entry.wallets.get(wallet)?;
+                Some(state.to_position(wallet, entry.key(), entry.last_price))
+            })
+            .collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        positions
+    }
+
+    /// Wallets with the largest positive balances in `symbol`.
+    pub fn top_holders(&self, symbol: &str, limit: usize) -> Vec<Position> {
+        let Some(entry) = self.symbols.get(symbol) else {
+            return Vec::new();
+        };
+        let mut holders: Vec<(&String, &PositionState)> =
+            entry.wallets.iter().filter(|(_, state)| state.balance > Decimal::ZERO).collect();
+        holders.sort_by(|a, b| b.1.balance.cmp(&a.1.balance).then_with(|| a.0.cmp(b.0)));
+        holders
+            .into_iter()
+            .take(limit)
+            .map(|(wallet, state)| state.to_position(wallet, symbol, entry.last_price))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn trade(price: i64, volume: i64, side: TradeSide, maker: &str, taker: &str) -> Transaction {
+        Transaction::new_at(
+            Uuid::new_v4(),
+            "DOGE".to_string(),
+            Decimal::new(price, 0),
+            Decimal::new(volume, 0),
+            side,
+            Utc::now(),
+        )
+        .with_wallets(Some(maker.to_string()), Some(taker.to_string()))
+    }
+
+    #[test]
+    fn test_average_cost_positions() {
+        let tracker = PositionTracker::default();
+        let activity = tracker.record(&trade(10, 100, TradeSide::Buy, "mm", "alice"));
+        assert_eq!(activity, WalletActivity { new_wallets: 2, holders: 1 });
+        tracker.record(&trade(20, 100, TradeSide::Buy, "mm", "alice"));
+        tracker.record(&trade(30, 50, TradeSide::Sell, "bob", "alice"));
+
+        let alice = &tracker.wallet_positions("alice")[0];
+        assert_eq!(alice.balance, Decimal::new(150, 0));
+        assert_eq!(alice.average_entry_price, Decimal::new(15, 0));
+        assert_eq!(alice.realized_pnl, Decimal::new(750, 0));
+        assert_eq!(alice.unrealized_pnl, Decimal::new(2250, 0));
+        assert_eq!(alice.trades, 3);
+
+        // The market maker sold 200 it never bought: a short at an average of 15.
+        let mm = &tracker.wallet_positions("mm")[0];
+        assert_eq!((mm.balance, mm.average_entry_price), (Decimal::new(-200, 0), Decimal::new(15, 0)));
+        assert_eq!(mm.unrealized_pnl, Decimal::new(-3000, 0));
+
+        // Bob bought 50 and now sells 80, flipping short at the new price.
+        tracker.record(&trade(40, 80, TradeSide::Sell, "carol", "bob"));
+        let bob = &tracker.wallet_positions("bob")[0];
+        assert_eq!((bob.balance, bob.average_entry_price), (Decimal::new(-30, 0), Decimal::new(40, 0)));
+        assert_eq!(bob.realized_pnl, Decimal::new(500, 0));
+
+        assert_eq!(tracker.holder_count("DOGE"), 2);
+        let holders = tracker.top_holders("DOGE", 10);
+        let wallets: Vec<&str> = holders.iter().map(|p| p.wallet.as_str()).collect();
+        assert_eq!(wallets, vec!["alice", "carol"]);
+        assert!(tracker.top_holders("PEPE", 10).is_empty());
+    }
+}
@@ -0,0 +1,479 @@
+use super::clock::{Clock, SystemClock};
+use super::source::{SourceRegistry, TransactionSource};
+use super::supervisor::{trade_events, MarketEventStream, TransactionStream};
+use crate::models::{TradeSide, Transaction};
+use anyhow::{bail, Context, Result};
+use async_stream::stream;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Replay files are sorted and held in memory so they can be seeked, so their size is capped.
+const MAX_REPLAY_FILE_BYTES: u64 = 256 * 1024 * 1024;
+/// Slowest and fastest replay speeds, as multiples of the original pace.
+const MIN_SPEED_FACTOR: f64 = 0.01;
+const MAX_SPEED_FACTOR: f64 = 10_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayFormat {
+    /// Header row followed by `symbol,price,volume,timestamp,side` columns, plus an optional `id`.
+    Csv,
+    /// One JSON `Transaction` per line.
+    Jsonl,
+}
+
+impl ReplayFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(ReplayFormat::Csv),
+            "jsonl" | "ndjson" => Some(ReplayFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// How fast recorded time passes during a replay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Multiple of the original pace, e.g. `10.0` replays ten times faster.
+    Factor(f64),
+    /// No waiting between trades.
+    Max,
+}
+
+impl ReplaySpeed {
+    /// Parses `"1x"`, `"10"`, `"0.5x"` or `"max"`. Factors must be between 0.01 and 10000.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s.eq_ignore_ascii_case("max") {
+            return Some(ReplaySpeed::Max);
+        }
+        let factor = s.trim_end_matches(['x', 'X']).parse::<f64>().ok()?;
+        (MIN_SPEED_FACTOR..=MAX_SPEED_FACTOR)
+            .contains(&factor)
+            .then_some(ReplaySpeed::Factor(factor))
+    }
+}
+
+impl Default for ReplaySpeed {
+    fn default() -> Self {
+        ReplaySpeed::Factor(1.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ReplaySpeed {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        ReplaySpeed::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid replay speed '{}'", s)))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayConfig {
+    pub path: PathBuf,
+    /// Name used for the source and its admin endpoints; the path when omitted.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Detected from the file extension when omitted.
+    #[serde(default)]
+    pub format: Option<ReplayFormat>,
+    #[serde(default)]
+    pub speed: ReplaySpeed,
+    /// Start over from the first record after the last one.
+    #[serde(default, rename = "loop")]
+    pub looping: bool,
+    /// Shift timestamps so the replay appears to happen now, keeping the original spacing.
+    #[serde(default = "default_rebase_timestamps")]
+    pub rebase_timestamps: bool,
+}
+
+fn default_rebase_timestamps() -> bool {
+    true
+}
+
+impl ReplayConfig {
+    pub fn validate(&self) -> Result<()> {
+        // Every pass would repeat the recorded timestamps, sending candles back in time
+        if self.looping && !self.rebase_timestamps {
+            bail!("Replay of {} loops, which needs rebase_timestamps", self.path.display());
+        }
+        Ok(())
+    }
+
+    /// Name of the source, as listed by the sources and replay admin endpoints.
+    pub fn source_name(&self) -> String {
+        match &self.name {
+            Some(name) => format!("replay:{}", name),
+            None => format!("replay:{}", self.path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvRecord {
+    #[serde(default)]
+    id: Option<Uuid>,
+    #[serde(default)]
+    venue: Option<String>,
+    #[serde(default)]
+    maker: Option<String>,
+    #[serde(default)]
+    taker: Option<String>,
+    symbol: String,
+    price: Decimal,
+    volume: Decimal,
+    timestamp: DateTime<Utc>,
+    side: TradeSide,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ControlState {
+    paused: bool,
+    /// Bumped on every seek so the replay loop can tell a new seek from an old one.
+    seek_generation: u64,
+    seek_to: Option<DateTime<Utc>>,
+}
+
+/// Pause, resume and seek handle for a running [`ReplaySource`].
+#[derive(Clone)]
+pub struct ReplayControl {
+    state: watch::Sender<ControlState>,
+}
+
+impl ReplayControl {
+    pub fn pause(&self) {
+        self.state.send_modify(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.state.send_modify(|state| state.paused = false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+
+    /// Continues from the first record at or after `timestamp`, in recorded time.
+    pub fn seek(&self, timestamp: DateTime<Utc>) {
+        self.state.send_modify(|state| {
+            state.seek_generation += 1;
+            state.seek_to = Some(timestamp);
+        });
+    }
+}
+
+/// Replays recorded trades from a CSV or JSONL file, honouring their original spacing.
+pub struct ReplaySource {
+    name: String,
+    config: ReplayConfig,
+    records: Arc<Vec<Transaction>>,
+    control: ReplayControl,
+    clock: Arc<dyn Clock>,
+}
+
+impl ReplaySource {
+    pub fn open(config: ReplayConfig) -> Result<Self> {
+        config.validate()?;
+        let format = match config.format.or_else(|| ReplayFormat::from_path(&config.path)) {
+            Some(format) => format,
+            None => bail!("Cannot tell the format of {}; set it explicitly", config.path.display()),
+        };
+        let size = std::fs::metadata(&config.path)
+            .with_context(|| format!("Failed to open replay file {}", config.path.display()))?
+            .len();
+        if size > MAX_REPLAY_FILE_BYTES {
+            bail!(
+                "Replay file {} is {} bytes, more than the {} bytes a replay can hold",
+                config.path.display(),
+                size,
+                MAX_REPLAY_FILE_BYTES
+            );
+        }
+        let mut records = match format {
+            ReplayFormat::Csv => read_csv(&config.path)?,
+            ReplayFormat::Jsonl => read_jsonl(&config.path)?,
+        };
+        if records.is_empty() {
+            bail!("Replay file {} has no records", config.path.display());
+        }
+        // Stable sort keeps file order for trades sharing a timestamp.
+        records.sort_by_key(|transaction| transaction.timestamp);
+
+        let (state, _) = watch::channel(ControlState::default());
+        Ok(Self {
+            name: config.source_name(),
+            config,
+            records: Arc::new(records),
+            control: ReplayControl { state },
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn control(&self) -> ReplayControl {
+        self.control.clone()
+    }
+
+    /// Registers the control handle so the replay admin endpoints can find it.
+    pub fn register(self, registry: &SourceRegistry) -> Self {
+        registry.register_replay(&self.name, self.control());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn generate_transaction_stream(&self) -> TransactionStream {
+        let records = self.records.clone();
+        let config = self.config.clone();
+        let clock = self.clock.clone();
+        let mut control = self.control.state.subscribe();
+
+        Box::pin(stream! {
+            let mut pass = 0u64;
+            let mut seek_generation = control.borrow().seek_generation;
+            // Rebased timestamps never go back past this, across passes and backward seeks.
+            let mut last_emitted: Option<DateTime<Utc>> = None;
+
+            loop {
+                let mut index = 0;
+                // Wall-clock and recorded-time anchors for pacing; reset after pauses and seeks.
+                let mut anchor: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+                let mut shift = chrono::Duration::zero();
+
+                while index < records.len() {
+                    let state = control.borrow_and_update().clone();
+                    if state.seek_generation != seek_generation {
+                        seek_generation = state.seek_generation;
+                        if let Some(target) = state.seek_to {
+                            index = records.partition_point(|transaction| transaction.timestamp < target);
+                            anchor = None;
+                            continue;
+                        }
+                    }
+                    if state.paused {
+                        if control.changed().await.is_err() {
+                            return;
+                        }
+                        anchor = None;
+                        continue;
+                    }
+
+                    let record = &records[index];
+                    let (wall_start, data_start) = *anchor.get_or_insert_with(|| {
+                        let now = clock.now();
+                        if config.rebase_timestamps {
+                            shift = last_emitted.map_or(now, |last| now.max(last)) - record.timestamp;
+                        }
+                        (now, record.timestamp)
+                    });
+
+                    if let ReplaySpeed::Factor(factor) = config.speed {
+                        let offset = (record.timestamp - data_start).to_std().unwrap_or_default();
+                        let scaled = Duration::try_from_secs_f64(offset.as_secs_f64() / factor)
+                            .ok()
+                            .and_then(|offset| chrono::Duration::from_std(offset).ok())
+                            .context("Replay offset out of range")?;
+                        let due = wall_start + scaled;
+                        let wait = (due - clock.now()).to_std().unwrap_or_default();
+                        if wait > Duration::ZERO {
+                            tokio::select! {
+                                _ = clock.sleep(wait) => {}
+                                changed = control.changed() => {
+                                    if changed.is_err() {
+                                        return;
+                                    }
+                                    // Re-check pause/seek before emitting anything.
+                                    continue;
+                                }
+                            }
+                        }
+                    }
+
+                    let mut transaction = record.clone();
+                    transaction.timestamp += shift;
+                    if pass > 0 {
+                        transaction.id = Uuid::new_v4();
+                    }
+                    index += 1;
+                    last_emitted = Some(transaction.timestamp);
+                    yield Ok(transaction);
+                }
+
+                if !config.looping {
+                    break;
+                }
+                pass += 1;
+            }
+        })
+    }
+}
+
+impl TransactionSource for ReplaySource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open(&self) -> MarketEventStream {
+        trade_events(self.generate_transaction_stream())
+    }
+}
+
+fn read_csv(path: &Path) -> Result<Vec<Transaction>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .with_context(|| format!("Failed to open replay file {}", path.display()))?;
+
+    reader
+        .deserialize::<CsvRecord>()
+        .enumerate()
+        .map(|(line, record)| {
+            let record = record.with_context(|| format!("Invalid record {} in {}", line + 1, path.display()))?;
+            let mut transaction = Transaction::new_at(
+                record.id.unwrap_or_else(Uuid::new_v4),
+                record.symbol,
+                record.price,
+                record.volume,
+                record.side,
+                record.timestamp,
+            );
+            transaction.venue = record.venue;
+            transaction.maker = record.maker;
+            transaction.taker = record.taker;
+            Ok(transaction)
+        })
+        .collect()
+}
+
+fn read_jsonl(path: &Path) -> Result<Vec<Transaction>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read replay file {}", path.display()))?;
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line, json)| {
+            serde_json::from_str(json).with_context(|| format!("Invalid record on line {} of {}", line + 1, path.display()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::SimulatedClock;
+    use chrono::TimeZone;
+    use futures::StreamExt;
+
+    fn write_fixture(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("replay-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    const CSV: &str = "symbol,price,volume,timestamp,side\n\
+        DOGE,0.10,100,2024-01-01T00:00:00Z,buy\n\
+        DOGE,0.11,50,2024-01-01T00:00:10Z,sell\n\
+        DOGE,0.12,25,2024-01-01T00:01:00Z,buy\n";
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(ReplaySpeed::parse("10x"), Some(ReplaySpeed::Factor(10.0)));
+        assert_eq!(ReplaySpeed::parse("0.5"), Some(ReplaySpeed::Factor(0.5)));
+        assert_eq!(ReplaySpeed::parse("MAX"), Some(ReplaySpeed::Max));
+        for speed in ["0x", "-1x", "1e-300x", "0.001x", "20000x", "NaNx", "fast"] {
+            assert_eq!(ReplaySpeed::parse(speed), None, "accepted {}", speed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_csv_replay_honours_speed() {
+        let path = write_fixture("speed.csv", CSV);
+        let start = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap();
+        let clock = Arc::new(SimulatedClock::new(start));
+        let source = ReplaySource::open(ReplayConfig {
+            path: path.clone(),
+            name: None,
+            format: None,
+            speed: ReplaySpeed::Factor(10.0),
+            looping: false,
+            rebase_timestamps: true,
+        })
+        .unwrap()
+        .with_clock(clock.clone());
+
+        let transactions: Vec<_> = source.generate_transaction_stream().map(|r| r.unwrap()).collect().await;
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0].timestamp, start);
+        assert_eq!(transactions[2].timestamp, start + chrono::Duration::seconds(60));
+        // A minute of recorded trading took six simulated seconds at 10x.
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(6));
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_replay_loops_and_seeks() {
+        let lines: Vec<String> = (0..5)
+            .map(|i| {
+                let transaction = Transaction::new_at(
+                    Uuid::new_v4(),
+                    "PEPE".to_string(),
+                    Decimal::from(i + 1),
+                    Decimal::ONE,
+                    TradeSide::Buy,
+                    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, i).unwrap(),
+                );
+                serde_json::to_string(&transaction).unwrap()
+            })
+            .collect();
+        let path = write_fixture("loop.jsonl", &lines.join("\n"));
+        let mut config = ReplayConfig {
+            path: path.clone(),
+            name: Some("loop".to_string()),
+            format: None,
+            speed: ReplaySpeed::Max,
+            looping: true,
+            rebase_timestamps: false,
+        };
+        // Looping without rebasing would repeat old timestamps
+        assert!(ReplaySource::open(config.clone()).is_err());
+        config.rebase_timestamps = true;
+        let source = ReplaySource::open(config).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let control = source.control();
+        let mut stream = source.generate_transaction_stream();
+
+        assert_eq!(stream.next().await.unwrap().unwrap().price, Decimal::from(1));
+        control.seek(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 3).unwrap());
+        assert_eq!(stream.next().await.unwrap().unwrap().price, Decimal::from(4));
+        let last = stream.next().await.unwrap().unwrap();
+        assert_eq!(last.price, Decimal::from(5));
+        // Looping starts over from the first record, after the previous pass in time.
+        let looped = stream.next().await.unwrap().unwrap();
+        assert_eq!(looped.price, Decimal::from(1));
+        assert!(looped.timestamp >= last.timestamp);
+
+        control.pause();
+        assert!(tokio::time::timeout(Duration::from_millis(50), stream.next()).await.is_err());
+        control.resume();
+        assert_eq!(stream.next().await.unwrap().unwrap().price, Decimal::from(2));
+    }
+}
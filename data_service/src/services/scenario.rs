@@ -0,0 +1,376 @@
+use super::clock::{Clock, SystemClock, Ticker};
+use super::mock_data::{new_rng, random_id, standard_normal, Checkpoint, GeneratorState};
+use super::source::TransactionSource;
+use super::supervisor::{trade_events, MarketEventStream};
+use crate::models::{TradeSide, Transaction};
+use anyhow::{bail, Context, Result};
+use async_stream::stream;
+use futures::Stream;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{path::Path, pin::Pin, sync::Arc};
+use tokio::time::Duration;
+
+/// Lowest price a scenario can drive a token to; keeps rug pulls from reaching zero.
+const MIN_PRICE: f64 = 1e-12;
+
+/// A set of per-symbol timelines, usually loaded from a JSON file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    pub symbols: Vec<SymbolScenario>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SymbolScenario {
+    pub symbol: String,
+    pub base_price: Decimal,
+    /// Start again from the first phase once the last one ends.
+    #[serde(default)]
+    pub repeat: bool,
+    pub phases: Vec<Regime>,
+}
+
+/// Market behaviour for one phase of a scenario (pump, dump, rug, dead token, ...).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Regime {
+    pub name: String,
+    pub duration_secs: f64,
+    /// Expected relative price drift per second, e.g. `0.01` for +1%/s.
+    pub trend: f64,
+    /// Standard deviation of the relative price move applied on each trade.
+    pub volatility: f64,
+    /// Chance that a trade also carries a jump of `jump_size`.
+    pub jump_probability: f64,
+    /// Relative size of a jump, e.g. `-0.9` for a rug pull.
+    pub jump_size: f64,
+    /// Mean trade arrival rate; inter-trade gaps are exponentially distributed.
+    pub trades_per_second: f64,
+    pub volume: VolumeDistribution,
+    /// Fraction of trades that are buys.
+    pub buy_ratio: f64,
+}
+
+impl Default for Regime {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            duration_secs: 60.0,
+            trend: 0.0,
+            volatility: 0.002,
+            jump_probability: 0.0,
+            jump_size: 0.0,
+            trades_per_second: 10.0,
+            volume: VolumeDistribution::default(),
+            buy_ratio: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VolumeDistribution {
+    Fixed { value: f64 },
+    Uniform { min: f64, max: f64 },
+    /// Heavy-tailed sizes: `median * e^(sigma * N(0, 1))`.
+    LogNormal { median: f64, sigma: f64 },
+}
+
+impl Default for VolumeDistribution {
+    fn default() -> Self {
+        VolumeDistribution::Uniform { min: 0.1, max: 10.0 }
+    }
+}
+
+impl VolumeDistribution {
+    fn validate(&self) -> Result<()> {
+        let valid = match *self {
+            VolumeDistribution::Fixed { value } => value.is_finite() && value > 0.0,
+            VolumeDistribution::Uniform { min, max } => min.is_finite() && max.is_finite() && min >= 0.0 && max >= min && max > 0.0,
+            VolumeDistribution::LogNormal { median, sigma } => median.is_finite() && median > 0.0 && sigma.is_finite() && sigma >= 0.0,
+        };
+        if !valid {
+            bail!("Volume distribution {:?} can't produce positive volumes", self);
+        }
+        Ok(())
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        match *self {
+            VolumeDistribution::Fixed { value } => value,
+            VolumeDistribution::Uniform { min, max } => min + (max - min) * rng.random::<f64>(),
+            VolumeDistribution::LogNormal { median, sigma } => median * (sigma * standard_normal(rng)).exp(),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let scenario: Scenario = serde_json::from_str(json).context("Failed to parse scenario")?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario file {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for symbol in &self.symbols {
+            symbol.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl SymbolScenario {
+    pub fn validate(&self) -> Result<()> {
+        if self.base_price <= Decimal::ZERO {
+            bail!("Scenario for {} has a non-positive base price", self.symbol);
+        }
+        if self.phases.is_empty() {
+            bail!("Scenario for {} has no phases", self.symbol);
+        }
+        for phase in &self.phases {
+            // try_from_secs_f64 also rejects NaN, infinite and overflowing durations
+            if Duration::try_from_secs_f64(phase.duration_secs).is_err()
+                || phase.duration_secs <= 0.0
+                || !phase.trades_per_second.is_finite()
+                || phase.trades_per_second < 0.0
+            {
+                bail!("Phase '{}' of {} has an invalid duration or trade rate", phase.name, self.symbol);
+            }
+            if !phase.trend.is_finite() || !phase.volatility.is_finite() || phase.volatility < 0.0 {
+                bail!("Phase '{}' of {} needs a finite trend and a finite, non-negative volatility", phase.name, self.symbol);
+            }
+            if !phase.jump_size.is_finite() {
+                bail!("Phase '{}' of {} has a non-finite jump_size", phase.name, self.symbol);
+            }
+            if !(0.0..=1.0).contains(&phase.jump_probability) || !(0.0..=1.0).contains(&phase.buy_ratio) {
+                bail!("Phase '{}' of {} has a probability outside [0, 1]", phase.name, self.symbol);
+            }
+            phase
+                .volume
+                .validate()
+                .with_context(|| format!("Phase '{}' of {} has an invalid volume", phase.name, self.symbol))?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives mock trades for one symbol through the phases of a [`SymbolScenario`].
+pub struct ScenarioGenerator {
+    name: String,
+    scenario: SymbolScenario,
+    seed: Option<u64>,
+    clock: Arc<dyn Clock>,
+    /// Survives restarts so a reopened scenario continues in the phase and at the price it
+    /// had reached.
+    checkpoint: Checkpoint,
+}
+
+impl ScenarioGenerator {
+    pub fn new(scenario: SymbolScenario) -> Self {
+        Self {
+            name: format!("scenario:{}", scenario.symbol),
+            scenario,
+            seed: None,
+            clock: Arc::new(SystemClock),
+            checkpoint: Checkpoint::default(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Resumes from and records into `checkpoint`.
+    pub fn with_checkpoint(mut self, checkpoint: Checkpoint) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    pub fn generate_transaction_stream(&self) -> Pin<Box<dyn Stream<Item = Result<Transaction>> + Send>> {
+        let scenario = self.scenario.clone();
+        let clock = self.clock.clone();
+        let mut rng = new_rng(self.seed);
+        let checkpoint = self.checkpoint.clone();
+        let resume = checkpoint.get();
+
+        Box::pin(stream! {
+            // Trade gaps vary, so every tick names its own deadline
+            let mut ticker = Ticker::new(clock.clone(), Duration::ZERO);
+            let (mut index, mut phase_end, mut price) = match resume {
+                GeneratorState::Scenario { phase, phase_end, price } => (phase, phase_end, price),
+                _ => (
+                    0,
+                    clock.now() + phase_duration(&scenario.phases[0])?,
+                    scenario.base_price.to_f64().context("Failed to convert base price")?,
+                ),
+            };
+
+            while let Some(phase) = scenario.phases.get(index) {
+                tracing::debug!("{} entering phase '{}'", scenario.symbol, phase.name);
+
+                loop {
+                    // A zero arrival rate is a dead market: wait out the phase.
+                    let gap = if phase.trades_per_second > 0.0 {
+                        -(1.0 - rng.random::<f64>()).ln() / phase.trades_per_second
+                    } else {
+                        f64::INFINITY
+                    };
+                    let due = Duration::try_from_secs_f64(gap)
+                        .ok()
+                        .and_then(|gap| chrono::Duration::from_std(gap).ok())
+                        .map(|gap| ticker.next_deadline(gap))
+                        .filter(|due| *due < phase_end);
+                    let Some(due) = due else {
+                        ticker.tick_at(phase_end).await;
+                        break;
+                    };
+                    ticker.tick_at(due).await;
+
+                    let mut change = phase.trend * gap + phase.volatility * standard_normal(&mut rng);
+                    if rng.random_bool(phase.jump_probability) {
+                        change += phase.jump_size;
+                    }
+                    price = (price * (1.0 + change)).max(MIN_PRICE);
+                    checkpoint.set(GeneratorState::Scenario { phase: index, phase_end, price });
+
+                    let side = if rng.random_bool(phase.buy_ratio) {
+                        TradeSide::Buy
+                    } else {
+                        TradeSide::Sell
+                    };
+
+                    let volume = phase.volume.sample(&mut rng).max(0.0);
+                    yield Ok(Transaction::new_at(
+                        random_id(&mut rng),
+                        scenario.symbol.clone(),
+                        Decimal::from_f64(price).context("Failed to convert scenario price")?,
+                        Decimal::from_f64(volume).context("Failed to convert scenario volume")?,
+                        side,
+                        clock.now(),
+                    ));
+                }
+
+                // Phases run back to back from the scripted end of the last one
+                index += 1;
+                if index == scenario.phases.len() && scenario.repeat {
+                    index = 0;
+                }
+                if let Some(next) = scenario.phases.get(index) {
+                    phase_end += phase_duration(next)?;
+                }
+                checkpoint.set(GeneratorState::Scenario { phase: index, phase_end, price });
+            }
+        })
+    }
+}
+
+fn phase_duration(phase: &Regime) -> Result<chrono::Duration> {
+    chrono::Duration::from_std(Duration::from_secs_f64(phase.duration_secs)).context("Phase duration out of range")
+}
+
+impl TransactionSource for ScenarioGenerator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open(&self) -> MarketEventStream {
+        trade_events(self.generate_transaction_stream())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::SimulatedClock;
+    use chrono::{TimeZone, Utc};
+    use futures::StreamExt;
+
+    const PUMP_AND_RUG: &str = r#"{
+        "symbols": [{
+            "symbol": "RUG",
+            "base_price": "1.0",
+            "phases": [
+                { "name": "pump", "duration_secs": 0.2, "trend": 5.0, "volatility": 0.0, "trades_per_second": 500, "buy_ratio": 1.0 },
+                { "name": "rug", "duration_secs": 0.05, "jump_probability": 1.0, "jump_size": -0.9, "volatility": 0.0, "trades_per_second": 1000,
+                  "volume": { "type": "fixed", "value": 1000 }, "buy_ratio": 0.0 }
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn test_scenario_validation() {
+        assert!(Scenario::from_json(PUMP_AND_RUG).is_ok());
+        assert!(Scenario::from_json(r#"{ "symbols": [{ "symbol": "X", "base_price": "1", "phases": [] }] }"#).is_err());
+        for phase in [
+            r#"{ "duration_secs": 10, "volume": { "type": "fixed", "value": 0 } }"#,
+            r#"{ "duration_secs": 10, "volume": { "type": "uniform", "min": -1, "max": 5 } }"#,
+            r#"{ "duration_secs": 1e300 }"#,
+            r#"{ "duration_secs": 0 }"#,
+            r#"{ "duration_secs": 10, "volatility": -0.1 }"#,
+        ] {
+            let json = format!(r#"{{ "symbols": [{{ "symbol": "X", "base_price": "1", "phases": [{}] }}] }}"#, phase);
+            assert!(Scenario::from_json(&json).is_err(), "accepted {}", phase);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scenario_keeps_to_script_and_resumes() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let scenario = Scenario::from_json(PUMP_AND_RUG).unwrap().symbols.remove(0);
+        let checkpoint = Checkpoint::default();
+        let generator = ScenarioGenerator::new(scenario)
+            .with_seed(7)
+            .with_clock(Arc::new(SimulatedClock::new(start)))
+            .with_checkpoint(checkpoint.clone());
+
+        // Stop partway through the pump, as a failing source would
+        let first: Vec<Transaction> =
+            generator.generate_transaction_stream().take(20).map(|result| result.unwrap()).collect().await;
+        let GeneratorState::Scenario { phase: 0, phase_end, .. } = checkpoint.get() else {
+            panic!("scenario state not recorded");
+        };
+        assert_eq!(phase_end, start + chrono::Duration::milliseconds(200));
+
+        // The restarted stream carries on with the pump instead of starting over
+        let rest: Vec<Transaction> =
+            generator.generate_transaction_stream().map(|result| result.unwrap()).collect().await;
+        let pump_end = start + chrono::Duration::milliseconds(200);
+        assert!(rest[0].price > first[19].price);
+        assert!(rest.iter().all(|t| t.timestamp < start + chrono::Duration::milliseconds(250)));
+        assert!(rest.iter().filter(|t| t.timestamp > pump_end).all(|t| t.side == TradeSide::Sell));
+        assert!(matches!(checkpoint.get(), GeneratorState::Scenario { phase: 2, .. }));
+
+        // A finished scenario stays finished
+        assert!(generator.generate_transaction_stream().next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scenario_runs_through_phases() {
+        let scenario = Scenario::from_json(PUMP_AND_RUG).unwrap();
+        let stream = ScenarioGenerator::new(scenario.symbols[0].clone()).generate_transaction_stream();
+        let transactions: Vec<Transaction> = stream.map(|result| result.unwrap()).collect().await;
+
+        let peak = transactions.iter().map(|t| t.price).max().unwrap();
+        let last = transactions.last().unwrap();
+        assert!(peak > Decimal::ONE);
+        assert_eq!(last.side, TradeSide::Sell);
+        assert_eq!(last.volume, Decimal::from(1000));
+        assert!(last.price < peak / Decimal::from(5));
+    }
+}
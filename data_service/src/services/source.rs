@@ -0,0 +1,123 @@
+use super::fault_injection::{FaultCounts, FaultInjector};
+use super::replay::ReplayControl;
+use super::supervisor::MarketEventStream;
+use crate::models::Transaction;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+/// Anything that can feed trades (and pool events) into the service.
+pub trait TransactionSource: Send + Sync + 'static {
+    /// Unique name, used for health reporting and stamped onto every trade.
+    fn name(&self) -> &str;
+
+    /// Venue for trades that arrive without one.
+    fn venue(&self) -> Option<&str> {
+        None
+    }
+
+    /// Opens a fresh stream. Called again after every failure, so sources that hold a
+    /// connection should reconnect here.
+    fn open(&self) -> MarketEventStream;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceState {
+    Starting,
+    Running,
+    /// Failed and waiting before the next restart.
+    Backoff,
+    /// The stream ended cleanly and will not be restarted.
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealth {
+    pub name: String,
+    pub state: SourceState,
+    pub restarts: u64,
+    pub events: u64,
+    /// Trades dropped because they failed validation.
+    pub rejected: u64,
+    pub last_event_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// What the source's fault injector has done so far, across restarts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faults: Option<FaultCounts>,
+}
+
+impl SourceHealth {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: SourceState::Starting,
+            restarts: 0,
+            events: 0,
+            rejected: 0,
+            last_event_at: None,
+            last_error: None,
+            faults: None,
+        }
+    }
+}
+
+/// Health of every supervised source, plus the channels behind HTTP push sources.
+#[derive(Default)]
+pub struct SourceRegistry {
+    health: DashMap<String, SourceHealth>,
+    push_senders: DashMap<String, mpsc::Sender<Transaction>>,
+    replays: DashMap<String, ReplayControl>,
+    faults: DashMap<String, FaultInjector>,
+}
+
+impl SourceRegistry {
+    pub fn health(&self) -> Vec<SourceHealth> {
+        let mut health: Vec<SourceHealth> = self.health.iter().map(|entry| self.with_faults(entry.value())).collect();
+        health.sort_by(|a, b| a.name.cmp(&b.name));
+        health
+    }
+
+    pub fn source_health(&self, name: &str) -> Option<SourceHealth> {
+        self.health.get(name).map(|entry| self.with_faults(entry.value()))
+    }
+
+    fn with_faults(&self, health: &SourceHealth) -> SourceHealth {
+        let mut health = health.clone();
+        health.faults = self.faults.get(&health.name).map(|injector| injector.counts());
+        health
+    }
+
+    pub(crate) fn update(&self, name: &str, f: impl FnOnce(&mut SourceHealth)) {
+        let mut entry = self.health.entry(name.to_string()).or_insert_with(|| SourceHealth::new(name));
+        f(entry.value_mut());
+    }
+
+    pub(crate) fn register_push(&self, name: &str, sender: mpsc::Sender<Transaction>) {
+        self.push_senders.insert(name.to_string(), sender);
+    }
+
+    pub fn push_sender(&self, name: &str) -> Option<mpsc::Sender<Transaction>> {
+        self.push_senders.get(name).map(|entry| entry.value().clone())
+    }
+
+    pub(crate) fn register_replay(&self, name: &str, control: ReplayControl) {
+        self.replays.insert(name.to_string(), control);
+    }
+
+    pub(crate) fn register_faults(&self, name: &str, injector: FaultInjector) {
+        self.faults.insert(name.to_string(), injector);
+    }
+
+    pub fn replay_control(&self, name: &str) -> Option<ReplayControl> {
+        self.replays.get(name).map(|entry| entry.value().clone())
+    }
+
+    /// Names of the registered replays, sorted.
+    pub fn replay_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.replays.iter().map(|entry| entry.key().clone()).collect();
+        names.sort();
+        names
+    }
+}
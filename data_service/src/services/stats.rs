@@ -0,0 +1,335 @@
+use crate::models::{KLine, KLineInterval, ReturnStats};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+/// Longest window a stats request can ask for, matching the candle history kept.
+pub const MAX_STATS_WINDOW: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StatsConfig {
+    /// Windows, in candles, kept up to date as candles close. Other windows are computed
+    /// from the candle history on request.
+    pub windows: Vec<usize>,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self { windows: vec![20, 100] }
+    }
+}
+
+impl StatsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (index, window) in self.windows.iter().enumerate() {
+            if !(2..=MAX_STATS_WINDOW).contains(window) {
+                bail!("Stats windows must be between 2 and {} candles", MAX_STATS_WINDOW);
+            }
+            if self.windows[..index].contains(window) {
+                bail!("Stats window {} is configured more than once", window);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a candle contributes to the window sums.
+struct CandleTerms {
+    open_time: DateTime<Utc>,
+    close_time: DateTime<Utc>,
+    close: f64,
+    /// Log return from the previous candle's close, if there was one.
+    log_return: Option<f64>,
+    /// `ln(high / low)^2`.
+    parkinson: f64,
+    /// `0.5 ln(high / low)^2 - (2 ln 2 - 1) ln(close / open)^2`.
+    garman_klass: f64,
+}
+
+impl CandleTerms {
+    fn new(kline: &KLine, previous_close: Option<f64>) -> Option<Self> {
+        let open = kline.ohlcv.open.to_f64()?;
+        let high = kline.ohlcv.high.to_f64()?;
+        let low = kline.ohlcv.low.to_f64()?;
+        let close = kline.ohlcv.close.to_f64()?;
+        if open <= 0.0 || low <= 0.0 || close <= 0.0 {
+            return None;
+        }
+        let range = (high / low).ln().powi(2);
+        let body = (close / open).ln().powi(2);
+        Some(Self {
+            open_time: kline.open_time,
+            close_time: kline.close_time,
+            close,
+            log_return: previous_close.map(|previous| (close / previous).ln()),
+            parkinson: range,
+            garman_klass: 0.5 * range - (2.0 * std::f64::consts::LN_2 - 1.0) * body,
+        })
+    }
+}
+
+/// Running sums over a window. Returns are summed from its second candle on, since the
+/// first one's return reaches back outside it.
+#[derive(Default)]
+struct Sums {
+    returns: usize,
+    r1: f64,
+    r2: f64,
+    r3: f64,
+    parkinson: f64,
+    garman_klass: f64,
+}
+
+impl Sums {
+    fn add_return(&mut self, r: f64) {
+        self.returns += 1;
+        self.r1 += r;
+        self.r2 += r * r;
+        self.r3 += r * r * r;
+    }
+
+    fn remove_return(&mut self, r: f64) {
+        self.returns -= 1;
+        self.r1 -= r;
+        self.r2 -= r * r;
+        self.r3 -= r * r * r;
+    }
+}
+
+/// Statistics over the last `size` closed candles of a series, updated in O(1) per candle
+/// apart from the drawdown, which is taken over the window's closes when read.
+struct RollingWindow {
+    size: usize,
+    candles: VecDeque<CandleTerms>,
+    sums: Sums,
+    /// Candles pushed since the sums were last rebuilt, to keep rounding from drifting.
+    pushes: usize,
+}
+
+impl RollingWindow {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            candles: VecDeque::with_capacity(size + 1),
+            sums: Sums::default(),
+            pushes: 0,
+        }
+    }
+
+    fn push(&mut self, kline: &KLine) {
+        let previous_close = self.candles.back().map(|candle| candle.close);
+        let Some(candle) = CandleTerms::new(kline, previous_close) else {
+            return;
+        };
+        if !self.candles.is_empty() {
+            if let Some(r) = candle.log_return {
+                self.sums.add_return(r);
+            }
+        }
+        self.sums.parkinson += candle.parkinson;
+        self.sums.garman_klass += candle.garman_klass;
+        self.candles.push_back(candle);
+
+        if self.candles.len() > self.size {
+            if let Some(dropped) = self.candles.pop_front() {
+                self.sums.parkinson -= dropped.parkinson;
+                self.sums.garman_klass -= dropped.garman_klass;
+            }
+            // The new first candle's return now reaches outside the window
+            if let Some(r) = self.candles.front().and_then(|candle| candle.log_return) {
+                self.sums.remove_return(r);
+            }
+        }
+
+        self.pushes += 1;
+        if self.pushes >= self.size {
+            self.rebuild();
+        }
+    }
+
+    fn rebuild(&mut self) {
+        let mut sums = Sums::default();
+        for (index, candle) in self.candles.iter().enumerate() {
+            if index > 0 {
+                if let Some(r) = candle.log_return {
+                    sums.add_return(r);
+                }
+            }
+            sums.parkinson += candle.parkinson;
+            sums.garman_klass += candle.garman_klass;
+        }
+        self.sums = sums;
+        self.pushes = 0;
+    }
+
+    fn stats(&self, venue: &str, symbol: &str, interval: KLineInterval) -> ReturnStats {
+        let periods_per_year = SECONDS_PER_YEAR / interval.as_seconds() as f64;
+        let candles = self.candles.len() as f64;
+        let sums = &self.sums;
+        let returns = sums.returns as f64;
+
+        let mean = (sums.returns > 0).then(|| sums.r1 / returns);
+        // Population variance; clamped since rounding can take it just below zero
+        let variance = mean.map(|mean| (sums.r2 / returns - mean * mean).max(0.0));
+        let stdev = variance.filter(|_| sums.returns > 1).map(|variance| (variance * returns / (returns - 1.0)).sqrt());
+        let skew = mean.zip(variance).filter(|(_, variance)| *variance > 0.0).map(|(mean, variance)| {
+            let third = sums.r3 / returns - 3.0 * mean * sums.r2 / returns + 2.0 * mean.powi(3);
+            third / variance.powf(1.5)
+        });
+
+        let mut peak = f64::MIN;
+        let mut max_drawdown: f64 = 0.0;
+        for candle in &self.candles {
+            peak = peak.max(candle.close);
+            max_drawdown = max_drawdown.max((peak - candle.close) / peak);
+        }
+
+        let has_candles = !self.candles.is_empty();
+        ReturnStats {
+            venue: venue.to_string(),
+            symbol: symbol.to_string(),
+            interval,
+            window: self.size,
+            candles: self.candles.len(),
+            from: self.candles.front().map(|candle| candle.open_time),
+            to: self.candles.back().map(|candle| candle.close_time),
+            close_to_close: stdev.map(|stdev| stdev * periods_per_year.sqrt()),
+            parkinson: has_candles.then(|| {
+                (sums.parkinson.max(0.0) / (4.0 * std::f64::consts::LN_2 * candles) * periods_per_year).sqrt()
+            }),
+            garman_klass: has_candles.then(|| (sums.garman_klass.max(0.0) / candles * periods_per_year).sqrt()),
+            mean_return: mean,
+            stdev_return: stdev,
+            skew,
+            max_drawdown: has_candles.then_some(max_drawdown),
+        }
+    }
+}
+
+/// Statistics over `window` candles, computed from scratch.
+pub fn compute_stats(klines: &[KLine], window: usize) -> Option<ReturnStats> {
+    let first = klines.first()?;
+    let mut rolling = RollingWindow::new(window);
+    for kline in &klines[klines.len().saturating_sub(window)..] {
+        rolling.push(kline);
+    }
+    Some(rolling.stats(&first.venue, &first.symbol, first.interval))
+}
+
+/// Keeps the configured windows of every candle series up to date as candles close.
+#[derive(Default)]
+pub struct StatsTracker {
+    windows: Vec<usize>,
+    series: DashMap<(String, String, KLineInterval), Vec<RollingWindow>>,
+}
+
+impl StatsTracker {
+    pub fn new(config: StatsConfig) -> Self {
+        Self {
+            windows: config.windows,
+            series: DashMap::new(),
+        }
+    }
+
+    pub fn tracks(&self, window: usize) -> bool {
+        self.windows.contains(&window)
+    }
+
+    pub fn default_window(&self) -> Option<usize> {
+        self.windows.first().copied()
+    }
+
+    pub fn on_close(&self, kline: &KLine) {
+        let mut windows = self
+            .series
+            .entry((kline.venue.clone(), kline.symbol.clone(), kline.interval))
+            .or_insert_with(|| self.windows.iter().map(|size| RollingWindow::new(*size)).collect());
+        for window in windows.iter_mut() {
+            window.push(kline);
+        }
+    }
+
+    /// Statistics for a tracked `window`; `None` if the window is not tracked or the
+    /// series has no closed candles yet.
+    pub fn get(&self, venue: &str, symbol: &str, interval: KLineInterval, window: usize) -> Option<ReturnStats> {
+        let windows = self.series.get(&(venue.to_string(), symbol.to_string(), interval))?;
+        windows
+            .iter()
+            .find(|rolling| rolling.size == window)
+            .map(|rolling| rolling.stats(venue, symbol, interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use chrono::TimeZone;
+    use rust_decimal::Decimal;
+
+    fn candles(closes: &[i64]) -> Vec<KLine> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut previous = closes[0];
+        closes
+            .iter()
+            .enumerate()
+            .map(|(index, close)| {
+                let open_time = start + chrono::Duration::hours(index as i64);
+                let mut kline = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneHour, open_time, Decimal::new(previous, 0));
+                kline.update(Decimal::new(previous.max(*close) + 2, 0), Decimal::ONE, TradeSide::Buy);
+                kline.update(Decimal::new(previous.min(*close) - 1, 0), Decimal::ONE, TradeSide::Sell);
+                kline.update(Decimal::new(*close, 0), Decimal::ONE, TradeSide::Buy);
+                kline.close();
+                previous = *close;
+                kline
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stats_over_window() {
+        let klines = candles(&[100, 110, 99, 121]);
+        let stats = compute_stats(&klines, 4).unwrap();
+        let returns = [(110.0f64 / 100.0).ln(), (99.0f64 / 110.0).ln(), (121.0f64 / 99.0).ln()];
+        let mean = returns.iter().sum::<f64>() / 3.0;
+        let stdev = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / 2.0).sqrt();
+
+        assert_eq!(stats.candles, 4);
+        assert!((stats.mean_return.unwrap() - mean).abs() < 1e-12);
+        assert!((stats.stdev_return.unwrap() - stdev).abs() < 1e-12);
+        assert!((stats.close_to_close.unwrap() - stdev * (365.0f64 * 24.0).sqrt()).abs() < 1e-9);
+        assert!((stats.max_drawdown.unwrap() - 0.1).abs() < 1e-12);
+        assert!(stats.skew.is_some());
+        assert!(stats.parkinson.unwrap() > 0.0 && stats.garman_klass.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_incremental_matches_history() {
+        let closes: Vec<i64> = (0..60).map(|index| 100 + (index * 7 % 13) - (index % 5) * 2).collect();
+        let klines = candles(&closes);
+        let tracker = StatsTracker::new(StatsConfig { windows: vec![20] });
+        for kline in &klines {
+            tracker.on_close(kline);
+        }
+
+        let incremental = tracker.get("default", "DOGE", KLineInterval::OneHour, 20).unwrap();
+        let computed = compute_stats(&klines, 20).unwrap();
+        assert_eq!(incremental.candles, 20);
+        assert_eq!(incremental.from, computed.from);
+        for (a, b) in [
+            (incremental.close_to_close, computed.close_to_close),
+            (incremental.parkinson, computed.parkinson),
+            (incremental.garman_klass, computed.garman_klass),
+            (incremental.skew, computed.skew),
+            (incremental.max_drawdown, computed.max_drawdown),
+        ] {
+            assert!((a.unwrap() - b.unwrap()).abs() < 1e-9);
+        }
+        assert!(tracker.get("default", "DOGE", KLineInterval::OneHour, 100).is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use super::source::{SourceState, TransactionSource};
+use super::DataService;
+use crate::models::{MarketEvent, Transaction};
+use anyhow::{anyhow, Result};
+use futures::{Stream, StreamExt};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{pin::Pin, sync::Arc};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+pub type TransactionStream = Pin<Box<dyn Stream<Item = Result<Transaction>> + Send>>;
+pub type MarketEventStream = Pin<Box<dyn Stream<Item = Result<MarketEvent>> + Send>>;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Wraps a trade-only stream so it can be supervised alongside pool-style sources.
+pub fn trade_events(stream: TransactionStream) -> MarketEventStream {
+    Box::pin(stream.map(|result| result.map(MarketEvent::Trade)))
+}
+
+/// Runs ingestion sources concurrently and feeds their output into the [`DataService`].
+///
+/// Every trade is stamped with its source name and validated before processing. Failed
+/// sources are reopened with exponential backoff, and their health is published through
+/// [`DataService::sources`].
+pub struct Supervisor {
+    data_service: Arc<DataService>,
+}
+
+impl Supervisor {
+    pub fn new(data_service: Arc<DataService>) -> Self {
+        Self { data_service }
+    }
+
+    /// Spawns a task that drives `source`. When its stream yields an error or panics it
+    /// is reopened after a backoff; a stream that simply ends is not restarted.
+    pub fn spawn(&self, source: Arc<dyn TransactionSource>) -> JoinHandle<()> {
+        let data_service = self.data_service.clone();
+        let name = source.name().to_string();
+        data_service.sources().update(&name, |_| {});
+
+        tokio::spawn(async move {
+            let registry = data_service.sources();
+            let mut backoff = INITIAL_BACKOFF;
+
+            loop {
+                // Each run gets its own task so that a panic in the source surfaces here as
+                // a JoinError instead of silently ending the feed.
+                let delivered = Arc::new(AtomicBool::new(false));
+                let run = tokio::spawn(run_source(source.clone(), data_service.clone(), delivered.clone()));
+                let err = match run.await {
+                    Ok(None) => {
+                        tracing::info!("Source {} finished", name);
+                        registry.update(&name, |health| health.state = SourceState::Finished);
+                        return;
+                    }
+                    Ok(Some(err)) => err,
+                    Err(err) if err.is_panic() => anyhow!("Source panicked: {}", panic_message(err.into_panic())),
+                    Err(_) => return,
+                };
+
+                // A source that made progress before failing starts its backoff over.
+                if delivered.load(Ordering::Relaxed) {
+                    backoff = INITIAL_BACKOFF;
+                }
+                tracing::error!("Source {} failed, restarting in {:?}: {:#}", name, backoff, err);
+                registry.update(&name, |health| {
+                    health.state = SourceState::Backoff;
+                    health.restarts += 1;
+                    health.last_error = Some(format!("{:#}", err));
+                });
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        })
+    }
+}
+
+/// Opens `source` and feeds its events into `data_service` until the stream ends.
+/// Returns the error that ended it, if any.
+async fn run_source(
+    source: Arc<dyn TransactionSource>,
+    data_service: Arc<DataService>,
+    delivered: Arc<AtomicBool>,
+) -> Option<anyhow::Error> {
+    let registry = data_service.sources();
+    let name = source.name();
+    let venue = source.venue();
+    let mut stream = source.open();
+    registry.update(name, |health| health.state = SourceState::Running);
+
+    while let Some(result) = stream.next().await {
+        let mut event = match result {
+            Ok(event) => event,
+            Err(err) => return Some(err),
+        };
+
+        if let MarketEvent::Trade(transaction) = &mut event {
+            transaction.source = Some(name.to_string());
+            if transaction.venue.is_none() {
+                transaction.venue = venue.map(str::to_string);
+            }
+            if let Err(err) = transaction.validate(data_service.now()) {
+                tracing::warn!("Rejected trade from {}: {:#}", name, err);
+                registry.update(name, |health| health.rejected += 1);
+                continue;
+            }
+        }
+
+        if let Err(err) = data_service.process_market_event(&event) {
+            tracing::error!("Failed to process event from {}: {:#}", name, err);
+        }
+        delivered.store(true, Ordering::Relaxed);
+        let now = data_service.now();
+        registry.update(name, |health| {
+            health.events += 1;
+            health.last_event_at = Some(now);
+        });
+    }
+    None
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+    use rust_decimal::Decimal;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct FlakySource {
+        attempts: AtomicU32,
+    }
+
+    impl TransactionSource for FlakySource {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        fn open(&self) -> MarketEventStream {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            let valid = Transaction::new("FLAKY".to_string(), Decimal::ONE, Decimal::ONE, TradeSide::Buy);
+            let invalid = Transaction::new("FLAKY".to_string(), Decimal::ZERO, Decimal::ONE, TradeSide::Buy);
+            let items: Vec<Result<Transaction>> = if attempt == 0 {
+                vec![Ok(valid), Ok(invalid), Err(anyhow::anyhow!("upstream hiccup"))]
+            } else {
+                vec![Ok(valid)]
+            };
+            trade_events(Box::pin(futures::stream::iter(items)))
+        }
+    }
+
+    struct PanickySource {
+        attempts: AtomicU32,
+    }
+
+    impl TransactionSource for PanickySource {
+        fn name(&self) -> &str {
+            "panicky"
+        }
+
+        fn open(&self) -> MarketEventStream {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            let valid = Transaction::new("PANICKY".to_string(), Decimal::ONE, Decimal::ONE, TradeSide::Buy);
+            trade_events(Box::pin(futures::stream::iter([Ok(valid)]).map(move |result| {
+                if attempt == 0 {
+                    panic!("generator bug");
+                }
+                result
+            })))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_failed_source() {
+        let data_service = Arc::new(DataService::new());
+        let source = Arc::new(FlakySource {
+            attempts: AtomicU32::new(0),
+        });
+        let mut rx = data_service.subscribe_transactions();
+
+        Supervisor::new(data_service.clone()).spawn(source.clone()).await.unwrap();
+
+        // One restart after the error, then the second stream ends cleanly.
+        assert_eq!(source.attempts.load(Ordering::SeqCst), 2);
+        let first = rx.try_recv().unwrap();
+        assert_eq!(first.source.as_deref(), Some("flaky"));
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+
+        let health = data_service.sources().source_health("flaky").unwrap();
+        assert_eq!(health.state, SourceState::Finished);
+        assert_eq!(health.restarts, 1);
+        assert_eq!(health.events, 2);
+        assert_eq!(health.rejected, 1);
+        assert_eq!(health.last_error.as_deref(), Some("upstream hiccup"));
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_restarts_panicked_source() {
+        let data_service = Arc::new(DataService::new());
+        let source = Arc::new(PanickySource {
+            attempts: AtomicU32::new(0),
+        });
+
+        Supervisor::new(data_service.clone()).spawn(source.clone()).await.unwrap();
+
+        assert_eq!(source.attempts.load(Ordering::SeqCst), 2);
+        let health = data_service.sources().source_health("panicky").unwrap();
+        assert_eq!(health.state, SourceState::Finished);
+        assert_eq!(health.restarts, 1);
+        assert_eq!(health.events, 1);
+        assert_eq!(health.last_error.as_deref(), Some("Source panicked: generator bug"));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::models::Transaction;
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Source name stamped onto derived trades.
+const SYNTHETIC_SOURCE: &str = "synthetic";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntheticOp {
+    /// `left * right`, e.g. `TOKEN-SOL * SOL-USD = TOKEN-USD`.
+    Product,
+    /// `left / right`, e.g. `TOKEN-USD / SOL-USD = TOKEN-SOL`.
+    Ratio,
+}
+
+/// One input of a synthetic symbol: a symbol, optionally pinned to a venue.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "LegSpec")]
+pub struct SyntheticLeg {
+    pub symbol: String,
+    /// Any venue when omitted.
+    pub venue: Option<String>,
+}
+
+/// Legs may be written as a bare symbol or as `{ "symbol": ..., "venue": ... }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LegSpec {
+    Symbol(String),
+    Full { symbol: String, venue: Option<String> },
+}
+
+impl From<LegSpec> for SyntheticLeg {
+    fn from(spec: LegSpec) -> Self {
+        match spec {
+            LegSpec::Symbol(symbol) => SyntheticLeg { symbol, venue: None },
+            LegSpec::Full { symbol, venue } => SyntheticLeg { symbol, venue },
+        }
+    }
+}
+
+impl SyntheticLeg {
+    fn matches(&self, transaction: &Transaction) -> bool {
+        transaction.symbol == self.symbol
+            && self.venue.as_ref().is_none_or(|venue| transaction.venue.as_ref() == Some(venue))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyntheticConfig {
+    pub symbol: String,
+    pub op: SyntheticOp,
+    /// The leg whose trades carry volume; its volume is passed through unchanged.
+    pub left: SyntheticLeg,
+    /// Updates from this leg only move the price and are published with zero volume.
+    pub right: SyntheticLeg,
+    /// Venue for the derived trades; the default venue when omitted.
+    #[serde(default)]
+    pub venue: Option<String>,
+}
+
+impl SyntheticConfig {
+    /// Rejects synthetic symbols that feed into themselves, directly or through other
+    /// synthetic symbols.
+    pub fn validate_all(configs: &[SyntheticConfig]) -> Result<()> {
+        let legs: HashMap<&str, [&str; 2]> = configs
+            .iter()
+            .map(|config| (config.symbol.as_str(), [config.left.symbol.as_str(), config.right.symbol.as_str()]))
+            .collect();
+        if legs.len() != configs.len() {
+            bail!("A synthetic symbol is configured more than once");
+        }
+
+        for config in configs {
+            let mut seen = HashSet::new();
+            let mut pending = vec![config.symbol.as_str()];
+            while let Some(symbol) = pending.pop() {
+                for leg in legs.get(symbol).into_iter().flatten() {
+                    if *leg == config.symbol {
+                        bail!("Synthetic symbol {} depends on itself", config.symbol);
+                    }
+                    if seen.insert(*leg) {
+                        pending.push(leg);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Derives trades of a synthetic symbol from the last prices of its two legs.
+pub struct SyntheticSymbol {
+    config: SyntheticConfig,
+    /// Last left and right prices.
+    last_prices: Mutex<(Option<Decimal>, Option<Decimal>)>,
+}
+
+impl SyntheticSymbol {
+    pub fn new(config: SyntheticConfig) -> Self {
+        Self {
+            config,
+            last_prices: Mutex::new((None, None)),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.config.symbol
+    }
+
+    /// Records a leg trade and returns the derived trade, once both legs have a price.
+    pub fn on_trade(&self, transaction: &Transaction) -> Option<Transaction> {
+        // Never react to our own output, even if someone configures it as a leg.
+        if transaction.symbol == self.config.symbol {
+            return None;
+        }
+        let is_left = self.config.left.matches(transaction);
+        let is_right = self.config.right.matches(transaction);
+        if !is_left && !is_right {
+            return None;
+        }
+
+        let (left, right) = {
+            let mut last_prices = self.last_prices.lock().expect("Synthetic symbol lock poisoned");
+            if is_left {
+                last_prices.0 = Some(transaction.price);
+            }
+            if is_right {
+                last_prices.1 = Some(transaction.price);
+            }
+            (last_prices.0?, last_prices.1?)
+        };
+
+        let price = match self.config.op {
+            SyntheticOp::Product => left.checked_mul(right)?,
+            SyntheticOp::Ratio => left.checked_div(right)?,
+        };
+        let volume = if is_left { transaction.volume } else { Decimal::ZERO };
+
+        let mut derived = Transaction::new_at(
+            Uuid::new_v4(),
+            self.config.symbol.clone(),
+            price.normalize(),
+            volume,
+            transaction.side,
+            transaction.timestamp,
+        );
+        derived.venue = self.config.venue.clone();
+        derived.source = Some(SYNTHETIC_SOURCE.to_string());
+        Some(derived)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+
+    fn config(symbol: &str, op: SyntheticOp, left: &str, right: &str) -> SyntheticConfig {
+        SyntheticConfig {
+            symbol: symbol.to_string(),
+            op,
+            left: SyntheticLeg::from(LegSpec::Symbol(left.to_string())),
+            right: SyntheticLeg::from(LegSpec::Symbol(right.to_string())),
+            venue: None,
+        }
+    }
+
+    fn trade(symbol: &str, price: &str, volume: i64) -> Transaction {
+        Transaction::new(symbol.to_string(), price.parse().unwrap(), Decimal::new(volume, 0), TradeSide::Buy)
+    }
+
+    #[test]
+    fn test_product_updates_on_either_leg() {
+        let synthetic = SyntheticSymbol::new(config("TOKEN-USD", SyntheticOp::Product, "TOKEN-SOL", "SOL-USD"));
+
+        assert!(synthetic.on_trade(&trade("TOKEN-SOL", "0.002", 500)).is_none());
+        let derived = synthetic.on_trade(&trade("SOL-USD", "150", 3)).unwrap();
+        assert_eq!((derived.price, derived.volume), ("0.3".parse().unwrap(), Decimal::ZERO));
+
+        let derived = synthetic.on_trade(&trade("TOKEN-SOL", "0.004", 200)).unwrap();
+        assert_eq!((derived.price, derived.volume), ("0.6".parse().unwrap(), Decimal::new(200, 0)));
+        assert_eq!(derived.symbol, "TOKEN-USD");
+        assert!(synthetic.on_trade(&trade("OTHER", "1", 1)).is_none());
+    }
+
+    #[test]
+    fn test_rejects_cycles() {
+        let ratio = config("TOKEN-SOL", SyntheticOp::Ratio, "TOKEN-USD", "SOL-USD");
+        let product = config("TOKEN-USD", SyntheticOp::Product, "TOKEN-SOL", "SOL-USD");
+        assert!(SyntheticConfig::validate_all(std::slice::from_ref(&ratio)).is_ok());
+        assert!(SyntheticConfig::validate_all(&[ratio, product]).is_err());
+
+        let leg: SyntheticLeg = serde_json::from_str(r#"{ "symbol": "SOL-USD", "venue": "pyth" }"#).unwrap();
+        assert_eq!(leg.venue.as_deref(), Some("pyth"));
+    }
+}
@@ -0,0 +1,275 @@
+use super::source::TransactionSource;
+use super::supervisor::MarketEventStream;
+use super::DataService;
+use crate::models::{MarketEvent, Transaction};
+use anyhow::{Context, Result};
+use async_stream::stream;
+use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex, Semaphore};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TcpIngestConfig {
+    pub addr: String,
+    #[serde(default = "default_name")]
+    pub name: String,
+    /// Trades buffered between the listener and the pipeline. Once full, connections
+    /// stop being read until there is room again.
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// Longer lines are answered with an error and the connection is closed.
+    #[serde(default = "default_max_line_bytes")]
+    pub max_line_bytes: usize,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+}
+
+fn default_name() -> String {
+    "tcp-ingest".to_string()
+}
+
+fn default_capacity() -> usize {
+    10_000
+}
+
+/// Longest newline-delimited line read from a socket unless configured otherwise.
+pub(crate) const DEFAULT_MAX_LINE_BYTES: usize = 64 * 1024;
+
+pub(crate) fn default_max_line_bytes() -> usize {
+    DEFAULT_MAX_LINE_BYTES
+}
+
+/// Outcome of [`read_line`].
+pub(crate) enum LineRead {
+    /// A line, possibly without its newline at the end of the stream, is in the buffer.
+    Line,
+    /// The line exceeded the limit; the buffer holds its first bytes.
+    TooLong,
+    Eof,
+}
+
+/// Reads one line into `buffer`, which is cleared first, without buffering more than
+/// `max_line_bytes` of it, so a peer that never sends a newline can't exhaust memory.
+pub(crate) async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    max_line_bytes: usize,
+) -> std::io::Result<LineRead> {
+    buffer.clear();
+    // Read one byte past the limit so an oversized line can be told apart.
+    let read = reader.take(max_line_bytes as u64 + 1).read_until(b'\n', buffer).await?;
+    if read == 0 {
+        return Ok(LineRead::Eof);
+    }
+    if !buffer.ends_with(b"\n") && buffer.len() > max_line_bytes {
+        return Ok(LineRead::TooLong);
+    }
+    Ok(LineRead::Line)
+}
+
+fn default_max_connections() -> usize {
+    64
+}
+
+/// Accepts newline-delimited `Transaction` JSON from producers on persistent connections.
+///
+/// Every non-empty line is answered with an ack line, `{"line": n, "ok": true, "id": ...}`
+/// or `{"line": n, "ok": false, "error": ...}`. A line is only acked once its trade is
+/// queued, so producers that cap their unacked lines never outrun the pipeline, and
+/// those that don't are held back by TCP flow control.
+pub struct TcpIngestSource {
+    name: String,
+    local_addr: SocketAddr,
+    receiver: Arc<Mutex<mpsc::Receiver<Transaction>>>,
+}
+
+impl TcpIngestSource {
+    /// Binds the listener and starts accepting connections.
+    pub async fn bind(config: TcpIngestConfig, data_service: Arc<DataService>) -> Result<Self> {
+        let listener = TcpListener::bind(&config.addr)
+            .await
+            .with_context(|| format!("Failed to bind TCP ingest listener to {}", config.addr))?;
+        let local_addr = listener.local_addr()?;
+        let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+
+        let name = config.name.clone();
+        tokio::spawn(async move {
+            let connections = Arc::new(Semaphore::new(config.max_connections.max(1)));
+            loop {
+                let permit = connections.clone().acquire_owned().await.expect("Semaphore is never closed");
+                let (socket, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        tracing::warn!("Failed to accept TCP ingest connection: {}", err);
+                        continue;
+                    }
+                };
+                tracing::info!("TCP ingest connection from {}", peer);
+                let sender = sender.clone();
+                let data_service = data_service.clone();
+                let max_line_bytes = config.max_line_bytes;
+                tokio::spawn(async move {
+                    if let Err(err) = handle_connection(socket, sender, data_service, max_line_bytes).await {
+                        tracing::warn!("TCP ingest connection from {} failed: {:#}", peer, err);
+                    }
+                    drop(permit);
+                });
+            }
+        });
+
+        Ok(Self {
+            name,
+            local_addr,
+            receiver: Arc::new(Mutex::new(receiver)),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+async fn handle_connection(
+    socket: TcpStream,
+    sender: mpsc::Sender<Transaction>,
+    data_service: Arc<DataService>,
+    max_line_bytes: usize,
+) -> Result<()> {
+    let (reader, mut writer) = socket.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    let mut line_number = 0u64;
+
+    loop {
+        let read = read_line(&mut reader, &mut buffer, max_line_bytes).await?;
+        if let LineRead::Eof = read {
+            return Ok(());
+        }
+        line_number += 1;
+
+        if let LineRead::TooLong = read {
+            let ack = json!({ "line": line_number, "ok": false, "error": "Line too long" });
+            writer.write_all(format!("{}\n", ack).as_bytes()).await?;
+            return Ok(());
+        }
+        let line = String::from_utf8_lossy(&buffer);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let transaction = serde_json::from_str::<Transaction>(&line)
+            .context("Invalid transaction JSON")
+            .and_then(|transaction| transaction.validate(data_service.now()).map(|_| transaction));
+        let ack = match transaction {
+            Ok(transaction) => {
+                let id = transaction.id;
+                if sender.send(transaction).await.is_err() {
+                    let ack = json!({ "line": line_number, "ok": false, "error": "Service is shutting down" });
+                    writer.write_all(format!("{}\n", ack).as_bytes()).await?;
+                    return Ok(());
+                }
+                json!({ "line": line_number, "ok": true, "id": id })
+            }
+            Err(err) => json!({ "line": line_number, "ok": false, "error": format!("{:#}", err) }),
+        };
+        writer.write_all(format!("{}\n", ack).as_bytes()).await?;
+    }
+}
+
+impl TransactionSource for TcpIngestSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open(&self) -> MarketEventStream {
+        let receiver = self.receiver.clone();
+        Box::pin(stream! {
+            let mut receiver = receiver.lock_owned().await;
+            while let Some(transaction) = receiver.recv().await {
+                yield Ok(MarketEvent::Trade(transaction));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use serde_json::Value;
+    use std::time::Duration;
+    use tokio::io::Lines;
+    use tokio::net::tcp::OwnedReadHalf;
+
+    fn config(capacity: usize) -> TcpIngestConfig {
+        TcpIngestConfig {
+            addr: "127.0.0.1:0".to_string(),
+            name: default_name(),
+            capacity,
+            max_line_bytes: 512,
+            max_connections: 4,
+        }
+    }
+
+    fn trade_line(price: &str) -> String {
+        format!(
+            "{{\"symbol\":\"DOGE\",\"price\":\"{}\",\"volume\":\"5\",\"timestamp\":\"2024-01-01T00:00:00Z\",\"side\":\"buy\"}}\n",
+            price
+        )
+    }
+
+    async fn next_ack(acks: &mut Lines<BufReader<OwnedReadHalf>>) -> Value {
+        let line = acks.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_acks_each_line() {
+        let source = TcpIngestSource::bind(config(16), Arc::new(DataService::new())).await.unwrap();
+        let (reader, mut writer) = TcpStream::connect(source.local_addr()).await.unwrap().into_split();
+        let mut acks = BufReader::new(reader).lines();
+
+        let input = format!("{}\n{}not json\n{}", trade_line("0.1"), trade_line("0"), "x".repeat(600));
+        writer.write_all(input.as_bytes()).await.unwrap();
+
+        let ok = next_ack(&mut acks).await;
+        assert_eq!(ok["line"], 1);
+        assert_eq!(ok["ok"], true);
+        let invalid = next_ack(&mut acks).await;
+        assert_eq!((invalid["line"].as_u64(), invalid["ok"].as_bool()), (Some(3), Some(false)));
+        assert!(invalid["error"].as_str().unwrap().contains("non-positive price"));
+        assert_eq!(next_ack(&mut acks).await["ok"], false);
+        assert_eq!(next_ack(&mut acks).await["error"], "Line too long");
+        assert!(acks.next_line().await.unwrap().is_none());
+
+        match source.open().next().await {
+            Some(Ok(MarketEvent::Trade(transaction))) => assert_eq!(ok["id"], transaction.id.to_string()),
+            _ => panic!("expected the accepted trade"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_holds_back_acks() {
+        let source = TcpIngestSource::bind(config(2), Arc::new(DataService::new())).await.unwrap();
+        let (reader, mut writer) = TcpStream::connect(source.local_addr()).await.unwrap().into_split();
+        let mut acks = BufReader::new(reader).lines();
+
+        for _ in 0..4 {
+            writer.write_all(trade_line("0.1").as_bytes()).await.unwrap();
+        }
+        assert_eq!(next_ack(&mut acks).await["line"], 1);
+        assert_eq!(next_ack(&mut acks).await["line"], 2);
+        // The third trade waits for room in the queue.
+        assert!(tokio::time::timeout(Duration::from_millis(100), acks.next_line()).await.is_err());
+
+        let mut stream = source.open();
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(next_ack(&mut acks).await["line"], 3);
+        stream.next().await.unwrap().unwrap();
+        assert_eq!(next_ack(&mut acks).await["line"], 4);
+    }
+}
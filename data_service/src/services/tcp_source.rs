@@ -0,0 +1,130 @@
+use super::source::TransactionSource;
+use super::supervisor::MarketEventStream;
+use super::tcp_ingest::{read_line, LineRead, DEFAULT_MAX_LINE_BYTES};
+use crate::models::{MarketEvent, Transaction};
+use anyhow::{anyhow, Context};
+use async_stream::stream;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+
+/// Connects to a producer that writes one JSON `Transaction` per line.
+///
+/// Lines that don't parse are skipped. A closed connection or a line longer than
+/// `max_line_bytes` is reported as a failure so the supervisor reconnects.
+pub struct TcpNdjsonSource {
+    name: String,
+    addr: String,
+    max_line_bytes: usize,
+}
+
+impl TcpNdjsonSource {
+    pub fn new(name: &str, addr: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            addr: addr.to_string(),
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+        }
+    }
+
+    pub fn with_max_line_bytes(mut self, max_line_bytes: usize) -> Self {
+        self.max_line_bytes = max_line_bytes;
+        self
+    }
+}
+
+impl TransactionSource for TcpNdjsonSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open(&self) -> MarketEventStream {
+        let name = self.name.clone();
+        let addr = self.addr.clone();
+        let max_line_bytes = self.max_line_bytes;
+        Box::pin(stream! {
+            let socket = match TcpStream::connect(&addr).await.with_context(|| format!("Failed to connect to {}", addr)) {
+                Ok(socket) => socket,
+                Err(err) => {
+                    yield Err(err);
+                    return;
+                }
+            };
+            tracing::info!("Source {} connected to {}", name, addr);
+
+            let mut reader = BufReader::new(socket);
+            let mut buffer = Vec::new();
+            loop {
+                match read_line(&mut reader, &mut buffer, max_line_bytes).await {
+                    Ok(LineRead::Line) => {
+                        let line = String::from_utf8_lossy(&buffer);
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        match serde_json::from_str::<Transaction>(&line) {
+                            Ok(transaction) => yield Ok(MarketEvent::Trade(transaction)),
+                            Err(err) => tracing::warn!("Skipping malformed line from {}: {}", name, err),
+                        }
+                    }
+                    Ok(LineRead::TooLong) => {
+                        yield Err(anyhow!("Line from {} is longer than {} bytes", addr, max_line_bytes));
+                        return;
+                    }
+                    Ok(LineRead::Eof) => {
+                        yield Err(anyhow!("Connection to {} closed", addr));
+                        return;
+                    }
+                    Err(err) => {
+                        yield Err(anyhow!(err).context(format!("Failed to read from {}", addr)));
+                        return;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_reads_lines_and_fails_on_close() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let lines = concat!(
+                r#"{"symbol":"DOGE","price":"0.1","volume":"5","timestamp":"2024-01-01T00:00:00Z","side":"buy"}"#,
+                "\nnot json\n\n",
+                r#"{"symbol":"DOGE","price":"0.2","volume":"1","timestamp":"2024-01-01T00:00:01Z","side":"sell"}"#,
+                "\n"
+            );
+            socket.write_all(lines.as_bytes()).await.unwrap();
+        });
+
+        let items: Vec<_> = TcpNdjsonSource::new("firehose", &addr).open().collect().await;
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[0], Ok(MarketEvent::Trade(t)) if t.price.to_string() == "0.1"));
+        assert!(matches!(&items[1], Ok(MarketEvent::Trade(t)) if t.price.to_string() == "0.2"));
+        assert!(items[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fails_on_oversized_line() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(&[b'x'; 256]).await.unwrap();
+            // Keep the connection open so only the length limit can end the stream
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+
+        let mut stream = TcpNdjsonSource::new("firehose", &addr).with_max_line_bytes(64).open();
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("longer than 64 bytes"));
+    }
+}
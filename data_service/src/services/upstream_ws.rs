@@ -0,0 +1,476 @@
+use super::clock::{Clock, SystemClock};
+use super::source::TransactionSource;
+use super::supervisor::MarketEventStream;
+use crate::models::{MarketEvent, TradeSide, Transaction};
+use anyhow::{anyhow, bail, Context, Result};
+use async_stream::stream;
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Messages as sent by `/ws/transactions`, or bare transactions.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UpstreamMessage {
+    Envelope { data: Transaction },
+    Bare(Transaction),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpstreamWsConfig {
+    pub url: String,
+    /// Venue for the feed's trades.
+    #[serde(default)]
+    pub venue: Option<String>,
+    /// Messages sent after every (re)connect, e.g. channel subscriptions.
+    #[serde(default)]
+    pub subscribe: Vec<Value>,
+    /// How to read trades out of upstream messages. Without one, messages must be
+    /// `Transaction` JSON, optionally wrapped as `{"typ": ..., "data": ...}`.
+    #[serde(default)]
+    pub mapping: Option<FieldMapping>,
+    /// Reconnect when the upstream goes quiet for this long.
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampUnit {
+    S,
+    #[default]
+    Ms,
+    Us,
+    Ns,
+}
+
+/// Maps an upstream trade message onto a [`Transaction`] using JSON pointers
+/// (e.g. `/data/p`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FieldMapping {
+    /// Where the trade sits in the message; may point at an array of trades.
+    pub root: String,
+    /// Only messages holding these values at these pointers are trades; others
+    /// (subscription acks, heartbeats) are ignored.
+    pub when: BTreeMap<String, Value>,
+    /// Upstream trade id. Hashed into a UUID so redelivered trades keep their id, across
+    /// reconnects and restarts.
+    pub id: Option<String>,
+    /// Maker and taker wallets, when the feed has them.
+    pub maker: Option<String>,
+    pub taker: Option<String>,
+    pub symbol: String,
+    /// Symbol to use for feeds whose messages don't carry one.
+    pub fixed_symbol: Option<String>,
+    /// Renames upstream symbols, e.g. `"DOGEUSDT": "DOGE"`.
+    pub symbols: HashMap<String, String>,
+    pub price: String,
+    pub volume: String,
+    /// RFC 3339 strings or epoch numbers in `timestamp_unit`. `null` stamps trades with the
+    /// time they were received.
+    pub timestamp: Option<String>,
+    pub timestamp_unit: TimestampUnit,
+    /// Accepts `buy`/`sell`, `b`/`s`, `bid`/`ask`, or a boolean.
+    pub side: String,
+    /// The side a boolean `true` stands for, e.g. `sell` for "buyer is maker" flags.
+    pub true_side: TradeSide,
+}
+
+impl Default for FieldMapping {
+    fn default() -> Self {
+        Self {
+            root: String::new(),
+            when: BTreeMap::new(),
+            id: None,
+            maker: None,
+            taker: None,
+            symbol: "/symbol".to_string(),
+            fixed_symbol: None,
+            symbols: HashMap::new(),
+            price: "/price".to_string(),
+            volume: "/volume".to_string(),
+            timestamp: Some("/timestamp".to_string()),
+            timestamp_unit: TimestampUnit::default(),
+            side: "/side".to_string(),
+            true_side: TradeSide::Buy,
+        }
+    }
+}
+
+impl FieldMapping {
+    /// Returns the trades in `message`, or none if it isn't a trade message. `received_at`
+    /// stamps trades when the mapping has no timestamp.
+    pub fn apply(&self, source: &str, message: &Value, received_at: DateTime<Utc>) -> Result<Vec<Transaction>> {
+        if !self.when.iter().all(|(pointer, expected)| message.pointer(pointer) == Some(expected)) {
+            return Ok(Vec::new());
+        }
+        match message.pointer(&self.root) {
+            Some(Value::Array(items)) => items.iter().map(|item| self.map_trade(source, item, received_at)).collect(),
+            Some(item) => Ok(vec![self.map_trade(source, item, received_at)?]),
+            None => bail!("Message has nothing at {}", self.root),
+        }
+    }
+
+    fn map_trade(&self, source: &str, item: &Value, received_at: DateTime<Utc>) -> Result<Transaction> {
+        let field = |pointer: &str| {
+            item.pointer(pointer)
+                .filter(|value| !value.is_null())
+                .with_context(|| format!("Missing field {}", pointer))
+        };
+
+        let symbol = match &self.fixed_symbol {
+            Some(symbol) => symbol.clone(),
+            None => json_string(field(&self.symbol)?).context("Invalid symbol")?,
+        };
+        let symbol = self.symbols.get(&symbol).cloned().unwrap_or(symbol);
+        let price = json_decimal(field(&self.price)?).context("Invalid price")?;
+        let volume = json_decimal(field(&self.volume)?).context("Invalid volume")?;
+        let timestamp = match &self.timestamp {
+            Some(pointer) => json_timestamp(field(pointer)?, self.timestamp_unit).context("Invalid timestamp")?,
+            None => received_at,
+        };
+        let side = json_side(field(&self.side)?, self.true_side).context("Invalid side")?;
+        let id = match &self.id {
+            Some(pointer) => stable_id(source, field(pointer)?),
+            None => Uuid::new_v4(),
+        };
+
+        let wallet = |pointer: &Option<String>| -> Result<Option<String>> {
+            match pointer.as_deref().and_then(|pointer| item.pointer(pointer)).filter(|value| !value.is_null()) {
+                Some(value) => Ok(Some(json_string(value).context("Invalid wallet")?)),
+                None => Ok(None),
+            }
+        };
+
+        Ok(Transaction::new_at(id, symbol, price, volume, side, timestamp)
+            .with_wallets(wallet(&self.maker)?, wallet(&self.taker)?))
+    }
+}
+
+fn json_string(value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        other => bail!("expected a string, got {}", other),
+    }
+}
+
+fn json_decimal(value: &Value) -> Result<Decimal> {
+    let text = json_string(value)?;
+    let text = text.trim();
+    text.parse::<Decimal>()
+        .or_else(|_| Decimal::from_scientific(text))
+        .with_context(|| format!("'{}' is not a number", text))
+}
+
+fn json_timestamp(value: &Value, unit: TimestampUnit) -> Result<DateTime<Utc>> {
+    if let Value::String(s) = value {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+            return Ok(timestamp.with_timezone(&Utc));
+        }
+    }
+    let number = json_decimal(value)?;
+    let timestamp = match unit {
+        // Fractional seconds are common enough to keep.
+        TimestampUnit::S => (number * Decimal::from(1000)).trunc().try_into().ok().and_then(DateTime::from_timestamp_millis),
+        TimestampUnit::Ms => number.trunc().try_into().ok().and_then(DateTime::from_timestamp_millis),
+        TimestampUnit::Us => number.trunc().try_into().ok().and_then(DateTime::from_timestamp_micros),
+        TimestampUnit::Ns => number.trunc().try_into().ok().map(DateTime::from_timestamp_nanos),
+    };
+    timestamp.with_context(|| format!("{} is out of range", number))
+}
+
+fn json_side(value: &Value, true_side: TradeSide) -> Result<TradeSide> {
+    let opposite = match true_side {
+        TradeSide::Buy => TradeSide::Sell,
+        TradeSide::Sell => TradeSide::Buy,
+    };
+    match value {
+        Value::Bool(true) => Ok(true_side),
+        Value::Bool(false) => Ok(opposite),
+        Value::String(s) => match s.to_ascii_lowercase().as_str() {
+            "buy" | "b" | "bid" => Ok(TradeSide::Buy),
+            "sell" | "s" | "ask" | "a" => Ok(TradeSide::Sell),
+            _ => bail!("unknown side '{}'", s),
+        },
+        other => bail!("unknown side {}", other),
+    }
+}
+
+/// Namespace for [`stable_id`]'s name-based UUIDs.
+const UPSTREAM_ID_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c_2a4e_8d3b_5f70_9a1e_c4b2_d7e8_0f35);
+
+/// Derives a UUID from an upstream id, the same in every process, so downstream consumers
+/// can drop trades redelivered after a reconnect or a restart. Ids that are already UUIDs
+/// are kept as they are.
+fn stable_id(source: &str, value: &Value) -> Uuid {
+    let key = match value {
+        Value::String(s) => match Uuid::parse_str(s) {
+            Ok(id) => return id,
+            Err(_) => s.clone(),
+        },
+        other => other.to_string(),
+    };
+    // The source name can't contain a NUL, so the two parts can't run together.
+    Uuid::new_v5(&UPSTREAM_ID_NAMESPACE, format!("{}\0{}", source, key).as_bytes())
+}
+
+/// Reads trades from another WebSocket feed, normalizing them through a [`FieldMapping`].
+///
+/// Each [`open`](TransactionSource::open) dials the upstream and sends the configured
+/// subscriptions again; the supervisor's backoff paces reconnects.
+#[derive(Clone)]
+pub struct UpstreamWsSource {
+    name: String,
+    config: UpstreamWsConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl UpstreamWsSource {
+    pub fn new(name: &str, config: UpstreamWsConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            config,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Clock for stamping trades whose messages carry no timestamp.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn parse_message(&self, text: &str) -> Result<Vec<Transaction>> {
+        match &self.config.mapping {
+            Some(mapping) => {
+                let message: Value = serde_json::from_str(text)?;
+                mapping.apply(&self.name, &message, self.clock.now())
+            }
+            None => match serde_json::from_str::<UpstreamMessage>(text)? {
+                UpstreamMessage::Envelope { data } | UpstreamMessage::Bare(data) => Ok(vec![data]),
+            },
+        }
+    }
+}
+
+impl TransactionSource for UpstreamWsSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn venue(&self) -> Option<&str> {
+        self.config.venue.as_deref()
+    }
+
+    fn open(&self) -> MarketEventStream {
+        let source = self.clone();
+        Box::pin(stream! {
+            let url = source.config.url.as_str();
+            let mut socket = match tokio_tungstenite::connect_async(url)
+                .await
+                .with_context(|| format!("Failed to connect to {}", url))
+            {
+                Ok((socket, _)) => socket,
+                Err(err) => {
+                    yield Err(err);
+                    return;
+                }
+            };
+            tracing::info!("Source {} connected to {}", source.name, url);
+
+            for message in &source.config.subscribe {
+                if let Err(err) = socket.send(Message::Text(message.to_string().into())).await {
+                    yield Err(anyhow!(err).context(format!("Failed to subscribe on {}", url)));
+                    return;
+                }
+            }
+
+            loop {
+                let next = match source.config.idle_timeout_secs {
+                    Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), socket.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            yield Err(anyhow!("No messages from {} for {}s", url, secs));
+                            return;
+                        }
+                    },
+                    None => socket.next().await,
+                };
+
+                match next {
+                    Some(Ok(Message::Text(text))) => match source.parse_message(&text) {
+                        Ok(transactions) => {
+                            for transaction in transactions {
+                                yield Ok(MarketEvent::Trade(transaction));
+                            }
+                        }
+                        Err(err) => tracing::warn!("Skipping unrecognised message from {}: {:#}", source.name, err),
+                    },
+                    Some(Ok(Message::Close(_))) | None => {
+                        yield Err(anyhow!("Connection to {} closed", url));
+                        return;
+                    }
+                    // Pings are answered by tungstenite on the next read.
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => {
+                        yield Err(anyhow!(err).context(format!("Connection to {} failed", url)));
+                        return;
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::DataService;
+    use crate::websocket::ws_transaction_handler;
+    use axum::extract::ws::{Message as AxumMessage, WebSocketUpgrade};
+    use axum::routing::{get, Router};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    async fn serve(router: Router) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("ws://{}", addr)
+    }
+
+    fn binance_mapping() -> FieldMapping {
+        serde_json::from_value(serde_json::json!({
+            "root": "/data",
+            "when": { "/stream": "dogeusdt@trade" },
+            "id": "/t",
+            "symbol": "/s",
+            "symbols": { "DOGEUSDT": "DOGE" },
+            "price": "/p",
+            "volume": "/q",
+            "timestamp": "/T",
+            "side": "/m",
+            "true_side": "sell"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_field_mapping() {
+        let mapping = binance_mapping();
+        let message = serde_json::json!({
+            "stream": "dogeusdt@trade",
+            "data": { "e": "trade", "s": "DOGEUSDT", "t": 12345, "p": "0.15", "q": 2e3, "T": 1700000000000u64, "m": true }
+        });
+
+        let trades = mapping.apply("binance", &message, Utc::now()).unwrap();
+        assert_eq!(trades.len(), 1);
+        let trade = &trades[0];
+        assert_eq!(trade.symbol, "DOGE");
+        assert_eq!(trade.price, "0.15".parse::<Decimal>().unwrap());
+        assert_eq!(trade.volume, Decimal::from(2000));
+        assert_eq!(trade.timestamp, DateTime::from_timestamp_millis(1_700_000_000_000).unwrap());
+        assert_eq!(trade.side, TradeSide::Sell);
+        assert_eq!(trade.id, mapping.apply("binance", &message, Utc::now()).unwrap()[0].id);
+        assert_ne!(trade.id, mapping.apply("other", &message, Utc::now()).unwrap()[0].id);
+
+        let ack = serde_json::json!({ "result": null, "id": 1 });
+        assert!(mapping.apply("binance", &ack, Utc::now()).unwrap().is_empty());
+        let broken = serde_json::json!({ "stream": "dogeusdt@trade", "data": { "s": "DOGEUSDT" } });
+        assert!(mapping.apply("binance", &broken, Utc::now()).is_err());
+
+        // Without a timestamp field, trades are stamped with the time they were received
+        let received_at = DateTime::from_timestamp(1_600_000_000, 0).unwrap();
+        let untimed = FieldMapping {
+            timestamp: None,
+            ..binance_mapping()
+        };
+        assert_eq!(untimed.apply("binance", &message, received_at).unwrap()[0].timestamp, received_at);
+    }
+
+    #[tokio::test]
+    async fn test_resubscribes_on_every_connect() {
+        // Answers the subscription with one trade, then hangs up.
+        let router = Router::new().route(
+            "/",
+            get(|ws: WebSocketUpgrade| async {
+                ws.on_upgrade(|mut socket| async move {
+                    let Some(Ok(AxumMessage::Text(subscribe))) = socket.recv().await else { return };
+                    let request: Value = serde_json::from_str(&subscribe).unwrap();
+                    let trade = serde_json::json!({
+                        "stream": "dogeusdt@trade",
+                        "data": { "s": "DOGEUSDT", "t": request["id"], "p": "0.1", "q": "5", "T": 1700000000000u64, "m": false }
+                    });
+                    let _ = socket.send(AxumMessage::Text(trade.to_string().into())).await;
+                })
+            }),
+        );
+        let url = serve(router).await;
+
+        let source = UpstreamWsSource::new(
+            "binance",
+            UpstreamWsConfig {
+                url: format!("{}/", url),
+                venue: None,
+                subscribe: vec![serde_json::json!({ "method": "SUBSCRIBE", "params": ["dogeusdt@trade"], "id": 1 })],
+                mapping: Some(binance_mapping()),
+                idle_timeout_secs: Some(5),
+            },
+        );
+
+        for _ in 0..2 {
+            let items: Vec<_> = source.open().collect().await;
+            assert_eq!(items.len(), 2);
+            assert!(matches!(&items[0], Ok(MarketEvent::Trade(t)) if t.symbol == "DOGE" && t.side == TradeSide::Buy));
+            assert!(items[1].is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reads_another_instance() {
+        let upstream = Arc::new(DataService::new());
+        let router = Router::new()
+            .route("/ws/transactions/{symbol}", get(ws_transaction_handler))
+            .with_state(upstream.clone());
+        let url = serve(router).await;
+
+        // Keep publishing until the connector has subscribed and picked a trade up.
+        let publisher = tokio::spawn(async move {
+            loop {
+                let trade = Transaction::new("DOGE".to_string(), Decimal::ONE, Decimal::TEN, TradeSide::Buy);
+                upstream.process_transaction(&trade).unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
+
+        let source = UpstreamWsSource::new(
+            "mirror",
+            UpstreamWsConfig {
+                url: format!("{}/ws/transactions/DOGE", url),
+                venue: None,
+                subscribe: Vec::new(),
+                mapping: None,
+                idle_timeout_secs: None,
+            },
+        );
+        let first = source.open().next().await.unwrap().unwrap();
+        publisher.abort();
+
+        match first {
+            MarketEvent::Trade(trade) => {
+                assert_eq!(trade.symbol, "DOGE");
+                assert_eq!(trade.volume, Decimal::TEN);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::models::{KLine, ProfileSource, TradeSide, Transaction, VolumeBucket, VolumeProfile};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// What volume profiles and Renko bricks need of a stored trade.
+#[derive(Debug, Clone, Copy)]
+pub struct TradePrint {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub price: Decimal,
+    pub volume: Decimal,
+    pub side: TradeSide,
+}
+
+impl From<&Transaction> for TradePrint {
+    fn from(transaction: &Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            timestamp: transaction.timestamp,
+            price: transaction.price,
+            volume: transaction.volume,
+            side: transaction.side,
+        }
+    }
+}
+
+/// Inputs to a volume profile over `[from, to)`.
+pub struct ProfileRequest<'a> {
+    pub venue: &'a str,
+    pub symbol: &'a str,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub buckets: usize,
+    /// Share of volume in the value area, e.g. 0.7.
+    pub value_area: Decimal,
+}
+
+/// Builds a profile from trades, plus candles covering the part of the range the trades
+/// don't. Candles and trades are expected not to overlap.
+pub fn build_profile(request: &ProfileRequest, trades: &[TradePrint], candles: &[KLine]) -> VolumeProfile {
+    let source = match (trades.is_empty(), candles.is_empty()) {
+        (_, true) => ProfileSource::Trades,
+        (true, false) => ProfileSource::Candles,
+        (false, false) => ProfileSource::Mixed,
+    };
+    let mut profile = VolumeProfile {
+        venue: request.venue.to_string(),
+        symbol: request.symbol.to_string(),
+        from: request.from,
+        to: request.to,
+        source,
+        total_volume: Decimal::ZERO,
+        point_of_control: None,
+        value_area_low: None,
+        value_area_high: None,
+        buckets: Vec::new(),
+    };
+
+    let lows = trades.iter().map(|t| t.price).chain(candles.iter().map(|c| c.ohlcv.low));
+    let highs = trades.iter().map(|t| t.price).chain(candles.iter().map(|c| c.ohlcv.high));
+    let (Some(min), Some(max)) = (lows.min(), highs.max()) else {
+        return profile;
+    };
+    // A range with a single price gets a single bucket.
+    let count = if max > min { request.buckets.max(1) } else { 1 };
+    let width = (max - min) / Decimal::from(count);
+    let mut buckets: Vec<VolumeBucket> = (0..count)
+        .map(|i| VolumeBucket {
+            price_low: min + width * Decimal::from(i),
+            price_high: if i + 1 == count { max } else { min + width * Decimal::from(i + 1) },
+            volume: Decimal::ZERO,
+            buy_volume: Decimal::ZERO,
+            sell_volume: Decimal::ZERO,
+        })
+        .collect();
+    let index_of = |price: Decimal| -> usize {
+        if width.is_zero() {
+            return 0;
+        }
+        ((price - min) / width).floor().to_usize().unwrap_or(0).min(count - 1)
+    };
+
+    for trade in trades {
+        let bucket = &mut buckets[index_of(trade.price)];
+        bucket.volume += trade.volume;
+        match trade.side {
+            TradeSide::Buy => bucket.buy_volume += trade.volume,
+            TradeSide::Sell => bucket.sell_volume += trade.volume,
+        }
+    }
+    for candle in candles {
+        let range = candle.ohlcv.high - candle.ohlcv.low;
+        let add = |bucket: &mut VolumeBucket, share: Decimal| {
+            bucket.volume += candle.ohlcv.volume * share;
+            bucket.buy_volume += candle.ohlcv.buy_volume * share;
+            bucket.sell_volume += candle.ohlcv.sell_volume * share;
+        };
+        if range.is_zero() {
+            add(&mut buckets[index_of(candle.ohlcv.close)], Decimal::ONE);
+            continue;
+        }
+        for bucket in &mut buckets[index_of(candle.ohlcv.low)..=index_of(candle.ohlcv.high)] {
+            let overlap = bucket.price_high.min(candle.ohlcv.high) - bucket.price_low.max(candle.ohlcv.low);
+            if overlap > Decimal::ZERO {
+                add(bucket, overlap / range);
+            }
+        }
+    }
+
+    profile.total_volume = buckets.iter().map(|bucket| bucket.volume).sum();
+    if profile.total_volume > Decimal::ZERO {
+        let (low, high, poc) = value_area(&buckets, profile.total_volume * request.value_area);
+        profile.point_of_control = Some((buckets[poc].price_low + buckets[poc].price_high) / Decimal::TWO);
+        profile.value_area_low = Some(buckets[low].price_low);
+        profile.value_area_high = Some(buckets[high].price_high);
+    }
+    for bucket in &mut buckets {
+        bucket.volume = bucket.volume.normalize();
+        bucket.buy_volume = bucket.buy_volume.normalize();
+        bucket.sell_volume = bucket.sell_volume.normalize();
+    }
+    profile.buckets = buckets;
+    profile
+}
+
+/// Grows the value area outwards from the point of control, one bucket at a time on
+/// whichever side has more volume, until it holds `target`. Returns the lowest and
+/// highest bucket of the area and the point of control.
+fn value_area(buckets: &[VolumeBucket], target: Decimal) -> (usize, usize, usize) {
+    let mut poc = 0;
+    for (index, bucket) in buckets.iter().enumerate() {
+        if bucket.volume > buckets[poc].volume {
+            poc = index;
+        }
+    }
+    let (mut low, mut high) = (poc, poc);
+    let mut volume = buckets[poc].volume;
+    while volume < target && (low > 0 || high + 1 < buckets.len()) {
+        let below = (low > 0).then(|| buckets[low - 1].volume);
+        let above = (high + 1 < buckets.len()).then(|| buckets[high + 1].volume);
+        if above.is_some() && above >= below {
+            high += 1;
+            volume += buckets[high].volume;
+        } else {
+            low -= 1;
+            volume += buckets[low].volume;
+        }
+    }
+    (low, high, poc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::KLineInterval;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_profile_from_trades_and_candles() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let request = ProfileRequest {
+            venue: "default",
+            symbol: "DOGE",
+            from: start,
+            to: start + chrono::Duration::hours(1),
+            buckets: 5,
+            value_area: Decimal::new(8, 1),
+        };
+        let print = |price: i64, volume: i64, side: TradeSide| TradePrint {
+            id: Uuid::new_v4(),
+            timestamp: start,
+            price: Decimal::new(price, 0),
+            volume: Decimal::new(volume, 0),
+            side,
+        };
+        let trades = vec![
+            print(100, 5, TradeSide::Buy),
+            print(102, 30, TradeSide::Buy),
+            print(103, 20, TradeSide::Sell),
+            print(105, 10, TradeSide::Sell),
+            print(109, 5, TradeSide::Buy),
+        ];
+
+        let profile = build_profile(&request, &trades, &[]);
+        assert_eq!(profile.source, ProfileSource::Trades);
+        assert_eq!(profile.total_volume, Decimal::new(70, 0));
+        let volumes: Vec<Decimal> = profile.buckets.iter().map(|b| b.volume).collect();
+        assert_eq!(volumes, [5, 50, 10, 0, 5].map(|v| Decimal::new(v, 0)));
+        assert_eq!(profile.buckets[1].sell_volume, Decimal::new(20, 0));
+        assert_eq!(profile.point_of_control, Some(Decimal::new(1027, 1)));
+        // 50 + 10 reaches 80% of 70.
+        assert_eq!(profile.value_area_low, Some(Decimal::new(1018, 1)));
+        assert_eq!(profile.value_area_high, Some(Decimal::new(1054, 1)));
+
+        // A candle from 100 to 110 spreads its 10 evenly over five buckets of 2.
+        let mut candle = KLine::new("default".to_string(), "DOGE".to_string(), KLineInterval::OneMinute, start, Decimal::new(100, 0));
+        candle.update(Decimal::new(100, 0), Decimal::new(6, 0), TradeSide::Buy);
+        candle.update(Decimal::new(110, 0), Decimal::new(4, 0), TradeSide::Sell);
+        let profile = build_profile(&request, &trades[1..2], &[candle]);
+        assert_eq!(profile.source, ProfileSource::Mixed);
+        assert_eq!(profile.total_volume, Decimal::new(40, 0));
+        assert_eq!(profile.buckets[1].volume, Decimal::new(32, 0));
+        // The candle is 60% buys.
+        assert_eq!(profile.buckets[1].buy_volume, Decimal::new(312, 1));
+        assert_eq!(profile.buckets[1].sell_volume, Decimal::new(8, 1));
+        assert_eq!(profile.buckets[4].volume, Decimal::new(2, 0));
+    }
+}
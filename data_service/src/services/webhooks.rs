@@ -0,0 +1,818 @@
+use super::DataService;
+use crate::models::{Alert, KLine, KLineInterval, TradeSide, Transaction};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{mpsc, Semaphore};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhooksConfig {
+    pub subscriptions: Vec<WebhookConfig>,
+    /// Holds deliveries awaiting a retry and dead letters, so neither is lost on restart.
+    #[serde(default = "default_queue_dir")]
+    pub queue_dir: PathBuf,
+    /// Attempts before a delivery is dead-lettered.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubling with every further attempt.
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Requests in flight across all webhooks.
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+    /// Deliveries each webhook buffers before new ones are dead-lettered.
+    #[serde(default = "default_queue_size")]
+    pub queue_size: usize,
+    /// Tasks delivering for each webhook.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// Dead letters kept; the oldest are deleted beyond this.
+    #[serde(default = "default_max_dead_letters")]
+    pub max_dead_letters: usize,
+}
+
+fn default_queue_dir() -> PathBuf {
+    PathBuf::from("webhook-queue")
+}
+
+fn default_max_attempts() -> u32 {
+    8
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    5 * 60 * 1000
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_in_flight() -> usize {
+    32
+}
+
+fn default_queue_size() -> usize {
+    1024
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+fn default_max_dead_letters() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub url: String,
+    /// Key for the HMAC-SHA256 `X-Webhook-Signature` header.
+    pub secret: String,
+    /// Events to deliver; an event matching several filters is delivered once.
+    pub events: Vec<WebhookFilter>,
+}
+
+/// Which events a webhook receives. Omitted fields match anything.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookFilter {
+    /// Closed candles.
+    Kline {
+        #[serde(default)]
+        venue: Option<String>,
+        #[serde(default)]
+        symbol: Option<String>,
+        #[serde(default)]
+        interval: Option<KLineInterval>,
+    },
+    Transaction {
+        #[serde(default)]
+        venue: Option<String>,
+        #[serde(default)]
+        symbol: Option<String>,
+        #[serde(default)]
+        side: Option<TradeSide>,
+        /// Minimum `Transaction::total_value()`.
+        #[serde(default)]
+        min_value: Option<Decimal>,
+    },
+    Alert {
+        #[serde(default)]
+        symbol: Option<String>,
+    },
+}
+
+fn matches(filter: &Option<String>, value: &str) -> bool {
+    filter.as_deref().is_none_or(|filter| filter == value)
+}
+
+impl WebhookFilter {
+    fn matches(&self, event: &WebhookEvent) -> bool {
+        match (self, event) {
+            (WebhookFilter::Kline { venue, symbol, interval }, WebhookEvent::Kline(kline)) => {
+                matches(venue, &kline.venue)
+                    && matches(symbol, &kline.symbol)
+                    && interval.is_none_or(|interval| interval == kline.interval)
+            }
+            (WebhookFilter::Transaction { venue, symbol, side, min_value }, WebhookEvent::Transaction(transaction)) => {
+                matches(venue, transaction.venue.as_deref().unwrap_or_default())
+                    && matches(symbol, &transaction.symbol)
+                    && side.is_none_or(|side| side == transaction.side)
+                    && min_value.is_none_or(|min_value| transaction.total_value() >= min_value)
+            }
+            (WebhookFilter::Alert { symbol }, WebhookEvent::Alert(alert)) => matches(symbol, &alert.symbol),
+            _ => false,
+        }
+    }
+}
+
+impl WebhooksConfig {
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for webhook in &self.subscriptions {
+            if !names.insert(webhook.name.as_str()) {
+                bail!("Webhook {} is configured more than once", webhook.name);
+            }
+            let url = reqwest::Url::parse(&webhook.url)
+                .with_context(|| format!("Webhook {} has an invalid url", webhook.name))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                bail!("Webhook {} needs an http or https url", webhook.name);
+            }
+            if webhook.secret.is_empty() {
+                bail!("Webhook {} needs a secret", webhook.name);
+            }
+            if webhook.events.is_empty() {
+                bail!("Webhook {} subscribes to no events", webhook.name);
+            }
+        }
+        if self.max_attempts == 0 || self.max_in_flight == 0 {
+            bail!("webhooks need a positive max_attempts and max_in_flight");
+        }
+        if self.queue_size == 0 || self.workers == 0 || self.max_dead_letters == 0 {
+            bail!("webhooks need a positive queue_size, workers and max_dead_letters");
+        }
+        Ok(())
+    }
+}
+
+enum WebhookEvent {
+    Kline(KLine),
+    Transaction(Transaction),
+    Alert(Alert),
+}
+
+impl WebhookEvent {
+    fn typ(&self) -> &'static str {
+        match self {
+            WebhookEvent::Kline(_) => "kline",
+            WebhookEvent::Transaction(_) => "transaction",
+            WebhookEvent::Alert(_) => "alert",
+        }
+    }
+
+    fn payload(&self) -> Result<Value> {
+        Ok(match self {
+            WebhookEvent::Kline(kline) => serde_json::to_value(kline)?,
+            WebhookEvent::Transaction(transaction) => serde_json::to_value(transaction)?,
+            WebhookEvent::Alert(alert) => serde_json::to_value(alert)?,
+        })
+    }
+}
+
+/// One event on its way to one webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook: String,
+    pub typ: String,
+    pub data: Value,
+    pub created_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+/// Delivery counters for one webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookStatus {
+    pub name: String,
+    pub url: String,
+    /// Deliveries waiting for a worker.
+    pub queued: u64,
+    pub delivered: u64,
+    pub failed_attempts: u64,
+    /// Deliveries waiting in the retry queue.
+    pub retrying: u64,
+    pub dead_lettered: u64,
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}`, as sent in `X-Webhook-Signature` after
+/// `sha256=`. Receivers should recompute it and reject stale timestamps.
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+struct Webhook {
+    config: WebhookConfig,
+    /// Deliveries waiting for one of the webhook's workers.
+    queue: mpsc::Sender<WebhookDelivery>,
+    delivered: AtomicU64,
+    failed_attempts: AtomicU64,
+    retrying: AtomicU64,
+}
+
+/// POSTs closed candles, transactions and alerts to webhook subscribers.
+///
+/// Every delivery is written to `queue_dir/pending` and put on its webhook's bounded
+/// queue, which `workers` tasks drain; a full queue dead-letters the delivery instead.
+/// Failed attempts are retried with exponential backoff, and after `max_attempts` the
+/// delivery moves to `queue_dir/dead`. Both are reloaded on startup.
+pub struct WebhookDispatcher {
+    config: WebhooksConfig,
+    webhooks: HashMap<String, Webhook>,
+    client: reqwest::Client,
+    in_flight: Semaphore,
+    /// Newest first, at most `max_dead_letters`.
+    dead_letters: Mutex<VecDeque<WebhookDelivery>>,
+}
+
+impl WebhookDispatcher {
+    /// Reloads the queue, resumes pending deliveries and starts delivering new events.
+    pub async fn start(config: WebhooksConfig, data_service: &DataService) -> Result<Arc<Self>> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .context("Failed to build webhook HTTP client")?;
+        let pending_dir = config.queue_dir.join("pending");
+        let dead_dir = config.queue_dir.join("dead");
+        for dir in [&pending_dir, &dead_dir] {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Failed to create webhook queue directory {}", dir.display()))?;
+        }
+
+        let mut dead_letters = load_deliveries(&dead_dir).await?;
+        dead_letters.sort_by_key(|delivery| std::cmp::Reverse(delivery.next_attempt_at));
+        for delivery in dead_letters.drain(config.max_dead_letters.min(dead_letters.len())..) {
+            remove_delivery(&dead_dir, delivery.id).await?;
+        }
+        let mut receivers = Vec::new();
+        let webhooks = config
+            .subscriptions
+            .iter()
+            .map(|webhook| {
+                let (queue, receiver) = mpsc::channel(config.queue_size);
+                receivers.push((webhook.name.clone(), receiver));
+                let state = Webhook {
+                    config: webhook.clone(),
+                    queue,
+                    delivered: AtomicU64::new(0),
+                    failed_attempts: AtomicU64::new(0),
+                    retrying: AtomicU64::new(0),
+                };
+                (webhook.name.clone(), state)
+            })
+            .collect();
+        let dispatcher = Arc::new(Self {
+            in_flight: Semaphore::new(config.max_in_flight),
+            config,
+            webhooks,
+            client,
+            dead_letters: Mutex::new(dead_letters.into()),
+        });
+        for (name, receiver) in receivers {
+            let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+            for _ in 0..dispatcher.config.workers {
+                tokio::spawn(dispatcher.clone().work(name.clone(), receiver.clone()));
+            }
+        }
+
+        let pending = load_deliveries(&pending_dir).await?;
+        if !pending.is_empty() {
+            tracing::info!("Resuming {} queued webhook deliveries", pending.len());
+        }
+        let mut resumed = Vec::new();
+        for mut delivery in pending {
+            match dispatcher.webhooks.get(&delivery.webhook) {
+                Some(webhook) => {
+                    if delivery.attempts > 0 {
+                        webhook.retrying.fetch_add(1, Ordering::Relaxed);
+                    }
+                    resumed.push(delivery);
+                }
+                None => {
+                    delivery.last_error = Some("Webhook is no longer configured".to_string());
+                    dispatcher.dead_letter(delivery).await;
+                }
+            }
+        }
+        // The queues may be smaller than the backlog, so they are refilled as the workers drain them.
+        let resuming = dispatcher.clone();
+        tokio::spawn(async move {
+            for delivery in resumed {
+                let queue = &resuming.webhooks[&delivery.webhook].queue;
+                if queue.send(delivery).await.is_err() {
+                    return;
+                }
+            }
+        });
+
+        tokio::spawn(dispatcher.clone().run(
+            data_service.subscribe(),
+            data_service.subscribe_transactions(),
+            data_service.subscribe_alerts(),
+        ));
+        Ok(dispatcher)
+    }
+
+    pub fn statuses(&self) -> Vec<WebhookStatus> {
+        let dead_letters = self.dead_letters.lock().expect("Dead letter lock poisoned");
+        let mut statuses: Vec<WebhookStatus> = self
+            .webhooks
+            .values()
+            .map(|webhook| WebhookStatus {
+                name: webhook.config.name.clone(),
+                url: webhook.config.url.clone(),
+                queued: (webhook.queue.max_capacity() - webhook.queue.capacity()) as u64,
+                delivered: webhook.delivered.load(Ordering::Relaxed),
+                failed_attempts: webhook.failed_attempts.load(Ordering::Relaxed),
+                retrying: webhook.retrying.load(Ordering::Relaxed),
+                dead_lettered: dead_letters.iter().filter(|d| d.webhook == webhook.config.name).count() as u64,
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Deliveries that ran out of attempts, most recent first.
+    pub fn dead_letters(&self) -> Vec<WebhookDelivery> {
+        self.dead_letters.lock().expect("Dead letter lock poisoned").iter().cloned().collect()
+    }
+
+    /// Moves a dead letter back into the queue with a fresh set of attempts.
+    pub async fn retry_dead_letter(&self, id: Uuid) -> Result<bool> {
+        let dead_letters = self.dead_letters();
+        let Some(delivery) = dead_letters.iter().find(|delivery| delivery.id == id) else {
+            return Ok(false);
+        };
+        let webhook = &delivery.webhook;
+        let queue = &self
+            .webhooks
+            .get(webhook)
+            .with_context(|| format!("Webhook {} is no longer configured", webhook))?
+            .queue;
+        let permit = queue
+            .try_reserve()
+            .map_err(|_| anyhow::anyhow!("Webhook {} queue is full", webhook))?;
+        let Some(mut delivery) = self.remove_dead_letter(id).await? else {
+            return Ok(false);
+        };
+        delivery.attempts = 0;
+        delivery.next_attempt_at = Utc::now();
+        delivery.last_error = None;
+        write_delivery(&self.pending_dir(), &delivery).await?;
+        permit.send(delivery);
+        Ok(true)
+    }
+
+    pub async fn delete_dead_letter(&self, id: Uuid) -> Result<bool> {
+        Ok(self.remove_dead_letter(id).await?.is_some())
+    }
+
+    async fn remove_dead_letter(&self, id: Uuid) -> Result<Option<WebhookDelivery>> {
+        let delivery = {
+            let mut dead_letters = self.dead_letters.lock().expect("Dead letter lock poisoned");
+            let Some(index) = dead_letters.iter().position(|delivery| delivery.id == id) else {
+                return Ok(None);
+            };
+            dead_letters.remove(index)
+        };
+        remove_delivery(&self.dead_dir(), id).await?;
+        Ok(delivery)
+    }
+
+    fn pending_dir(&self) -> PathBuf {
+        self.config.queue_dir.join("pending")
+    }
+
+    fn dead_dir(&self) -> PathBuf {
+        self.config.queue_dir.join("dead")
+    }
+
+    async fn run(
+        self: Arc<Self>,
+        mut klines: broadcast::Receiver<KLine>,
+        mut transactions: broadcast::Receiver<Transaction>,
+        mut alerts: broadcast::Receiver<Alert>,
+    ) {
+        loop {
+            let event = tokio::select! {
+                kline = klines.recv() => kline.map(WebhookEvent::Kline),
+                transaction = transactions.recv() => transaction.map(WebhookEvent::Transaction),
+                alert = alerts.recv() => alert.map(WebhookEvent::Alert),
+            };
+            match event {
+                Ok(WebhookEvent::Kline(kline)) if !kline.is_closed => {}
+                Ok(event) => self.dispatch(event).await,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Webhook dispatcher fell behind and skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn dispatch(&self, event: WebhookEvent) {
+        let mut payload = None;
+        for webhook in self.webhooks.values() {
+            if !webhook.config.events.iter().any(|filter| filter.matches(&event)) {
+                continue;
+            }
+            let data = match payload.get_or_insert_with(|| event.payload()) {
+                Ok(data) => data.clone(),
+                Err(err) => {
+                    tracing::warn!("Failed to serialize {} for webhooks: {:#}", event.typ(), err);
+                    return;
+                }
+            };
+            let now = Utc::now();
+            let mut delivery = WebhookDelivery {
+                id: Uuid::new_v4(),
+                webhook: webhook.config.name.clone(),
+                typ: event.typ().to_string(),
+                data,
+                created_at: now,
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+            };
+            let Ok(permit) = webhook.queue.try_reserve() else {
+                delivery.last_error = Some("Webhook queue is full".to_string());
+                self.dead_letter(delivery).await;
+                continue;
+            };
+            if let Err(err) = write_delivery(&self.pending_dir(), &delivery).await {
+                tracing::warn!("Failed to queue webhook delivery {}: {:#}", delivery.id, err);
+            }
+            permit.send(delivery);
+        }
+    }
+
+    async fn work(self: Arc<Self>, webhook: String, queue: Arc<tokio::sync::Mutex<mpsc::Receiver<WebhookDelivery>>>) {
+        let webhook = &self.webhooks[&webhook];
+        loop {
+            let Some(delivery) = queue.lock().await.recv().await else {
+                return;
+            };
+            self.deliver(webhook, delivery).await;
+        }
+    }
+
+    /// Attempts a delivery until it succeeds or is dead-lettered.
+    async fn deliver(&self, webhook: &Webhook, mut delivery: WebhookDelivery) {
+        loop {
+            if let Ok(wait) = (delivery.next_attempt_at - Utc::now()).to_std() {
+                tokio::time::sleep(wait).await;
+            }
+            let retry = delivery.attempts > 0;
+            let result = {
+                let _permit = self.in_flight.acquire().await.expect("Semaphore is never closed");
+                self.post(&webhook.config, &delivery).await
+            };
+            delivery.attempts += 1;
+
+            let err = match result {
+                Ok(()) => {
+                    webhook.delivered.fetch_add(1, Ordering::Relaxed);
+                    if retry {
+                        webhook.retrying.fetch_sub(1, Ordering::Relaxed);
+                    }
+                    if let Err(err) = remove_delivery(&self.pending_dir(), delivery.id).await {
+                        tracing::warn!("Failed to remove delivered webhook from the queue: {:#}", err);
+                    }
+                    return;
+                }
+                Err(err) => err,
+            };
+            webhook.failed_attempts.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("Webhook {} delivery {} failed: {:#}", webhook.config.name, delivery.id, err);
+            delivery.last_error = Some(format!("{:#}", err));
+
+            if delivery.attempts >= self.config.max_attempts {
+                if retry {
+                    webhook.retrying.fetch_sub(1, Ordering::Relaxed);
+                }
+                self.dead_letter(delivery).await;
+                return;
+            }
+            if !retry {
+                webhook.retrying.fetch_add(1, Ordering::Relaxed);
+            }
+            delivery.next_attempt_at = Utc::now() + self.backoff(delivery.attempts);
+            if let Err(err) = write_delivery(&self.pending_dir(), &delivery).await {
+                tracing::warn!("Failed to queue webhook delivery {}: {:#}", delivery.id, err);
+            }
+        }
+    }
+
+    /// Wait after the `attempts`th failed attempt.
+    fn backoff(&self, attempts: u32) -> chrono::Duration {
+        let factor = 1u64 << attempts.saturating_sub(1).min(32);
+        let millis = self.config.initial_backoff_ms.saturating_mul(factor).min(self.config.max_backoff_ms);
+        chrono::Duration::milliseconds(millis as i64)
+    }
+
+    async fn post(&self, webhook: &WebhookConfig, delivery: &WebhookDelivery) -> Result<()> {
+        let body = serde_json::to_vec(&json!({
+            "id": delivery.id,
+            "typ": delivery.typ,
+            "data": delivery.data,
+            "created_at": delivery.created_at,
+        }))?;
+        let timestamp = Utc::now().timestamp();
+        let signature = sign_webhook(&webhook.secret, timestamp, &body);
+
+        let response = self
+            .client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Id", delivery.id.to_string())
+            .header("X-Webhook-Timestamp", timestamp.to_string())
+            .header("X-Webhook-Signature", format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await
+            .context("Request failed")?;
+        if !response.status().is_success() {
+            bail!("Receiver answered {}", response.status());
+        }
+        Ok(())
+    }
+
+    /// Keeps the newest `max_dead_letters`, deleting the files of older ones.
+    async fn dead_letter(&self, delivery: WebhookDelivery) {
+        tracing::warn!(
+            "Dead-lettering webhook {} delivery {} after {} attempts: {}",
+            delivery.webhook,
+            delivery.id,
+            delivery.attempts,
+            delivery.last_error.as_deref().unwrap_or_default()
+        );
+        if let Err(err) = write_delivery(&self.dead_dir(), &delivery).await {
+            tracing::warn!("Failed to persist dead letter {}: {:#}", delivery.id, err);
+        }
+        if let Err(err) = remove_delivery(&self.pending_dir(), delivery.id).await {
+            tracing::warn!("Failed to remove dead letter from the queue: {:#}", err);
+        }
+        let pruned: Vec<WebhookDelivery> = {
+            let mut dead_letters = self.dead_letters.lock().expect("Dead letter lock poisoned");
+            dead_letters.push_front(delivery);
+            let keep = self.config.max_dead_letters.min(dead_letters.len());
+            dead_letters.drain(keep..).collect()
+        };
+        for delivery in pruned {
+            if let Err(err) = remove_delivery(&self.dead_dir(), delivery.id).await {
+                tracing::warn!("Failed to remove pruned dead letter {}: {:#}", delivery.id, err);
+            }
+        }
+    }
+}
+
+fn delivery_path(dir: &Path, id: Uuid) -> PathBuf {
+    dir.join(format!("{}.json", id))
+}
+
+/// Writes through a temporary file so a crash never leaves a torn delivery behind.
+async fn write_delivery(dir: &Path, delivery: &WebhookDelivery) -> Result<()> {
+    let path = delivery_path(dir, delivery.id);
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec(delivery)?)
+        .await
+        .with_context(|| format!("Failed to write {}", tmp.display()))?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+async fn remove_delivery(dir: &Path, id: Uuid) -> Result<()> {
+    match tokio::fs::remove_file(delivery_path(dir, id)).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+async fn load_deliveries(dir: &Path) -> Result<Vec<WebhookDelivery>> {
+    let mut deliveries = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .with_context(|| format!("Failed to read webhook queue {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let bytes = tokio::fs::read(&path).await?;
+        match serde_json::from_slice(&bytes) {
+            Ok(delivery) => deliveries.push(delivery),
+            Err(err) => tracing::warn!("Skipping unreadable webhook delivery {}: {}", path.display(), err),
+        }
+    }
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+
+    struct Receiver {
+        /// Requests to fail before accepting.
+        failures: AtomicU64,
+        received: mpsc::UnboundedSender<(HeaderMap, Vec<u8>)>,
+    }
+
+    async fn receive(State(receiver): State<Arc<Receiver>>, headers: HeaderMap, body: axum::body::Bytes) -> StatusCode {
+        let failing = receiver
+            .failures
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok();
+        let _ = receiver.received.send((headers, body.to_vec()));
+        if failing {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn spawn_receiver(failures: u64) -> (String, mpsc::UnboundedReceiver<(HeaderMap, Vec<u8>)>) {
+        let (sender, received) = mpsc::unbounded_channel();
+        let receiver = Arc::new(Receiver {
+            failures: AtomicU64::new(failures),
+            received: sender,
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let router = Router::new().route("/hook", post(receive)).with_state(receiver);
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, received)
+    }
+
+    /// Accepts connections and never answers, so every request times out.
+    async fn spawn_hanging_receiver() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+        url
+    }
+
+    fn config(url: &str, max_attempts: u32, name: &str) -> WebhooksConfig {
+        WebhooksConfig {
+            subscriptions: vec![WebhookConfig {
+                name: "receiver".to_string(),
+                url: url.to_string(),
+                secret: "s3cret".to_string(),
+                events: vec![WebhookFilter::Transaction {
+                    venue: None,
+                    symbol: Some("DOGE".to_string()),
+                    side: None,
+                    min_value: None,
+                }],
+            }],
+            queue_dir: std::env::temp_dir().join(format!("webhooks-{}-{}", std::process::id(), name)),
+            max_attempts,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 50,
+            timeout_ms: 1000,
+            max_in_flight: 4,
+            queue_size: 16,
+            workers: 2,
+            max_dead_letters: 16,
+        }
+    }
+
+    fn trade(symbol: &str) -> Transaction {
+        Transaction::new(symbol.to_string(), Decimal::ONE, Decimal::ONE, TradeSide::Buy)
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retries() -> Result<()> {
+        let (url, mut received) = spawn_receiver(2).await;
+        let config = config(&url, 5, "retries");
+        let data_service = DataService::new();
+        let dispatcher = WebhookDispatcher::start(config.clone(), &data_service).await?;
+
+        data_service.process_transaction(&trade("PEPE"))?;
+        data_service.process_transaction(&trade("DOGE"))?;
+
+        let mut ids = HashSet::new();
+        for _ in 0..3 {
+            let (headers, body) = tokio::time::timeout(Duration::from_secs(5), received.recv()).await?.unwrap();
+            let timestamp: i64 = headers["x-webhook-timestamp"].to_str()?.parse()?;
+            let expected = format!("sha256={}", sign_webhook("s3cret", timestamp, &body));
+            assert_eq!(headers["x-webhook-signature"].to_str()?, expected);
+            let body: Value = serde_json::from_slice(&body)?;
+            assert_eq!((body["typ"].as_str(), body["data"]["symbol"].as_str()), (Some("transaction"), Some("DOGE")));
+            ids.insert(headers["x-webhook-id"].to_str()?.to_string());
+        }
+        // Retries keep the delivery id, so receivers can deduplicate.
+        assert_eq!(ids.len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let status = &dispatcher.statuses()[0];
+        assert_eq!((status.delivered, status.failed_attempts, status.retrying), (1, 2, 0));
+        assert_eq!(std::fs::read_dir(config.queue_dir.join("pending"))?.count(), 0);
+        std::fs::remove_dir_all(&config.queue_dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_survive_restart() -> Result<()> {
+        let (url, mut received) = spawn_receiver(u64::MAX).await;
+        let config = config(&url, 2, "dead");
+        let data_service = DataService::new();
+        let dispatcher = WebhookDispatcher::start(config.clone(), &data_service).await?;
+
+        data_service.process_transaction(&trade("DOGE"))?;
+        for _ in 0..2 {
+            tokio::time::timeout(Duration::from_secs(5), received.recv()).await?.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let dead = dispatcher.dead_letters();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].attempts, 2);
+        assert!(dead[0].last_error.as_deref().unwrap().contains("500"));
+
+        let restarted = WebhookDispatcher::start(config.clone(), &DataService::new()).await?;
+        assert_eq!(restarted.dead_letters()[0].id, dead[0].id);
+        assert!(restarted.delete_dead_letter(dead[0].id).await?);
+        assert!(restarted.dead_letters().is_empty());
+        assert_eq!(std::fs::read_dir(config.queue_dir.join("dead"))?.count(), 0);
+        std::fs::remove_dir_all(&config.queue_dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_dead_letters_and_cap() -> Result<()> {
+        let url = spawn_hanging_receiver().await;
+        let mut config = config(&url, 1, "full");
+        config.timeout_ms = 200;
+        config.queue_size = 1;
+        config.workers = 1;
+        config.max_dead_letters = 2;
+        let data_service = DataService::new();
+        let dispatcher = WebhookDispatcher::start(config.clone(), &data_service).await?;
+
+        data_service.process_transaction(&trade("DOGE"))?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        // Written to the queue before the first attempt.
+        assert_eq!(std::fs::read_dir(config.queue_dir.join("pending"))?.count(), 1);
+
+        for _ in 0..4 {
+            data_service.process_transaction(&trade("DOGE"))?;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let dead = dispatcher.dead_letters();
+        assert_eq!(dead.len(), 2);
+        assert!(dead.iter().all(|d| d.attempts == 0 && d.last_error.as_deref() == Some("Webhook queue is full")));
+        assert_eq!(dispatcher.statuses()[0].queued, 1);
+
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        let dead = dispatcher.dead_letters();
+        assert_eq!(dead.len(), 2);
+        assert!(dead.iter().all(|d| d.attempts == 1));
+        assert_eq!(std::fs::read_dir(config.queue_dir.join("dead"))?.count(), 2);
+        assert_eq!(std::fs::read_dir(config.queue_dir.join("pending"))?.count(), 0);
+        std::fs::remove_dir_all(&config.queue_dir)?;
+        Ok(())
+    }
+}
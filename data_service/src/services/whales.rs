@@ -0,0 +1,214 @@
+use super::consolidated::CONSOLIDATED_VENUE;
+use crate::models::{Transaction, WhaleTrade};
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// Whale trades kept for the REST history, across all symbols.
+const MAX_WHALE_HISTORY: usize = 1000;
+
+/// When a symbol's trades count as whale trades. Set exactly one of `min_value` and
+/// `percentile`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhaleConfig {
+    pub symbol: String,
+    /// Fixed threshold on `Transaction::total_value()`.
+    #[serde(default)]
+    pub min_value: Option<Decimal>,
+    /// Threshold as a percentile (0-100) of the values of the last `window` trades.
+    #[serde(default)]
+    pub percentile: Option<f64>,
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Trades needed in the window before a percentile threshold applies.
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+}
+
+fn default_window() -> usize {
+    1000
+}
+
+fn default_min_samples() -> usize {
+    100
+}
+
+impl WhaleConfig {
+    pub fn validate_all(configs: &[WhaleConfig]) -> Result<()> {
+        let mut seen = HashSet::new();
+        for config in configs {
+            if !seen.insert(config.symbol.as_str()) {
+                bail!("Whale threshold for {} is configured more than once", config.symbol);
+            }
+            match (config.min_value, config.percentile) {
+                (Some(min_value), None) if min_value > Decimal::ZERO => {}
+                (None, Some(percentile)) if percentile > 0.0 && percentile < 100.0 => {
+                    if config.window == 0 || config.min_samples > config.window {
+                        bail!("Whale threshold for {} needs 0 < min_samples <= window", config.symbol);
+                    }
+                }
+                _ => bail!(
+                    "Whale threshold for {} needs either a positive min_value or a percentile between 0 and 100",
+                    config.symbol
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct WhaleWatch {
+    config: WhaleConfig,
+    /// Values of the most recent trades, oldest first. Only kept for percentile thresholds.
+    recent: VecDeque<Decimal>,
+}
+
+impl WhaleWatch {
+    fn threshold(&self) -> Option<Decimal> {
+        if let Some(min_value) = self.config.min_value {
+            return Some(min_value);
+        }
+        let percentile = self.config.percentile?;
+        if self.recent.is_empty() || self.recent.len() < self.config.min_samples {
+            return None;
+        }
+        let mut values: Vec<Decimal> = self.recent.iter().copied().collect();
+        values.sort();
+        // Nearest-rank percentile.
+        let rank = (percentile / 100.0 * values.len() as f64).ceil() as usize;
+        Some(values[rank.clamp(1, values.len()) - 1])
+    }
+
+    fn remember(&mut self, value: Decimal) {
+        if self.config.percentile.is_none() {
+            return;
+        }
+        self.recent.push_back(value);
+        if self.recent.len() > self.config.window {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Flags trades above a per-symbol value threshold and keeps a history of them.
+#[derive(Default)]
+pub struct WhaleDetector {
+    watches: HashMap<String, Mutex<WhaleWatch>>,
+    history: Mutex<VecDeque<WhaleTrade>>,
+}
+
+impl WhaleDetector {
+    pub fn new(configs: Vec<WhaleConfig>) -> Self {
+        let watches = configs
+            .into_iter()
+            .map(|config| {
+                let recent = VecDeque::with_capacity(config.window.min(default_window()));
+                (config.symbol.clone(), Mutex::new(WhaleWatch { config, recent }))
+            })
+            .collect();
+        Self {
+            watches,
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns the trade as a whale trade if it crosses its symbol's threshold. A
+    /// percentile threshold is taken over the trades before this one.
+    pub fn on_trade(&self, transaction: &Transaction) -> Option<WhaleTrade> {
+        // Consolidated trades repeat the venue trades they were built from.
+        if transaction.venue.as_deref() == Some(CONSOLIDATED_VENUE) {
+            return None;
+        }
+        let watch = self.watches.get(&transaction.symbol)?;
+        let value = transaction.total_value();
+        let threshold = {
+            let mut watch = watch.lock().expect("Whale watch lock poisoned");
+            let threshold = watch.threshold();
+            watch.remember(value);
+            threshold?
+        };
+        if value < threshold || value.is_zero() {
+            return None;
+        }
+
+        let whale = WhaleTrade {
+            transaction: transaction.clone(),
+            value,
+            threshold,
+        };
+        let mut history = self.history.lock().expect("Whale history lock poisoned");
+        history.push_back(whale.clone());
+        if history.len() > MAX_WHALE_HISTORY {
+            history.pop_front();
+        }
+        Some(whale)
+    }
+
+    /// Most recent whale trades, newest first, optionally for one symbol.
+    pub fn recent(&self, symbol: Option<&str>, limit: usize) -> Vec<WhaleTrade> {
+        let history = self.history.lock().expect("Whale history lock poisoned");
+        history
+            .iter()
+            .rev()
+            .filter(|whale| symbol.is_none_or(|symbol| whale.transaction.symbol == symbol))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TradeSide;
+
+    fn trade(symbol: &str, price: i64, volume: i64) -> Transaction {
+        Transaction::new(symbol.to_string(), Decimal::new(price, 0), Decimal::new(volume, 0), TradeSide::Buy)
+    }
+
+    fn config(symbol: &str) -> WhaleConfig {
+        WhaleConfig {
+            symbol: symbol.to_string(),
+            min_value: None,
+            percentile: None,
+            window: 10,
+            min_samples: 5,
+        }
+    }
+
+    #[test]
+    fn test_absolute_and_percentile_thresholds() {
+        let detector = WhaleDetector::new(vec![
+            WhaleConfig {
+                min_value: Some(Decimal::new(1000, 0)),
+                ..config("DOGE")
+            },
+            WhaleConfig {
+                percentile: Some(90.0),
+                ..config("PEPE")
+            },
+        ]);
+
+        assert!(detector.on_trade(&trade("DOGE", 10, 99)).is_none());
+        let whale = detector.on_trade(&trade("DOGE", 10, 100)).unwrap();
+        assert_eq!((whale.value, whale.threshold), (Decimal::new(1000, 0), Decimal::new(1000, 0)));
+        assert!(detector.on_trade(&trade("SHIB", 10, 1_000_000)).is_none());
+
+        // No percentile threshold until five trades are in the window.
+        for volume in 1..=5 {
+            assert!(detector.on_trade(&trade("PEPE", 1, volume * 100)).is_none());
+        }
+        assert!(detector.on_trade(&trade("PEPE", 1, 400)).is_none());
+        let whale = detector.on_trade(&trade("PEPE", 1, 600)).unwrap();
+        assert_eq!(whale.threshold, Decimal::new(500, 0));
+
+        let recent = detector.recent(None, 10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].transaction.symbol, "PEPE");
+        assert_eq!(detector.recent(Some("DOGE"), 10).len(), 1);
+
+        assert!(WhaleConfig::validate_all(&[config("DOGE")]).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use super::{envelope, forward_stream};
+use crate::services::DataService;
+use axum::{
+    extract::{Query, State, WebSocketUpgrade},
+    response::Response,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct AlertStreamQuery {
+    /// Only alerts for this symbol; every alert when omitted.
+    symbol: Option<String>,
+}
+
+/// Alerts from every registered rule.
+pub async fn ws_alert_handler(
+    Query(query): Query<AlertStreamQuery>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_alert_socket(socket, data_service, query.symbol))
+}
+
+async fn handle_alert_socket(
+    socket: axum::extract::ws::WebSocket,
+    data_service: Arc<DataService>,
+    symbol: Option<String>,
+) {
+    let rx = data_service.subscribe_alerts();
+    forward_stream(socket, rx, |alert| {
+        if symbol.as_ref().is_none_or(|symbol| alert.symbol == *symbol) {
+            envelope("alert", alert)
+        } else {
+            None
+        }
+    })
+    .await;
+}
@@ -0,0 +1,67 @@
+use super::{envelope, forward_stream};
+use crate::models::BarType;
+use crate::services::DataService;
+use axum::{
+    extract::{Path, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+/// Tick, volume or dollar bars for `symbol` on the default venue.
+pub async fn ws_bar_handler(
+    Path((symbol, bar_type, threshold)): Path<(String, String, String)>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let venue = data_service.default_venue().to_string();
+    upgrade(ws, data_service, venue, symbol, bar_type, threshold)
+}
+
+pub async fn ws_venue_bar_handler(
+    Path((venue, symbol, bar_type, threshold)): Path<(String, String, String, String)>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    upgrade(ws, data_service, venue, symbol, bar_type, threshold)
+}
+
+fn upgrade(
+    ws: WebSocketUpgrade,
+    data_service: Arc<DataService>,
+    venue: String,
+    symbol: String,
+    bar_type: String,
+    threshold: String,
+) -> Response {
+    let Some(bar_type) = BarType::from_str(&bar_type) else {
+        return (StatusCode::BAD_REQUEST, "Invalid bar type").into_response();
+    };
+    let Ok(threshold) = threshold.parse::<Decimal>() else {
+        return (StatusCode::BAD_REQUEST, "Invalid threshold").into_response();
+    };
+    if !data_service.bars_enabled(&symbol, bar_type, threshold) {
+        return (StatusCode::NOT_FOUND, "No bars configured for symbol, type and threshold").into_response();
+    }
+    ws.on_upgrade(move |socket| handle_bar_socket(socket, data_service, venue, symbol, bar_type, threshold))
+}
+
+async fn handle_bar_socket(
+    socket: axum::extract::ws::WebSocket,
+    data_service: Arc<DataService>,
+    venue: String,
+    symbol: String,
+    bar_type: BarType,
+    threshold: Decimal,
+) {
+    let rx = data_service.subscribe_bars();
+    forward_stream(socket, rx, |bar| {
+        if bar.venue == venue && bar.symbol == symbol && bar.bar_type == bar_type && bar.threshold == threshold {
+            envelope("kline", bar)
+        } else {
+            None
+        }
+    })
+    .await;
+}
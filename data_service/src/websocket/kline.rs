@@ -1,96 +1,148 @@
-use crate::models::KLineInterval;
-use crate::services::DataService;
-use anyhow::{Context, Result};
+use super::{envelope, forward_stream};
+use crate::models::{ChartType, KLineInterval, MarketCapKLine, Ohlc, PriceUnit, Transaction};
+use crate::services::{
+    validate_box_size, DataService, HeikinAshiStream, RenkoBuilder, RenkoStream, TradePrint,
+};
+use anyhow::Context;
 use axum::{
-    extract::{Path, State, WebSocketUpgrade, ws::Utf8Bytes},
+    extract::{Path, Query, State, WebSocketUpgrade},
     response::{IntoResponse, Response},
 };
-use futures::{sink::SinkExt, stream::StreamExt};
-use serde_json::json;
-use std::{sync::Arc, time::Duration};
-use tokio::{select, time};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
 
-const PING_INTERVAL: Duration = Duration::from_secs(5);
-const PING_TIMEOUT: Duration = Duration::from_secs(10);
+#[derive(Deserialize)]
+pub struct KLineStreamQuery {
+    #[serde(default)]
+    unit: PriceUnit,
+    #[serde(default)]
+    chart_type: ChartType,
+    /// Brick size for Renko charts, in price units.
+    box_size: Option<Decimal>,
+}
 
+/// K-lines for `symbol` on the default venue.
 pub async fn ws_kline_handler(
     Path((symbol, interval)): Path<(String, String)>,
+    Query(query): Query<KLineStreamQuery>,
+    State(kline_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let venue = kline_service.default_venue().to_string();
+    upgrade(ws, kline_service, venue, symbol, interval, query)
+}
+
+pub async fn ws_venue_kline_handler(
+    Path((venue, symbol, interval)): Path<(String, String, String)>,
+    Query(query): Query<KLineStreamQuery>,
     State(kline_service): State<Arc<DataService>>,
     ws: WebSocketUpgrade,
 ) -> Response {
+    upgrade(ws, kline_service, venue, symbol, interval, query)
+}
+
+fn upgrade(
+    ws: WebSocketUpgrade,
+    kline_service: Arc<DataService>,
+    venue: String,
+    symbol: String,
+    interval: String,
+    query: KLineStreamQuery,
+) -> Response {
+    let unit = query.unit;
     let interval = match KLineInterval::from_str(&interval) {
         Some(interval) => interval,
         None => return (axum::http::StatusCode::BAD_REQUEST, "Invalid interval").into_response(),
     };
+    if unit == PriceUnit::Mcap && kline_service.symbol_metadata(&symbol).is_none() {
+        return (axum::http::StatusCode::BAD_REQUEST, "No supply metadata for symbol").into_response();
+    }
+
+    if query.chart_type == ChartType::Renko {
+        let box_size = query.box_size.unwrap_or_default();
+        // Subscribed first, so no trade falls between the history and the stream
+        let rx = kline_service.subscribe_transactions();
+        let history = kline_service.recent_trades(&venue, &symbol);
+        if let Err(err) = validate_box_size(box_size, &history) {
+            return (axum::http::StatusCode::BAD_REQUEST, format!("{:#}", err)).into_response();
+        }
+        // Trades queued for the stream so far may be in the history too
+        let renko = RenkoStream::new(RenkoBuilder::new(venue.clone(), symbol.clone(), box_size), &history, rx.len());
+        return ws.on_upgrade(move |socket| handle_renko_socket(socket, kline_service, rx, venue, symbol, renko, unit));
+    }
+
+    let heikin_ashi = query.chart_type == ChartType::HeikinAshi;
+    ws.on_upgrade(move |socket| handle_socket(socket, kline_service, venue, symbol, interval, unit, heikin_ashi))
+}
 
-    let symbol = symbol.clone();
-    ws.on_upgrade(move |socket| handle_socket(socket, kline_service, symbol, interval))
+/// Serializes a kline message for a candle or brick of `symbol` in the requested unit.
+/// Supply is looked up per message so metadata updates apply to open streams.
+fn kline_message<T: Ohlc + Serialize>(
+    kline_service: &DataService,
+    symbol: &str,
+    kline: T,
+    unit: PriceUnit,
+) -> anyhow::Result<String> {
+    let data = match unit {
+        PriceUnit::Price => serde_json::to_value(kline)?,
+        PriceUnit::Mcap => {
+            let metadata = kline_service
+                .symbol_metadata(symbol)
+                .context("Supply metadata was removed")?;
+            serde_json::to_value(MarketCapKLine::new(&kline, &metadata))?
+        }
+    };
+    envelope("kline", data).context("Failed to serialize kline message")
 }
 
 async fn handle_socket(
     socket: axum::extract::ws::WebSocket,
     kline_service: Arc<DataService>,
+    venue: String,
     symbol: String,
     interval: KLineInterval,
+    unit: PriceUnit,
+    heikin_ashi: bool,
 ) {
-    let (mut sender, mut receiver) = socket.split();
-    let mut rx = kline_service.subscribe();
-    let mut ping_interval = time::interval(PING_INTERVAL);
-    let mut last_ping_time = None;
-
-    loop {
-        select! {
-            // Handle incoming WebSocket messages
-            msg = receiver.next() => {
-                match msg {
-                    Some(Ok(axum::extract::ws::Message::Pong(_))) => {
-                        last_ping_time = None;
-                    }
-                    Some(Ok(axum::extract::ws::Message::Close(_))) => {
-                        break;
-                    }
-                    None => break,
-                    _ => {}
-                }
-            }
-
-            // Handle K-line updates
-            Ok(kline) = rx.recv() => {
-                if kline.symbol == symbol && kline.interval == interval {
-                    let msg = json!({
-                        "typ": "kline",
-                        "data": kline
-                    });
-
-                    if let Ok(text) = serde_json::to_string(&msg).context("Failed to serialize kline message") {
-                        let message = axum::extract::ws::Message::Text(text.into());
-                        if sender.send(message).await.is_err() {
-                            break;
-                        }
-                    }
-                }
-            }
+    let rx = kline_service.subscribe();
+    // Subscribed first, so no candle closes between the history and the stream
+    let mut heikin_ashi = heikin_ashi
+        .then(|| HeikinAshiStream::new(&kline_service.get_venue_klines(&venue, &symbol, interval, usize::MAX)));
 
-            // Send periodic pings
-            _ = ping_interval.tick() => {
-                // If we haven't received a pong since our last ping, disconnect
-                if last_ping_time.is_some() {
-                    break;
-                }
-
-                let ping_message = axum::extract::ws::Message::Ping(Vec::new().into());
-                if sender.send(ping_message).await.is_err() {
-                    break;
-                }
-                last_ping_time = Some(time::Instant::now());
-            }
+    forward_stream(socket, rx, |kline| {
+        if kline.venue != venue || kline.symbol != symbol || kline.interval != interval {
+            return None;
         }
+        let kline = match heikin_ashi.as_mut() {
+            Some(heikin_ashi) => heikin_ashi.on_kline(&kline)?,
+            None => kline,
+        };
+        kline_message(&kline_service, &symbol, kline, unit).ok()
+    })
+    .await;
+}
 
-        // Check ping timeout
-        if let Some(ping_time) = last_ping_time {
-            if ping_time.elapsed() > PING_TIMEOUT {
-                break;
-            }
+/// Streams Renko bricks built from the trade stream, on top of the trades kept so far.
+async fn handle_renko_socket(
+    socket: axum::extract::ws::WebSocket,
+    kline_service: Arc<DataService>,
+    rx: broadcast::Receiver<Transaction>,
+    venue: String,
+    symbol: String,
+    mut renko: RenkoStream,
+    unit: PriceUnit,
+) {
+    forward_stream(socket, rx, |transaction| {
+        if transaction.venue.as_deref() != Some(venue.as_str()) || transaction.symbol != symbol {
+            return Vec::new();
         }
-    }
-} 
\ No newline at end of file
+        renko
+            .on_trade(&TradePrint::from(&transaction))
+            .into_iter()
+            .filter_map(|brick| kline_message(&kline_service, &symbol, brick, unit).ok())
+            .collect()
+    })
+    .await;
+}
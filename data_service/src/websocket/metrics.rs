@@ -0,0 +1,58 @@
+use super::{envelope, forward_stream};
+use crate::models::KLineInterval;
+use crate::services::DataService;
+use axum::{
+    extract::{Path, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Per-candle wallet metrics for `symbol` on the default venue.
+pub async fn ws_metrics_handler(
+    Path((symbol, interval)): Path<(String, String)>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let venue = data_service.default_venue().to_string();
+    upgrade(ws, data_service, venue, symbol, interval)
+}
+
+pub async fn ws_venue_metrics_handler(
+    Path((venue, symbol, interval)): Path<(String, String, String)>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    upgrade(ws, data_service, venue, symbol, interval)
+}
+
+fn upgrade(
+    ws: WebSocketUpgrade,
+    data_service: Arc<DataService>,
+    venue: String,
+    symbol: String,
+    interval: String,
+) -> Response {
+    let Some(interval) = KLineInterval::from_str(&interval) else {
+        return (StatusCode::BAD_REQUEST, "Invalid interval").into_response();
+    };
+    ws.on_upgrade(move |socket| handle_metrics_socket(socket, data_service, venue, symbol, interval))
+}
+
+async fn handle_metrics_socket(
+    socket: axum::extract::ws::WebSocket,
+    data_service: Arc<DataService>,
+    venue: String,
+    symbol: String,
+    interval: KLineInterval,
+) {
+    let rx = data_service.subscribe_trader_metrics();
+    forward_stream(socket, rx, |metrics| {
+        if metrics.venue == venue && metrics.symbol == symbol && metrics.interval == interval {
+            envelope("metrics", metrics)
+        } else {
+            None
+        }
+    })
+    .await;
+}
@@ -1,5 +1,99 @@
+use axum::extract::ws::{Message, WebSocket};
+use futures::{sink::SinkExt, stream::StreamExt};
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+use tokio::{
+    select,
+    sync::broadcast::{self, error::RecvError},
+    time,
+};
+
+mod alerts;
+mod bars;
+mod footprint;
+mod metrics;
+mod patterns;
+mod ticker;
 mod transactions;
+mod whales;
 pub mod kline;
 
-pub use transactions::ws_transaction_handler; 
-pub use kline::ws_kline_handler; 
\ No newline at end of file
+pub use alerts::ws_alert_handler;
+pub use bars::{ws_bar_handler, ws_venue_bar_handler};
+pub use footprint::{ws_footprint_handler, ws_venue_footprint_handler};
+pub use kline::{ws_kline_handler, ws_venue_kline_handler};
+pub use metrics::{ws_metrics_handler, ws_venue_metrics_handler};
+pub use patterns::ws_pattern_handler;
+pub use ticker::ws_ticker_handler;
+pub use transactions::{ws_transaction_handler, ws_venue_transaction_handler};
+pub use whales::ws_whale_handler;
+
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wraps `data` in the `{"typ", "data"}` envelope of every stream.
+fn envelope(typ: &str, data: impl Serialize) -> Option<String> {
+    serde_json::to_string(&json!({ "typ": typ, "data": data })).ok()
+}
+
+/// Forwards updates from `rx` until the client closes the socket, stops answering pings
+/// or can't be written to. `messages` turns an update into the messages to send, none
+/// for updates the client didn't ask for. Updates a slow client lagged behind on are
+/// skipped.
+async fn forward_stream<T, M>(socket: WebSocket, mut rx: broadcast::Receiver<T>, mut messages: impl FnMut(T) -> M)
+where
+    T: Clone,
+    M: IntoIterator<Item = String>,
+{
+    let (mut sender, mut receiver) = socket.split();
+    let mut ping_interval = time::interval(PING_INTERVAL);
+    let mut last_ping_time = None;
+
+    loop {
+        select! {
+            // Handle incoming WebSocket messages
+            msg = receiver.next() => {
+                match msg {
+                    Some(Ok(Message::Pong(_))) => {
+                        last_ping_time = None;
+                    }
+                    Some(Ok(Message::Close(_))) | None => break,
+                    _ => {}
+                }
+            }
+
+            update = rx.recv() => {
+                match update {
+                    Ok(update) => {
+                        for text in messages(update) {
+                            if sender.send(Message::Text(text.into())).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+
+            // Send periodic pings
+            _ = ping_interval.tick() => {
+                // If we haven't received a pong since our last ping, disconnect
+                if last_ping_time.is_some() {
+                    break;
+                }
+
+                if sender.send(Message::Ping(Vec::new().into())).await.is_err() {
+                    break;
+                }
+                last_ping_time = Some(time::Instant::now());
+            }
+        }
+
+        // Check ping timeout
+        if last_ping_time.is_some_and(|ping_time: time::Instant| ping_time.elapsed() > PING_TIMEOUT) {
+            break;
+        }
+    }
+}
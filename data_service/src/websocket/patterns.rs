@@ -0,0 +1,51 @@
+use super::{envelope, forward_stream};
+use crate::models::KLineInterval;
+use crate::services::DataService;
+use axum::{
+    extract::{Query, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct PatternStreamQuery {
+    /// Only patterns for this symbol; every symbol when omitted.
+    symbol: Option<String>,
+    /// Only patterns on this interval; every interval when omitted.
+    interval: Option<String>,
+}
+
+/// Candlestick patterns completed by closed candles.
+pub async fn ws_pattern_handler(
+    Query(query): Query<PatternStreamQuery>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let interval = match query.interval.as_deref().map(KLineInterval::from_str) {
+        Some(None) => return (StatusCode::BAD_REQUEST, "Invalid interval").into_response(),
+        Some(interval) => interval,
+        None => None,
+    };
+    ws.on_upgrade(move |socket| handle_pattern_socket(socket, data_service, query.symbol, interval))
+}
+
+async fn handle_pattern_socket(
+    socket: axum::extract::ws::WebSocket,
+    data_service: Arc<DataService>,
+    symbol: Option<String>,
+    interval: Option<KLineInterval>,
+) {
+    let rx = data_service.subscribe_patterns();
+    forward_stream(socket, rx, |event| {
+        if symbol.as_ref().is_none_or(|symbol| event.symbol == *symbol)
+            && interval.is_none_or(|interval| event.interval == interval)
+        {
+            envelope("pattern", event)
+        } else {
+            None
+        }
+    })
+    .await;
+}
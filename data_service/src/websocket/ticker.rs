@@ -0,0 +1,32 @@
+use super::{envelope, forward_stream};
+use crate::services::DataService;
+use axum::{
+    extract::{Path, State, WebSocketUpgrade},
+    response::Response,
+};
+use std::sync::Arc;
+
+/// Index ticker updates for a consolidated pseudo-symbol such as `DOGE.IDX`.
+pub async fn ws_ticker_handler(
+    Path(symbol): Path<String>,
+    State(data_service): State<Arc<DataService>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_ticker_socket(socket, data_service, symbol))
+}
+
+async fn handle_ticker_socket(
+    socket: axum::extract::ws::WebSocket,
+    data_service: Arc<DataService>,
+    symbol: String,
+) {
+    let rx = data_service.subscribe_tickers();
+    forward_stream(socket, rx, |ticker| {
+        if ticker.symbol == symbol {
+            envelope("ticker", ticker)
+        } else {
+            None
+        }
+    })
+    .await;
+}
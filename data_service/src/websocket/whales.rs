@@ -0,0 +1,16 @@
+use super::{envelope, forward_stream};
+use crate::services::DataService;
+use axum::{
+    extract::{State, WebSocketUpgrade},
+    response::Response,
+};
+use std::sync::Arc;
+
+/// Whale trades across all symbols.
+pub async fn ws_whale_handler(State(data_service): State<Arc<DataService>>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_whale_socket(socket, data_service))
+}
+
+async fn handle_whale_socket(socket: axum::extract::ws::WebSocket, data_service: Arc<DataService>) {
+    forward_stream(socket, data_service.subscribe_whales(), |whale| envelope("whale", whale)).await;
+}
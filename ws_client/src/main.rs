@@ -1,17 +1,19 @@
 use anyhow::Context;
-use futures_util::StreamExt;
 use data_service::models::{KLine, Transaction};
+use futures_util::StreamExt;
 use serde::Deserialize;
 use std::env;
 use std::net::SocketAddr;
 use tokio::task::JoinSet;
 use tokio_tungstenite::tungstenite::Message;
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct KLineData {
     typ: String,
     data: KLine,
 }
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct TxData {
     typ: String,
@@ -35,12 +37,9 @@ async fn main() -> anyhow::Result<()> {
                     .unwrap();
             loop {
                 let msg = socket.next().await.unwrap().unwrap();
-                match msg {
-                    Message::Text(msg) => {
-                        let data = serde_json::from_slice::<KLineData>(msg.as_ref());
-                        println!("{:?}", data);
-                    }
-                    _ => {}
+                if let Message::Text(msg) = msg {
+                    let data = serde_json::from_slice::<KLineData>(msg.as_ref());
+                    println!("{:?}", data);
                 }
             }
         });
@@ -52,12 +51,9 @@ async fn main() -> anyhow::Result<()> {
                     .unwrap();
             loop {
                 let msg = socket.next().await.unwrap().unwrap();
-                match msg {
-                    Message::Text(msg) => {
-                        let data = serde_json::from_slice::<TxData>(msg.as_ref());
-                        println!("{:?}", data);
-                    }
-                    _ => {}
+                if let Message::Text(msg) = msg {
+                    let data = serde_json::from_slice::<TxData>(msg.as_ref());
+                    println!("{:?}", data);
                 }
             }
         });